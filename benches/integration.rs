@@ -2,8 +2,11 @@ use criterion::{Criterion, criterion_group, criterion_main};
 use std::fs;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use taut::blocks::FileBlocks;
 use taut::discovery;
+use taut::markers::{Marker, MarkerArgs};
 use taut::runner::{self, IsolationMode};
+use taut::selection::TestSelector;
 
 mod fixtures;
 use fixtures::FixtureProject;
@@ -26,6 +29,10 @@ criterion_group!(
              bench_execution_process_per_run,
              bench_execution_realistic_ppe,
              bench_execution_realistic_ppr,
+             bench_index_medium_uncached,
+             bench_index_medium_cached,
+             bench_file_affinity_medium,
+             bench_coverage_overhead_ppr,
 );
 criterion_main!(benches);
 
@@ -37,7 +44,7 @@ fn bench_cold_run_small(c: &mut Criterion) {
             || FixtureProject::small(),
             |fixture| {
                 let project_dir = vec![fixture.dir.path().to_path_buf()];
-                let _ = discovery::extract_tests(&project_dir, None);
+                let _ = discovery::extract_tests(&project_dir, &[]);
             },
             criterion::BatchSize::SmallInput,
         );
@@ -52,7 +59,7 @@ fn bench_cold_run_medium(c: &mut Criterion) {
             || FixtureProject::medium(),
             |fixture| {
                 let project_dir = vec![fixture.dir.path().to_path_buf()];
-                let _ = discovery::extract_tests(&project_dir, None);
+                let _ = discovery::extract_tests(&project_dir, &[]);
             },
             criterion::BatchSize::SmallInput,
         );
@@ -68,9 +75,9 @@ fn bench_warm_run_small(c: &mut Criterion) {
             |fixture| {
                 let project_dir = vec![fixture.dir.path().to_path_buf()];
                 // First run to populate data
-                let _ = discovery::extract_tests(&project_dir, None);
+                let _ = discovery::extract_tests(&project_dir, &[]);
                 // Second run is "warm"
-                let _ = discovery::extract_tests(&project_dir, None);
+                let _ = discovery::extract_tests(&project_dir, &[]);
             },
             criterion::BatchSize::SmallInput,
         );
@@ -86,9 +93,9 @@ fn bench_warm_run_medium(c: &mut Criterion) {
             |fixture| {
                 let project_dir = vec![fixture.dir.path().to_path_buf()];
                 // First run to populate data
-                let _ = discovery::extract_tests(&project_dir, None);
+                let _ = discovery::extract_tests(&project_dir, &[]);
                 // Second run is "warm"
-                let _ = discovery::extract_tests(&project_dir, None);
+                let _ = discovery::extract_tests(&project_dir, &[]);
             },
             criterion::BatchSize::SmallInput,
         );
@@ -113,7 +120,7 @@ fn bench_incremental_small(c: &mut Criterion) {
             |fixture| {
                 let project_dir = vec![fixture.dir.path().to_path_buf()];
                 // Rerun after modification
-                let _ = discovery::extract_tests(&project_dir, None);
+                let _ = discovery::extract_tests(&project_dir, &[]);
             },
             criterion::BatchSize::SmallInput,
         );
@@ -138,7 +145,7 @@ fn bench_incremental_medium(c: &mut Criterion) {
             |fixture| {
                 let project_dir = vec![fixture.dir.path().to_path_buf()];
                 // Rerun after modification
-                let _ = discovery::extract_tests(&project_dir, None);
+                let _ = discovery::extract_tests(&project_dir, &[]);
             },
             criterion::BatchSize::SmallInput,
         );
@@ -154,7 +161,7 @@ fn bench_filtered_small(c: &mut Criterion) {
             |fixture| {
                 let project_dir = vec![fixture.dir.path().to_path_buf()];
                 // Filter to match ~10% of tests (pattern that matches some but not all)
-                let _ = discovery::extract_tests(&project_dir, Some("test_api"));
+                let _ = discovery::extract_tests(&project_dir, &["test_api".to_string()]);
             },
             criterion::BatchSize::SmallInput,
         );
@@ -170,7 +177,7 @@ fn bench_filtered_medium(c: &mut Criterion) {
             |fixture| {
                 let project_dir = vec![fixture.dir.path().to_path_buf()];
                 // Filter to match ~10% of tests
-                let _ = discovery::extract_tests(&project_dir, Some("test_api"));
+                let _ = discovery::extract_tests(&project_dir, &["test_api".to_string()]);
             },
             criterion::BatchSize::SmallInput,
         );
@@ -188,7 +195,7 @@ fn bench_noop_overhead(c: &mut Criterion) {
             |fixture| {
                 let project_dir = vec![fixture.dir.path().to_path_buf()];
                 // Discover noop tests (minimal execution needed)
-                let _ = discovery::extract_tests(&project_dir, None);
+                let _ = discovery::extract_tests(&project_dir, &[]);
             },
             criterion::BatchSize::SmallInput,
         );
@@ -204,7 +211,9 @@ fn bench_execution_process_per_test(c: &mut Criterion) {
             || FixtureProject::noop(),
             |fixture| {
                 let project_dir = vec![fixture.dir.path().to_path_buf()];
-                let tests = discovery::extract_tests(&project_dir, None).unwrap_or_default();
+                let tests = discovery::extract_tests(&project_dir, &[])
+                    .map(|collection| collection.tests)
+                    .unwrap_or_default();
 
                 let counter = Arc::new(AtomicUsize::new(0));
                 let _ = runner::run_tests(
@@ -232,7 +241,9 @@ fn bench_execution_process_per_run(c: &mut Criterion) {
             || FixtureProject::noop(),
             |fixture| {
                 let project_dir = vec![fixture.dir.path().to_path_buf()];
-                let tests = discovery::extract_tests(&project_dir, None).unwrap_or_default();
+                let tests = discovery::extract_tests(&project_dir, &[])
+                    .map(|collection| collection.tests)
+                    .unwrap_or_default();
 
                 let counter = Arc::new(AtomicUsize::new(0));
                 let _ = runner::run_tests(
@@ -259,7 +270,9 @@ fn bench_execution_realistic_ppe(c: &mut Criterion) {
             || FixtureProject::realistic(),
             |fixture| {
                 let project_dir = vec![fixture.dir.path().to_path_buf()];
-                let tests = discovery::extract_tests(&project_dir, None).unwrap_or_default();
+                let tests = discovery::extract_tests(&project_dir, &[])
+                    .map(|collection| collection.tests)
+                    .unwrap_or_default();
 
                 let counter = Arc::new(AtomicUsize::new(0));
                 let _ = runner::run_tests(
@@ -286,7 +299,9 @@ fn bench_execution_realistic_ppr(c: &mut Criterion) {
             || FixtureProject::realistic(),
             |fixture| {
                 let project_dir = vec![fixture.dir.path().to_path_buf()];
-                let tests = discovery::extract_tests(&project_dir, None).unwrap_or_default();
+                let tests = discovery::extract_tests(&project_dir, &[])
+                    .map(|collection| collection.tests)
+                    .unwrap_or_default();
 
                 let counter = Arc::new(AtomicUsize::new(0));
                 let _ = runner::run_tests(
@@ -304,3 +319,106 @@ fn bench_execution_realistic_ppr(c: &mut Criterion) {
         );
     });
 }
+
+/// **Workflow 10: Warm Indexing without Block Cache (Medium)**
+/// Re-parse every Python file, as `index_files` does on a cold cache
+fn bench_index_medium_uncached(c: &mut Criterion) {
+    let fixture = FixtureProject::medium();
+
+    c.bench_function("index_medium_uncached", |b| {
+        b.iter(|| {
+            for file in &fixture.test_files {
+                let _ = FileBlocks::from_file(&file.canonicalize().unwrap());
+            }
+        });
+    });
+}
+
+/// **Workflow 11: Warm Indexing with Block Cache (Medium)**
+/// Index after a previous run persisted the block index; unchanged files are not re-parsed
+fn bench_index_medium_cached(c: &mut Criterion) {
+    let fixture = FixtureProject::medium();
+    let project_dir = vec![fixture.dir.path().to_path_buf()];
+
+    let cache_dir = fixture.dir.path().join(".taut-cache");
+
+    // Prime the on-disk block index
+    let mut selector = TestSelector::new(&cache_dir, None);
+    selector.index_files(&project_dir);
+    selector.save();
+
+    c.bench_function("index_medium_cached", |b| {
+        b.iter(|| {
+            let mut selector = TestSelector::new(&cache_dir, None);
+            selector.index_files(&project_dir);
+        });
+    });
+}
+
+/// **Workflow 12: Worker File Affinity (Medium)**
+/// Process-per-run pool over the medium suite, with and without `--file-affinity`
+fn bench_file_affinity_medium(c: &mut Criterion) {
+    let mut group = c.benchmark_group("file_affinity_medium");
+    for affinity in [false, true] {
+        group.bench_function(if affinity { "on" } else { "off" }, |b| {
+            b.iter_batched(
+                || {
+                    let fixture = FixtureProject::medium();
+                    let project_dir = vec![fixture.dir.path().to_path_buf()];
+                    let mut tests = discovery::extract_tests(&project_dir, &[])
+                        .map(|collection| collection.tests)
+                        .unwrap_or_default();
+                    // Only @parallel tests are spread across the pool
+                    for test in &mut tests {
+                        test.markers.push(Marker {
+                            name: "parallel".to_string(),
+                            args: MarkerArgs::default(),
+                        });
+                    }
+                    (fixture, tests)
+                },
+                |(_fixture, tests)| {
+                    let options = runner::RunOptions {
+                        isolation: IsolationMode::ProcessPerRun,
+                        file_affinity: affinity,
+                        ..runner::RunOptions::default()
+                    };
+                    let _ = runner::run_tests_with_options(&tests, &options, |_| {});
+                },
+                criterion::BatchSize::SmallInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+/// **Workflow 13: Coverage Overhead with Process-Per-Run**
+/// Realistic suite in one worker, with and without coverage; the difference is the
+/// per-test cost of starting and stopping the tracer
+fn bench_coverage_overhead_ppr(c: &mut Criterion) {
+    let mut group = c.benchmark_group("coverage_overhead_ppr");
+    for coverage in [false, true] {
+        group.bench_function(if coverage { "on" } else { "off" }, |b| {
+            b.iter_batched(
+                || {
+                    let fixture = FixtureProject::realistic();
+                    let project_dir = vec![fixture.dir.path().to_path_buf()];
+                    let tests = discovery::extract_tests(&project_dir, &[])
+                        .map(|collection| collection.tests)
+                        .unwrap_or_default();
+                    (fixture, tests)
+                },
+                |(_fixture, tests)| {
+                    let options = runner::RunOptions {
+                        isolation: IsolationMode::ProcessPerRun,
+                        collect_coverage: coverage,
+                        ..runner::RunOptions::default()
+                    };
+                    let _ = runner::run_tests_with_options(&tests, &options, |_| {});
+                },
+                criterion::BatchSize::SmallInput,
+            );
+        });
+    }
+    group.finish();
+}
@@ -3,18 +3,29 @@
 A fast Python test runner written in Rust.
 
 This module provides decorators for marking tests:
-- @skip - Skip a test, optionally with a reason
+- @skip - Skip a test, optionally with a reason (or call skip("reason") inside one)
 - @mark - Add metadata to a test (slow=True, group="auth", etc.)
 - @parallel - Mark a test as parallel-safe
+- @parametrize - Run a test once per set of arguments
+- @fixture - Provide a value to tests by parameter name (from conftest.py)
+
+Assertion helpers:
+- raises() - Assert that a block raises an exception
+- fail() - Fail the current test with a message
 
 And the CLI entry point:
 - main() - Run the taut CLI
 """
 
-from typing import Any, Callable, Optional, TypeVar, Union
+import unittest
+from types import TracebackType
+from typing import Any, Callable, Iterable, NoReturn, Optional, Sequence, TypeVar, Union
 
 F = TypeVar("F", bound=Callable[..., Any])
 
+# Set by taut while a test body runs, so skip("reason") skips it on the spot
+_running_test = False
+
 
 # =============================================================================
 # @skip decorator
@@ -37,6 +48,11 @@ def skip(arg: Union[F, str, None] = None, *, reason: Optional[str] = None) -> An
         @skip(reason="Flaky test")
         def test_flaky():
             pass
+
+    Called inside a running test, it skips the test there and then:
+        def test_upload():
+            if not network_available():
+                skip("offline")
     """
 
     def decorator(func: F) -> F:
@@ -44,6 +60,10 @@ def skip(arg: Union[F, str, None] = None, *, reason: Optional[str] = None) -> An
         func._taut_skip_reason = skip_reason or ""  # type: ignore[attr-defined]
         return func
 
+    # skip("reason") from inside a running test
+    if _running_test and not callable(arg):
+        raise unittest.SkipTest(arg or reason or "")
+
     # @skip (no parens, no reason)
     if callable(arg):
         skip_reason = ""
@@ -99,7 +119,8 @@ def parallel(func: Optional[F] = None) -> Any:
     """
     Decorator to mark a test as safe to run in parallel with other parallel tests.
 
-    By default, tests run sequentially. Use @parallel to opt-in to parallel execution.
+    Under --no-parallel, unmarked tests run one at a time while @parallel tests
+    still run concurrently.
 
     Usage:
         @parallel
@@ -131,6 +152,123 @@ def parallel(func: Optional[F] = None) -> Any:
     return decorator
 
 
+# =============================================================================
+# @parametrize decorator
+# =============================================================================
+
+
+def parametrize(argnames: Union[str, Sequence[str]], argvalues: Iterable[Any]) -> Callable[[F], F]:
+    """
+    Decorator to run a test once per set of arguments.
+
+    taut reads the arguments statically, so values must be literals
+    (numbers, strings, booleans, None, lists and tuples).
+
+    Usage:
+        @parametrize("n", [1, 2, 3])
+        def test_positive(n):
+            assert n > 0
+
+        @parametrize("a, b, total", [(1, 2, 3), (2, 2, 4)])
+        def test_add(a, b, total):
+            assert a + b == total
+
+    Stacked @parametrize decorators run every combination.
+    """
+
+    def decorator(func: F) -> F:
+        if not hasattr(func, "_taut_parametrize"):
+            func._taut_parametrize = []  # type: ignore[attr-defined]
+        func._taut_parametrize.append((argnames, list(argvalues)))  # type: ignore[attr-defined]
+        return func
+
+    return decorator
+
+
+# =============================================================================
+# @fixture decorator
+# =============================================================================
+
+
+def fixture(func: Optional[F] = None) -> Any:
+    """
+    Decorator to turn a function in conftest.py into a fixture.
+
+    Tests in the same directory or below receive the fixture's return
+    value by naming it as a parameter. Fixtures are function-scoped and
+    may themselves take other fixtures as parameters.
+
+    Usage:
+        # conftest.py
+        @fixture
+        def db():
+            return {"users": ["alice"]}
+
+        # test_users.py
+        def test_has_users(db):
+            assert db["users"]
+    """
+
+    def decorator(func: F) -> F:
+        func._taut_fixture = True  # type: ignore[attr-defined]
+        return func
+
+    # @fixture (no parens)
+    if func is not None:
+        return decorator(func)
+
+    # @fixture()
+    return decorator
+
+
+# =============================================================================
+# Assertion helpers
+# =============================================================================
+
+
+class raises:
+    """
+    Context manager asserting that its block raises `expected_exception`.
+
+    Usage:
+        with raises(ValueError) as info:
+            int("nope")
+        assert "nope" in str(info.value)
+    """
+
+    def __init__(self, expected_exception: Union[type, tuple]) -> None:
+        self.expected_exception = expected_exception
+        self.value: Optional[BaseException] = None
+
+    def __enter__(self) -> "raises":
+        return self
+
+    def __exit__(
+        self,
+        exc_type: Optional[type],
+        exc: Optional[BaseException],
+        tb: Optional[TracebackType],
+    ) -> bool:
+        if exc_type is None:
+            name = getattr(self.expected_exception, "__name__", str(self.expected_exception))
+            raise AssertionError(f"DID NOT RAISE {name}")
+        if not issubclass(exc_type, self.expected_exception):
+            return False
+        self.value = exc
+        return True
+
+
+def fail(msg: str = "") -> NoReturn:
+    """
+    Fail the current test.
+
+    Usage:
+        if not ready:
+            fail("service never became ready")
+    """
+    raise AssertionError(msg or "failed")
+
+
 # =============================================================================
 # CLI Entry Point (from Rust)
 # =============================================================================
@@ -141,5 +279,5 @@ from taut._taut import main
 # Exports
 # =============================================================================
 
-__all__ = ["skip", "mark", "parallel", "main"]
+__all__ = ["skip", "mark", "parallel", "parametrize", "fixture", "raises", "fail", "main"]
 __version__ = "0.1.0"
@@ -0,0 +1,153 @@
+//! Driving `git bisect` to find the commit that broke a test.
+//!
+//! taut only decides whether each commit git checks out is good, bad or
+//! untestable; git does the searching. The bisect session is always reset
+//! afterwards, returning the repository to the branch it started on.
+
+use anyhow::{Context, Result};
+use std::process::Command;
+
+/// How the target test fared at one commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The test passed.
+    Good,
+    /// The test failed or errored.
+    Bad,
+    /// The test doesn't exist or was skipped, so this commit says nothing.
+    Skip,
+}
+
+impl Verdict {
+    fn as_str(self) -> &'static str {
+        match self {
+            Verdict::Good => "good",
+            Verdict::Bad => "bad",
+            Verdict::Skip => "skip",
+        }
+    }
+}
+
+/// Where a bisect ended up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The first commit at which the test is bad.
+    FirstBad(String),
+    /// Skipped commits left several candidates; these are all of them.
+    Ambiguous(Vec<String>),
+}
+
+/// Bisect the commits between `good` and `bad`, calling `check` with the
+/// checked-out commit's hash at each step.
+///
+/// Fails if git refuses to start (not a repository, unknown refs, local
+/// changes that a checkout would overwrite) or `check` fails.
+pub fn bisect(
+    good: &str,
+    bad: &str,
+    mut check: impl FnMut(&str) -> Result<Verdict>,
+) -> Result<Outcome> {
+    let mut output = git(&["bisect", "start", bad, good, "--"])
+        .with_context(|| format!("Can't bisect from {} to {}", good, bad))?;
+
+    let outcome = loop {
+        if let Some(outcome) = finished(&output) {
+            break Ok(outcome);
+        }
+        let commit = match git(&["rev-parse", "HEAD"]) {
+            Ok(commit) => commit.trim().to_string(),
+            Err(e) => break Err(e),
+        };
+        let verdict = match check(&commit) {
+            Ok(verdict) => verdict,
+            Err(e) => break Err(e),
+        };
+        output = match git(&["bisect", verdict.as_str()]) {
+            Ok(output) => output,
+            Err(e) => break Err(e),
+        };
+    };
+
+    git(&["bisect", "reset"])?;
+    outcome
+}
+
+/// The result, if git's reply to the last bisect command ends the search.
+fn finished(output: &str) -> Option<Outcome> {
+    if let Some(line) = output
+        .lines()
+        .find(|line| line.ends_with(" is the first bad commit"))
+    {
+        let commit = line.split_whitespace().next()?;
+        return Some(Outcome::FirstBad(commit.to_string()));
+    }
+    if output.contains("only 'skip'ped commits left") {
+        let candidates = output
+            .lines()
+            .skip_while(|line| !line.starts_with("The first bad commit could be any of"))
+            .skip(1)
+            .filter_map(|line| line.split_whitespace().next())
+            .filter(|word| word.len() >= 7 && word.chars().all(|c| c.is_ascii_hexdigit()))
+            .map(String::from)
+            .collect();
+        return Some(Outcome::Ambiguous(candidates));
+    }
+    None
+}
+
+/// Run git and return its stdout. `git bisect skip` exits non-zero when only
+/// skipped commits remain, so that reply is passed through rather than failing.
+fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if !output.status.success() && finished(&stdout).is_none() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finished_reads_first_bad_commit() {
+        let output = "3f9c2a1d8e7b6a5c4d3e2f1a0b9c8d7e6f5a4b3c is the first bad commit\n\
+                      commit 3f9c2a1d8e7b6a5c4d3e2f1a0b9c8d7e6f5a4b3c\n";
+        assert_eq!(
+            finished(output),
+            Some(Outcome::FirstBad(
+                "3f9c2a1d8e7b6a5c4d3e2f1a0b9c8d7e6f5a4b3c".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn finished_lists_candidates_left_by_skips() {
+        let output = "There are only 'skip'ped commits left to test.\n\
+                      The first bad commit could be any of:\n\
+                      1111111111111111111111111111111111111111\n\
+                      2222222222222222222222222222222222222222\n\
+                      We cannot bisect more!\n";
+        assert_eq!(
+            finished(output),
+            Some(Outcome::Ambiguous(vec![
+                "1111111111111111111111111111111111111111".to_string(),
+                "2222222222222222222222222222222222222222".to_string(),
+            ]))
+        );
+    }
+
+    #[test]
+    fn finished_keeps_going_mid_bisect() {
+        let output = "Bisecting: 3 revisions left to test after this (roughly 2 steps)\n";
+        assert_eq!(finished(output), None);
+    }
+}
@@ -2,7 +2,7 @@ use anyhow::{Context, Result};
 use rustpython_parser::Parse;
 use rustpython_parser::ast::{self, Ranged};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use xxhash_rust::xxh64;
 
@@ -13,6 +13,8 @@ pub enum BlockKind {
     Class,
     TopLevel,
     Import,
+    /// An `if __name__ == "__main__":` guard, which tests never depend on
+    Main,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -30,31 +32,42 @@ pub struct Block {
     pub checksum: String,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct FileBlocks {
     pub file: PathBuf,
     pub blocks: Vec<Block>,
     pub line_to_block: HashMap<usize, usize>, // line_number -> block index
+    /// Decorator and `def` lines of functions and methods. Defining a function
+    /// runs them at import, so executing them doesn't mean the function ran.
+    /// Not defaulted, so block indexes persisted without it are re-parsed.
+    pub header_lines: HashSet<usize>,
 }
 
 impl FileBlocks {
     pub fn from_file(path: &Path) -> Result<Self> {
+        // Block IDs are dependency keys, so they always use the canonical path
+        let path = &path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
         let source = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::from_source(&source, path)
+    }
 
-        let ast = ast::Suite::parse(&source, "<module>")
+    /// Blocks of `source`, as if it were the contents of `path`.
+    pub fn from_source(source: &str, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let ast = ast::Suite::parse(source, "<module>")
             .map_err(|e| anyhow::anyhow!("Parse error in {}: {}", path.display(), e))?;
 
         let mut blocks = Vec::new();
 
-        // Extract imports as a single block
-        Self::extract_imports(&ast, &source, path, &mut blocks);
+        // Extract runs of consecutive imports
+        Self::extract_imports(&ast, source, path, &mut blocks);
 
         // Extract top-level code
-        Self::extract_top_level(&ast, &source, path, &mut blocks);
+        Self::extract_top_level(&ast, source, path, &mut blocks);
 
         // Extract functions and classes
-        Self::extract_definitions(&ast, &source, path, &mut blocks, None);
+        Self::extract_definitions(&ast, source, path, &mut blocks, None);
 
         // Build line -> block index mapping
         let mut line_to_block = HashMap::new();
@@ -64,10 +77,14 @@ impl FileBlocks {
             }
         }
 
+        let mut header_lines = HashSet::new();
+        Self::extract_header_lines(&ast, source, &mut header_lines);
+
         Ok(Self {
             file: path.to_path_buf(),
             blocks,
             line_to_block,
+            header_lines,
         })
     }
 
@@ -76,42 +93,40 @@ impl FileBlocks {
     }
 
     fn extract_imports(ast: &[ast::Stmt], source: &str, file: &Path, blocks: &mut Vec<Block>) {
-        let mut import_lines: Vec<(usize, usize)> = Vec::new();
+        // Runs of adjacent imports, split wherever other code sits between them
+        let mut groups: Vec<(usize, usize)> = Vec::new();
+        let mut previous_was_import = false;
 
         for stmt in ast {
-            match stmt {
-                ast::Stmt::Import(imp) => {
-                    let start = offset_to_line(source, imp.range.start().into());
-                    let end = offset_to_line(source, imp.range.end().into());
-                    import_lines.push((start, end));
-                }
-                ast::Stmt::ImportFrom(imp) => {
-                    let start = offset_to_line(source, imp.range.start().into());
-                    let end = offset_to_line(source, imp.range.end().into());
-                    import_lines.push((start, end));
+            if !matches!(stmt, ast::Stmt::Import(_) | ast::Stmt::ImportFrom(_)) {
+                previous_was_import = false;
+                continue;
+            }
+
+            let start = offset_to_line(source, stmt.range().start().into());
+            let end = offset_to_line(source, stmt.range().end().into());
+            match groups.last_mut() {
+                Some((_, group_end)) if previous_was_import && start <= *group_end + 2 => {
+                    *group_end = end;
                 }
-                _ => {}
+                _ => groups.push((start, end)),
             }
+            previous_was_import = true;
         }
 
-        if import_lines.is_empty() {
-            return;
+        for (num, (start, end)) in groups.into_iter().enumerate() {
+            let source_slice = extract_lines(source, start, end);
+            blocks.push(Block {
+                id: BlockId {
+                    file: file.to_path_buf(),
+                    kind: BlockKind::Import,
+                    name: format!("<imports_{}>", num),
+                    start_line: start,
+                    end_line: end,
+                },
+                checksum: compute_checksum(&source_slice),
+            });
         }
-
-        let min_line = import_lines.iter().map(|(s, _)| *s).min().unwrap();
-        let max_line = import_lines.iter().map(|(_, e)| *e).max().unwrap();
-        let source_slice = extract_lines(source, min_line, max_line);
-
-        blocks.push(Block {
-            id: BlockId {
-                file: file.to_path_buf(),
-                kind: BlockKind::Import,
-                name: "<imports>".to_string(),
-                start_line: min_line,
-                end_line: max_line,
-            },
-            checksum: compute_checksum(&source_slice),
-        });
     }
 
     fn extract_top_level(ast: &[ast::Stmt], source: &str, file: &Path, blocks: &mut Vec<Block>) {
@@ -123,6 +138,21 @@ impl FileBlocks {
                 | ast::Stmt::ImportFrom(_)
                 | ast::Stmt::FunctionDef(_)
                 | ast::Stmt::ClassDef(_) => continue,
+                ast::Stmt::If(if_stmt) if is_main_guard(&if_stmt.test) => {
+                    let start = offset_to_line(source, stmt.range().start().into());
+                    let end = offset_to_line(source, stmt.range().end().into());
+                    let source_slice = extract_lines(source, start, end);
+                    blocks.push(Block {
+                        id: BlockId {
+                            file: file.to_path_buf(),
+                            kind: BlockKind::Main,
+                            name: "<main>".to_string(),
+                            start_line: start,
+                            end_line: end,
+                        },
+                        checksum: compute_checksum(&source_slice),
+                    });
+                }
                 _ => {
                     let start = offset_to_line(source, stmt.range().start().into());
                     let end = offset_to_line(source, stmt.range().end().into());
@@ -169,6 +199,32 @@ impl FileBlocks {
         blocks.push(add_block(current_start, current_end, block_num));
     }
 
+    /// Lines from each function's first decorator up to its first body statement,
+    /// in the module and (nested) class bodies that run at import.
+    fn extract_header_lines(ast: &[ast::Stmt], source: &str, lines: &mut HashSet<usize>) {
+        for stmt in ast {
+            let (decorators, body) = match stmt {
+                ast::Stmt::FunctionDef(func) => (&func.decorator_list, &func.body),
+                ast::Stmt::AsyncFunctionDef(func) => (&func.decorator_list, &func.body),
+                ast::Stmt::ClassDef(class) => {
+                    Self::extract_header_lines(&class.body, source, lines);
+                    continue;
+                }
+                _ => continue,
+            };
+            let start = match decorators.first() {
+                Some(decorator) => decorator.range().start(),
+                None => stmt.range().start(),
+            };
+            let start = offset_to_line(source, start.into());
+            let body_start = body
+                .first()
+                .map(|s| offset_to_line(source, s.range().start().into()))
+                .unwrap_or(start);
+            lines.extend(start..body_start);
+        }
+    }
+
     fn extract_definitions(
         ast: &[ast::Stmt],
         source: &str,
@@ -233,17 +289,24 @@ impl FileBlocks {
                     });
                 }
                 ast::Stmt::ClassDef(class) => {
+                    // Nested classes are named by their dotted path (Outer.Inner)
+                    let class_name = match parent_class {
+                        Some(outer) => format!("{}.{}", outer, class.name),
+                        None => class.name.to_string(),
+                    };
                     let start = offset_to_line(source, class.range.start().into());
                     let end = offset_to_line(source, class.range.end().into());
 
-                    // Class header (before first method)
+                    // Class header (before first method or nested class)
                     let header_end = class
                         .body
                         .iter()
                         .filter_map(|s| {
                             if matches!(
                                 s,
-                                ast::Stmt::FunctionDef(_) | ast::Stmt::AsyncFunctionDef(_)
+                                ast::Stmt::FunctionDef(_)
+                                    | ast::Stmt::AsyncFunctionDef(_)
+                                    | ast::Stmt::ClassDef(_)
                             ) {
                                 Some(offset_to_line(source, s.range().start().into()) - 1)
                             } else {
@@ -258,15 +321,55 @@ impl FileBlocks {
                         id: BlockId {
                             file: file.to_path_buf(),
                             kind: BlockKind::Class,
-                            name: class.name.to_string(),
+                            name: class_name.clone(),
                             start_line: start,
                             end_line: header_end,
                         },
                         checksum: compute_checksum(&class_source),
                     });
 
+                    // Class-level statements after the first method (e.g. trailing class
+                    // variables), grouped into runs not interrupted by definitions
+                    let mut body_groups: Vec<(usize, usize)> = Vec::new();
+                    let mut previous_was_body = false;
+                    for stmt in &class.body {
+                        let stmt_start = offset_to_line(source, stmt.range().start().into());
+                        if stmt_start <= header_end
+                            || matches!(
+                                stmt,
+                                ast::Stmt::FunctionDef(_)
+                                    | ast::Stmt::AsyncFunctionDef(_)
+                                    | ast::Stmt::ClassDef(_)
+                            )
+                        {
+                            previous_was_body = false;
+                            continue;
+                        }
+
+                        let stmt_end = offset_to_line(source, stmt.range().end().into());
+                        match body_groups.last_mut() {
+                            Some((_, group_end)) if previous_was_body => *group_end = stmt_end,
+                            _ => body_groups.push((stmt_start, stmt_end)),
+                        }
+                        previous_was_body = true;
+                    }
+
+                    for (num, (body_start, body_end)) in body_groups.into_iter().enumerate() {
+                        let body_source = extract_lines(source, body_start, body_end);
+                        blocks.push(Block {
+                            id: BlockId {
+                                file: file.to_path_buf(),
+                                kind: BlockKind::Class,
+                                name: format!("{}.<body_{}>", class_name, num),
+                                start_line: body_start,
+                                end_line: body_end,
+                            },
+                            checksum: compute_checksum(&body_source),
+                        });
+                    }
+
                     // Recursively extract methods
-                    Self::extract_definitions(&class.body, source, file, blocks, Some(&class.name));
+                    Self::extract_definitions(&class.body, source, file, blocks, Some(&class_name));
                 }
                 _ => {}
             }
@@ -274,6 +377,25 @@ impl FileBlocks {
     }
 }
 
+/// Whether `test` is `__name__ == "__main__"` (either way around).
+fn is_main_guard(test: &ast::Expr) -> bool {
+    let ast::Expr::Compare(compare) = test else {
+        return false;
+    };
+    let is_name =
+        |expr: &ast::Expr| matches!(expr, ast::Expr::Name(name) if name.id.as_str() == "__name__");
+    let is_main = |expr: &ast::Expr| match expr {
+        ast::Expr::Constant(c) => matches!(&c.value, ast::Constant::Str(s) if s == "__main__"),
+        _ => false,
+    };
+    match (compare.ops.as_slice(), compare.comparators.as_slice()) {
+        ([ast::CmpOp::Eq], [right]) => {
+            (is_name(&compare.left) && is_main(right)) || (is_main(&compare.left) && is_name(right))
+        }
+        _ => false,
+    }
+}
+
 fn compute_checksum(source: &str) -> String {
     let normalized: String = source
         .lines()
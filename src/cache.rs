@@ -1,10 +1,105 @@
+use crate::blocks::FileBlocks;
+use crate::depdb::TestId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
 use xxhash_rust::xxh64;
 
-/// Get the global cache directory for the current project.
-/// Returns: ~/.cache/taut/<project-hash>/ (platform-specific)
-pub fn get_cache_dir() -> PathBuf {
+const BLOCK_INDEX_FILE: &str = "block_index.msgpack";
+const STEPWISE_FILE: &str = "stepwise.msgpack";
+const SEED_FILE: &str = "last_seed.msgpack";
+
+/// Environment variable naming the cache directory.
+pub const CACHE_DIR_ENV: &str = "TAUT_CACHE_DIR";
+
+/// Environment variable overriding the branch name used by `cache_per_branch`.
+pub const CACHE_BRANCH_ENV: &str = "TAUT_CACHE_BRANCH";
+
+/// Cached block index: file path -> (stamp at parse time, parsed blocks)
+pub type BlockIndex = HashMap<PathBuf, (FileStamp, FileBlocks)>;
+
+/// Cheap fingerprint of a file used to decide whether cached blocks are stale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub mtime_ns: u64,
+    pub size: u64,
+}
+
+impl FileStamp {
+    /// Read the current stamp of a file, or None if it can't be stat'ed.
+    pub fn of(path: &Path) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+        let mtime_ns = meta
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_nanos() as u64;
+        Some(Self {
+            mtime_ns,
+            size: meta.len(),
+        })
+    }
+}
+
+/// Pick the cache directory: `--cache-dir`, then `TAUT_CACHE_DIR`, then the
+/// `cache_dir` config option, then the per-project default. With `per_branch`,
+/// each git branch gets its own subdirectory of that.
+pub fn resolve_cache_dir(
+    flag: Option<PathBuf>,
+    config: Option<PathBuf>,
+    per_branch: bool,
+) -> PathBuf {
+    let dir = flag
+        .or_else(|| {
+            std::env::var_os(CACHE_DIR_ENV)
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+        })
+        .or(config)
+        .unwrap_or_else(default_cache_dir);
+    if per_branch {
+        dir.join(branch_segment())
+    } else {
+        dir
+    }
+}
+
+/// The current git branch as a directory name, for `cache_per_branch`.
+///
+/// `TAUT_CACHE_BRANCH` takes precedence; outside a git checkout this is "nogit".
+pub fn branch_segment() -> String {
+    let branch = std::env::var(CACHE_BRANCH_ENV)
+        .ok()
+        .filter(|branch| !branch.is_empty())
+        .or_else(|| {
+            let output = std::process::Command::new("git")
+                .args(["rev-parse", "--abbrev-ref", "HEAD"])
+                .stderr(std::process::Stdio::null())
+                .output()
+                .ok()?;
+            let branch = String::from_utf8(output.stdout).ok()?.trim().to_string();
+            (output.status.success() && !branch.is_empty()).then_some(branch)
+        })
+        .unwrap_or_else(|| "nogit".to_string());
+
+    // Branch names like "feature/login" must stay a single path segment
+    branch
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Per-project cache directory: ~/.cache/taut/<project-hash>/ (platform-specific)
+pub fn default_cache_dir() -> PathBuf {
     let cache_base = dirs::cache_dir()
         .unwrap_or_else(|| PathBuf::from(".cache"))
         .join("taut");
@@ -12,25 +107,58 @@ pub fn get_cache_dir() -> PathBuf {
     // Hash the current directory to isolate per-project caches
     let cwd = std::env::current_dir().unwrap_or_default();
     let hash = xxh64::xxh64(cwd.to_string_lossy().as_bytes(), 0);
-    let project_hash = &format!("{:x}", hash)[..16]; // First 16 chars
-
-    cache_base.join(project_hash)
+    // Zero-padded: a hash with leading zero nibbles still names a 16-char directory
+    cache_base.join(format!("{:016x}", hash))
 }
 
 /// Ensure the cache directory exists
-pub fn ensure_cache_dir() -> std::io::Result<PathBuf> {
-    let dir = get_cache_dir();
-    fs::create_dir_all(&dir)?;
+pub fn ensure_cache_dir(dir: &Path) -> std::io::Result<&Path> {
+    fs::create_dir_all(dir)?;
     Ok(dir)
 }
 
-/// Clear all caches for the current project
+/// Write `data` to `path` through a temporary file that is then renamed over
+/// it, so a crash mid-write never leaves a truncated cache file behind.
+pub fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.tmp", std::process::id()));
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+/// Hold an exclusive advisory lock guarding `path` until the returned file is
+/// dropped. The lock lives in a `.lock` file next to `path`.
+pub fn lock_file(path: &Path) -> std::io::Result<fs::File> {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(PathBuf::from(lock_path))?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// State of a cache file, as reported by `taut cache verify`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileHealth {
+    /// Not written yet
+    Missing,
+    Valid,
+    /// Present but unreadable; holds the reason
+    Corrupt(String),
+}
+
+/// Clear all caches in `dir`
 /// Returns the stats of what was cleared (size_bytes, file_count)
-pub fn clear_cache() -> std::io::Result<(u64, usize)> {
-    let stats = get_cache_stats();
-    let dir = get_cache_dir();
+pub fn clear_cache(dir: &Path) -> std::io::Result<(u64, usize)> {
+    let stats = get_cache_stats(dir);
     if dir.exists() {
-        fs::remove_dir_all(&dir)?;
+        fs::remove_dir_all(dir)?;
     }
     Ok((stats.size_bytes, stats.file_count))
 }
@@ -43,8 +171,8 @@ pub struct CacheStats {
     pub file_count: usize,
 }
 
-pub fn get_cache_stats() -> CacheStats {
-    let cache_dir = get_cache_dir();
+pub fn get_cache_stats(dir: &Path) -> CacheStats {
+    let cache_dir = dir.to_path_buf();
     let exists = cache_dir.exists();
 
     let (size_bytes, file_count) = if exists {
@@ -67,3 +195,91 @@ pub fn get_cache_stats() -> CacheStats {
         file_count,
     }
 }
+
+/// Load the persisted block index, or an empty index if missing or unreadable.
+pub fn load_block_index(dir: &Path) -> BlockIndex {
+    let path = dir.join(BLOCK_INDEX_FILE);
+    fs::read(&path)
+        .ok()
+        .and_then(|data| rmp_serde::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Persist parsed blocks along with the stamp each file had when parsed.
+pub fn save_block_index(
+    dir: &Path,
+    blocks: &HashMap<PathBuf, FileBlocks>,
+    stamps: &HashMap<PathBuf, FileStamp>,
+) {
+    let index: HashMap<&PathBuf, (&FileStamp, &FileBlocks)> = blocks
+        .iter()
+        .filter_map(|(path, file_blocks)| Some((path, (stamps.get(path)?, file_blocks))))
+        .collect();
+
+    if let Ok(dir) = ensure_cache_dir(dir)
+        && let Ok(data) = rmp_serde::to_vec(&index)
+    {
+        let _ = write_atomic(&dir.join(BLOCK_INDEX_FILE), &data);
+    }
+}
+
+/// Check that the persisted block index can be read back.
+pub fn verify_block_index(dir: &Path) -> FileHealth {
+    match fs::read(dir.join(BLOCK_INDEX_FILE)) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => FileHealth::Missing,
+        Err(e) => FileHealth::Corrupt(e.to_string()),
+        Ok(data) => match rmp_serde::from_slice::<BlockIndex>(&data) {
+            Ok(_) => FileHealth::Valid,
+            Err(e) => FileHealth::Corrupt(e.to_string()),
+        },
+    }
+}
+
+/// Drop the persisted block index. It only saves re-parsing unchanged files,
+/// so the next run simply rebuilds it.
+pub fn remove_block_index(dir: &Path) -> std::io::Result<()> {
+    match fs::remove_file(dir.join(BLOCK_INDEX_FILE)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Load the test `--stepwise` last stopped at, if any.
+pub fn load_stepwise(dir: &Path) -> Option<TestId> {
+    let data = fs::read(dir.join(STEPWISE_FILE)).ok()?;
+    rmp_serde::from_slice(&data).ok()
+}
+
+/// Remember the test `--stepwise` stopped at, or forget it once a run gets through.
+pub fn save_stepwise(dir: &Path, stopped_at: Option<&TestId>) {
+    let Ok(dir) = ensure_cache_dir(dir) else {
+        return;
+    };
+    let path = dir.join(STEPWISE_FILE);
+    match stopped_at {
+        Some(id) => {
+            if let Ok(data) = rmp_serde::to_vec(id) {
+                let _ = write_atomic(&path, &data);
+            }
+        }
+        None => {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Load the seed of the last shuffled run, for `--last-seed`.
+pub fn load_seed(dir: &Path) -> Option<u64> {
+    let data = fs::read(dir.join(SEED_FILE)).ok()?;
+    rmp_serde::from_slice(&data).ok()
+}
+
+/// Remember the seed a shuffled run used.
+pub fn save_seed(dir: &Path, seed: u64) {
+    let Ok(dir) = ensure_cache_dir(dir) else {
+        return;
+    };
+    if let Ok(data) = rmp_serde::to_vec(&seed) {
+        let _ = write_atomic(&dir.join(SEED_FILE), &data);
+    }
+}
@@ -3,13 +3,18 @@
 //! This module contains the CLI definition and entry points that can be
 //! called from both the binary and the Python extension.
 
-use crate::{cache, config, depdb, discovery, output, runner, selection};
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use crate::discovery::TestItem;
+use crate::{
+    bisect, cache, config, depdb, discovery, exclude, output, runner, selection, shuffle,
+    worker_pool,
+};
+use anyhow::{Context, Result};
+use clap::{ArgAction, Parser, Subcommand};
 use notify::{RecursiveMode, Watcher};
-use std::path::PathBuf;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(Parser, Debug)]
 #[command(name = "taut", version, about = "Tests, without the overhead.")]
@@ -17,19 +22,43 @@ pub struct Args {
     #[command(subcommand)]
     pub command: Option<Commands>,
 
-    /// Path(s) to test files or directories
-    #[arg(default_value = ".")]
+    /// Path(s) to test files or directories, or test IDs (file.py::[Class::]test)
+    /// [default: `testpaths` from pyproject.toml, else .]
     pub paths: Vec<PathBuf>,
 
-    /// Filter tests by name substring
+    /// Filter tests by name substring or glob; repeat to match any, prefix with ! to exclude
     #[arg(short = 'k', long)]
-    pub filter: Option<String>,
+    pub filter: Vec<String>,
 
-    /// Verbose output
+    /// Make -k patterns match whole test names instead of substrings
+    #[arg(long, alias = "full-match")]
+    pub exact: bool,
+
+    /// Verbose output (-vv for full tracebacks)
+    #[arg(short, long, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Only print the final summary line
     #[arg(short, long)]
-    pub verbose: bool,
+    pub quiet: bool,
+
+    /// Print nothing on stdout but the final pass/fail/skip line, for embedding
+    /// in other tools (unlike -q, the shuffle seed goes to stderr)
+    #[arg(
+        long,
+        conflicts_with_all = ["verbose", "progress", "profile", "dry_run", "explain"]
+    )]
+    pub summary_only: bool,
 
-    /// Disable parallel execution
+    /// Show a `[ k/N ]` counter instead of dots (terminal only)
+    #[arg(long)]
+    pub progress: bool,
+
+    /// Don't print a line or marker for each skipped test; the summary still counts them
+    #[arg(long)]
+    pub quiet_skips: bool,
+
+    /// Run only @parallel tests concurrently; the rest run one at a time first
     #[arg(long)]
     pub no_parallel: bool,
 
@@ -41,10 +70,223 @@ pub struct Args {
     #[arg(long)]
     pub no_cache: bool,
 
+    /// Print why each test is re-run, e.g. which function it depends on changed
+    #[arg(long)]
+    pub explain: bool,
+
+    /// Select tests as usual, then list what would run and be skipped without running anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Directory for taut's cache (default: per-project dir under the user cache dir;
+    /// also settable with TAUT_CACHE_DIR)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Exit code when no tests are collected (e.g. set nonzero in CI to catch bad paths)
+    #[arg(long, global = true, value_name = "CODE", default_value_t = 0)]
+    pub exitcode_on_no_tests: u8,
+
+    /// Give up collecting a file that takes longer than this many seconds to parse
+    #[arg(long, global = true, value_name = "SECONDS")]
+    pub collect_timeout: Option<f64>,
+
     /// Execution isolation mode
     #[arg(long, default_value = "process-per-test")]
     pub isolation: String,
 
+    /// Event loop for async tests: a fresh one per test (asyncio.run), or one
+    /// shared by every test in the same Python process
+    #[arg(long, default_value = "per-test", value_parser = ["per-test", "shared"])]
+    pub async_mode: String,
+
+    /// How test files are imported: prepend puts the test's directory on
+    /// sys.path, rootdir also adds the rootdir, importlib leaves sys.path alone
+    #[arg(long, default_value = "prepend", value_parser = ["prepend", "rootdir", "importlib"])]
+    pub import_mode: String,
+
+    /// What to do with Python warnings raised during a test: report them (default),
+    /// fail the test (error), or drop them (ignore)
+    #[arg(long, default_value = "default", value_parser = ["default", "error", "ignore"])]
+    pub warnings: String,
+
+    /// Record coverage for files whose path contains this substring or matches
+    /// this glob, even inside site-packages (repeatable)
+    #[arg(long, value_name = "PATTERN")]
+    pub coverage_include: Vec<String>,
+
+    /// Never record coverage for files whose path contains this substring or
+    /// matches this glob (repeatable)
+    #[arg(long, value_name = "PATTERN")]
+    pub coverage_exclude: Vec<String>,
+
+    /// Fail a test if its setUp fixture takes longer than this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    pub fixtures_setup_timeout: Option<f64>,
+
+    /// Test execution order (`random` shuffles; see --seed)
+    #[arg(
+        long,
+        visible_alias = "order-by",
+        value_enum,
+        default_value_t = OrderBy::Discovery
+    )]
+    pub order: OrderBy,
+
+    /// Run tests in a random order to surface order dependencies (same as `--order random`)
+    #[arg(long, conflicts_with = "order")]
+    pub shuffle: bool,
+
+    /// Seed for --shuffle or `--order random` (implies --shuffle); printed on every shuffled run
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Shuffle with the seed of the last shuffled run (implies --shuffle)
+    #[arg(long, conflicts_with = "seed")]
+    pub last_seed: bool,
+
+    /// Print how long each phase of the run took (discovery, selection, execution)
+    #[arg(long)]
+    pub profile: bool,
+
+    /// Only run the first N selected tests (after ordering)
+    #[arg(long, value_name = "N")]
+    pub max_collect: Option<usize>,
+
+    /// Stop at the first failure, and resume from that test on the next --stepwise run
+    #[arg(long, visible_alias = "sw")]
+    pub stepwise: bool,
+
+    /// Run each selected test N times (disables caching)
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    pub repeat: usize,
+
+    /// Rewrite `assert` statements to report operand values on failure
+    #[arg(long, default_value_t = true, action = ArgAction::Set)]
+    pub assert_rewrite: bool,
+
+    /// Run the full suite only to populate the cache; always exits 0
+    #[arg(long)]
+    pub prime: bool,
+
+    /// Run only tests that depend on code changed since this git ref (plus new and failing tests)
+    #[arg(
+        long,
+        value_name = "REF",
+        conflicts_with_all = ["no_cache", "prime", "cov_fail_under"]
+    )]
+    pub changed_since: Option<String>,
+
+    /// Restart each process-per-run worker after N tests to bound memory and leaked state
+    #[arg(long, value_name = "N")]
+    pub worker_max_tests: Option<usize>,
+
+    /// Number of process-per-run workers (default: -j, then CPU count)
+    #[arg(long, value_name = "N")]
+    pub workers: Option<usize>,
+
+    /// Don't capture test stdout/stderr; stream it live instead
+    #[arg(short = 's', long)]
+    pub no_capture: bool,
+
+    /// Report parallel test results in collection order instead of as they finish
+    #[arg(long)]
+    pub ordered_output: bool,
+
+    /// Never run two @parallel tests sharing a @mark(group=...) at the same time
+    #[arg(long)]
+    pub parallel_groups: bool,
+
+    /// With --isolation process-per-run, hand each worker consecutive tests from
+    /// the same file so it can reuse the compiled module
+    #[arg(long)]
+    pub file_affinity: bool,
+
+    /// Abort the run (exit code 2) if a process-per-run worker crashes, instead of
+    /// respawning it and retrying the test
+    #[arg(long)]
+    pub bail_on_worker_crash: bool,
+
+    /// Stop starting tests after this many seconds of wall-clock time; tests left
+    /// over are reported as not run and taut exits with code 3
+    #[arg(long, value_name = "SECONDS")]
+    pub deadline: Option<f64>,
+
+    /// Flag tests that ran slower than on the previous run (see --duration-threshold)
+    #[arg(long)]
+    pub compare_durations: bool,
+
+    /// With --compare-durations, how many percent slower counts as a regression
+    #[arg(long, value_name = "PERCENT", default_value_t = 50.0)]
+    pub duration_threshold: f64,
+
+    /// With --deadline, kill tests still running at the deadline instead of
+    /// letting them finish
+    #[arg(long, requires = "deadline")]
+    pub deadline_kill: bool,
+
+    /// Print GitHub Actions `::error` annotations for failures
+    /// (enabled automatically when GITHUB_ACTIONS=true)
+    #[arg(long)]
+    pub github_annotations: bool,
+
+    /// Skip test files under this path or matching this glob (repeatable)
+    #[arg(long, value_name = "PATH")]
+    pub ignore: Vec<String>,
+
+    /// Leave out the test with this ID, e.g. tests/test_api.py::test_login (repeatable)
+    #[arg(long, value_name = "TEST_ID")]
+    pub deselect: Vec<String>,
+
+    /// Leave out tests marked @mark(slow=True)
+    #[arg(long)]
+    pub skip_slow: bool,
+
+    /// Only run tests marked @mark(slow=True)
+    #[arg(long, conflicts_with = "skip_slow")]
+    pub only_slow: bool,
+
+    /// Also run the `>>>` examples in docstrings of non-test modules
+    #[arg(long)]
+    pub doctests: bool,
+
+    /// Exit 0 even if some test files couldn't be collected (e.g. syntax errors)
+    #[arg(long)]
+    pub continue_on_collection_errors: bool,
+
+    /// Treat decorators that look like misspelled markers (e.g. @skp) as
+    /// collection errors instead of warnings
+    #[arg(long)]
+    pub strict_markers: bool,
+
+    /// Run only the test IDs listed in this file (one per line), skipping discovery
+    #[arg(long, value_name = "PATH")]
+    pub from_file: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = output::OutputFormat::Human)]
+    pub output: output::OutputFormat,
+
+    /// Traceback detail in the failure summary
+    #[arg(long, value_enum, default_value_t = output::TracebackStyle::Short)]
+    pub tb: output::TracebackStyle,
+
+    /// Whose captured stdout/stderr the summary shows
+    #[arg(long, value_parser = ["failed", "all", "no"], default_value = "failed")]
+    pub show_capture: String,
+
+    /// Write an aggregate coverage report after the run (FORMAT: lcov or html)
+    #[arg(long, num_args = 2, value_names = ["FORMAT", "PATH"])]
+    pub coverage_report: Option<Vec<String>>,
+
+    /// Fail the run if overall line coverage is below this percentage (implies a full run)
+    #[arg(long, value_name = "PERCENT")]
+    pub cov_fail_under: Option<f64>,
+
+    /// Tally failures in the summary (supported: category)
+    #[arg(long, value_name = "KEY", value_parser = ["category"])]
+    pub group_failures_by: Option<String>,
+
     /// Generate markdown documentation for CLI
     #[arg(long, hide = true)]
     pub markdown_help: bool,
@@ -54,23 +296,37 @@ pub struct Args {
 pub enum Commands {
     /// List discovered tests without running them
     List {
-        /// Path(s) to test files or directories
-        #[arg(default_value = ".")]
+        /// Path(s) to test files or directories [default: `testpaths` from pyproject.toml, else .]
         paths: Vec<PathBuf>,
 
-        /// Filter tests by name substring
+        /// Filter tests by name substring or glob; repeat to match any, prefix with ! to exclude
         #[arg(short = 'k', long)]
-        filter: Option<String>,
+        filter: Vec<String>,
+
+        /// Make -k patterns match whole test names instead of substrings
+        #[arg(long, alias = "full-match")]
+        exact: bool,
+
+        /// Print the collected tests as a JSON array
+        #[arg(long)]
+        json: bool,
+
+        /// Print tests grouped by directory, file and class
+        #[arg(long, conflicts_with = "json")]
+        tree: bool,
+
+        /// Print each marker in use, with its arguments, and how many tests have it
+        #[arg(long, conflicts_with_all = ["json", "tree"])]
+        markers: bool,
     },
     /// Watch for changes and re-run affected tests
     Watch {
-        /// Path(s) to test files or directories
-        #[arg(default_value = ".")]
+        /// Path(s) to test files or directories [default: `testpaths` from pyproject.toml, else .]
         paths: Vec<PathBuf>,
 
-        /// Filter tests by name substring
+        /// Filter tests by name substring or glob; repeat to match any, prefix with ! to exclude
         #[arg(short = 'k', long)]
-        filter: Option<String>,
+        filter: Vec<String>,
 
         /// Verbose output
         #[arg(short, long)]
@@ -87,6 +343,44 @@ pub enum Commands {
         /// Disable dependency caching (run all tests)
         #[arg(long)]
         no_cache: bool,
+
+        /// Clear the screen before each re-run
+        #[arg(long)]
+        clear: bool,
+
+        /// Wait this long for file events to settle before re-running (default: 100)
+        #[arg(long, value_name = "MS")]
+        debounce: Option<u64>,
+    },
+    /// Show everything taut knows about one test, as JSON
+    Explain {
+        /// Test ID (e.g., tests/test_math.py::TestMath::test_add)
+        node_id: String,
+    },
+    /// Find the commit that broke a test by running it at each `git bisect` step
+    Bisect {
+        /// Test ID (e.g., tests/test_math.py::TestMath::test_add)
+        node_id: String,
+
+        /// A commit where the test passes
+        #[arg(long, value_name = "REF")]
+        good: String,
+
+        /// A commit where the test fails
+        #[arg(long, value_name = "REF", default_value = "HEAD")]
+        bad: String,
+    },
+    /// Print the version of taut
+    Version {
+        /// Also print the Python interpreter, coverage tracer, cache directory and worker count
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// List tests whose outcome changed between runs without any change to their code
+    Flaky {
+        /// Print the flaky tests as a JSON array
+        #[arg(long)]
+        json: bool,
     },
     /// Cache management commands
     Cache {
@@ -101,6 +395,30 @@ pub enum CacheAction {
     Info,
     /// Clear all cached data
     Clear,
+    /// Remove cached entries for files that no longer exist
+    Prune,
+    /// Check that the cache files can be read back (exits 1 if one is corrupt)
+    Verify {
+        /// Recover what can be read from corrupt files and discard the rest
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Write the dependency database to a portable file (e.g. to share with CI)
+    Export {
+        /// File to write
+        path: PathBuf,
+        /// Project root; recorded paths are stored relative to it
+        #[arg(long, default_value = ".")]
+        root: PathBuf,
+    },
+    /// Replace the dependency database with one written by `taut cache export`
+    Import {
+        /// File written by `taut cache export`
+        path: PathBuf,
+        /// Project root to resolve the exported paths against
+        #[arg(long, default_value = ".")]
+        root: PathBuf,
+    },
 }
 
 /// Run the CLI with command line arguments from the environment.
@@ -124,15 +442,47 @@ pub fn run_with_args(args: Vec<String>) -> i32 {
 
 /// Run the CLI with parsed arguments.
 /// Returns the exit code.
-fn run_with_parsed_args(args: Args) -> i32 {
+fn run_with_parsed_args(mut args: Args) -> i32 {
     // Handle markdown help generation
     if args.markdown_help {
         print!("{}", clap_markdown::help_markdown::<Args>());
         return 0;
     }
 
+    let config = config::Config::load(Path::new("."));
+    let cache_dir = cache::resolve_cache_dir(
+        args.cache_dir.clone(),
+        config.cache_dir,
+        config.cache_per_branch,
+    );
+    let testpaths = config.testpaths;
+    args.paths = paths_or_default(std::mem::take(&mut args.paths), &testpaths);
+
+    let no_tests_code = i32::from(args.exitcode_on_no_tests);
+    let collect_timeout = args
+        .collect_timeout
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok());
     let result = match args.command {
-        Some(Commands::List { paths, filter }) => list_tests(&paths, filter.as_deref()),
+        Some(Commands::List {
+            paths,
+            filter,
+            exact,
+            json,
+            tree,
+            markers,
+        }) => {
+            let paths = paths_or_default(paths, &testpaths);
+            let collect = Collect {
+                filter: &filter,
+                exact,
+                timeout: collect_timeout,
+            };
+            if markers {
+                list_markers(&paths, collect, no_tests_code)
+            } else {
+                list_tests(&paths, collect, json, tree, no_tests_code, &cache_dir)
+            }
+        }
         Some(Commands::Watch {
             paths,
             filter,
@@ -140,16 +490,35 @@ fn run_with_parsed_args(args: Args) -> i32 {
             jobs,
             isolation,
             no_cache,
+            clear,
+            debounce,
         }) => watch_tests(
-            &paths,
-            filter.as_deref(),
-            verbose,
-            jobs,
-            &isolation,
-            no_cache,
+            &paths_or_default(paths, &testpaths),
+            WatchRun {
+                filter: &filter,
+                verbose,
+                jobs,
+                isolation: &isolation,
+                no_cache,
+                cache_dir: &cache_dir,
+                collect_timeout,
+            },
+            WatchOptions {
+                clear,
+                debounce,
+                no_tests_code,
+            },
         ),
-        Some(Commands::Cache { action }) => handle_cache_command(action),
-        None => run_tests(args),
+        Some(Commands::Explain { node_id }) => explain_test(&node_id, collect_timeout, &cache_dir),
+        Some(Commands::Flaky { json }) => report_flaky(json, &cache_dir),
+        Some(Commands::Bisect { node_id, good, bad }) => {
+            bisect_test(&node_id, &good, &bad, collect_timeout)
+        }
+        Some(Commands::Version { verbose }) => {
+            print_version(verbose, config.max_workers, &cache_dir)
+        }
+        Some(Commands::Cache { action }) => handle_cache_command(action, &cache_dir),
+        None => run_tests(args, collect_timeout, &cache_dir),
     };
 
     match result {
@@ -161,53 +530,316 @@ fn run_with_parsed_args(args: Args) -> i32 {
     }
 }
 
+/// The paths given on the command line, else the configured `testpaths`, else
+/// the current directory.
+fn paths_or_default(paths: Vec<PathBuf>, testpaths: &[PathBuf]) -> Vec<PathBuf> {
+    if !paths.is_empty() {
+        paths
+    } else if !testpaths.is_empty() {
+        testpaths.to_vec()
+    } else {
+        vec![PathBuf::from(".")]
+    }
+}
+
 /// Generate markdown documentation for the CLI.
 pub fn generate_markdown_help() -> String {
     clap_markdown::help_markdown::<Args>()
 }
 
-fn list_tests(paths: &[PathBuf], filter: Option<&str>) -> Result<i32> {
-    let test_files = discovery::find_test_files(paths)?;
+/// A test in `list --json` output.
+#[derive(serde::Serialize)]
+struct ListedTest<'a> {
+    #[serde(flatten)]
+    item: &'a TestItem,
+    /// Why a plain run would skip it (`@skip` reason or "unchanged"), if it would
+    skip_reason: Option<String>,
+}
 
-    if test_files.is_empty() {
-        output::print_no_tests_found();
-        return Ok(0);
+/// How `taut list` collects tests.
+#[derive(Clone, Copy)]
+struct Collect<'a> {
+    filter: &'a [String],
+    /// Filters match whole names only
+    exact: bool,
+    /// Give up on files that take longer than this to parse
+    timeout: Option<Duration>,
+}
+
+impl Collect<'_> {
+    fn tests(&self, paths: &[PathBuf], config: &config::Config) -> Result<discovery::Collection> {
+        let patterns = test_patterns(config, self.timeout);
+        let test_files = discovery::find_test_files_with(paths, &config.exclude, &patterns)?;
+        discovery::extract_tests_with(&test_files, self.filter, self.exact, &patterns)
     }
+}
 
-    let all_tests = discovery::extract_tests(&test_files, filter)?;
+/// The test patterns from `config`, giving up on files that take longer than
+/// `collect_timeout` to parse.
+fn test_patterns(
+    config: &config::Config,
+    collect_timeout: Option<Duration>,
+) -> discovery::TestPatterns {
+    discovery::TestPatterns::from_config(config).with_collect_timeout(collect_timeout)
+}
+
+fn list_tests(
+    paths: &[PathBuf],
+    collect: Collect,
+    json: bool,
+    tree: bool,
+    no_tests_code: i32,
+    cache_dir: &Path,
+) -> Result<i32> {
+    let config = config::Config::load(&paths[0]);
+    let collection = collect.tests(paths, &config)?;
+    warn_collection_errors(&collection.errors);
+    warn_collection_errors(&collection.warnings);
+    let mut all_tests = collection.tests;
+    set_rootdir(&mut all_tests, &config);
+
+    if json {
+        // Say which tests a plain `taut` run would skip, and why
+        let mut selector = selection::TestSelector::new(cache_dir, config.fingerprint.clone());
+        selector.index_files(paths);
+        let listed: Vec<ListedTest> = all_tests
+            .iter()
+            .map(|item| {
+                let decision = selector.decision(item);
+                ListedTest {
+                    item,
+                    skip_reason: item.skip_reason().or_else(|| {
+                        (!decision.should_run()).then(|| decision.reason().to_string())
+                    }),
+                }
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&listed)?);
+        return Ok(if listed.is_empty() { no_tests_code } else { 0 });
+    }
 
     if all_tests.is_empty() {
         output::print_no_tests_found();
-        return Ok(0);
+        return Ok(no_tests_code);
     }
 
-    for test in &all_tests {
-        println!("{}", test.id());
+    if tree {
+        print!("{}", output::format_test_tree(&all_tests));
+    } else {
+        for test in &all_tests {
+            println!("{}", test.id());
+        }
     }
 
     println!("\n{} tests", all_tests.len());
     Ok(0)
 }
 
-fn watch_tests(
-    paths: &[PathBuf],
-    filter: Option<&str>,
+fn list_markers(paths: &[PathBuf], collect: Collect, no_tests_code: i32) -> Result<i32> {
+    let config = config::Config::load(&paths[0]);
+    let collection = collect.tests(paths, &config)?;
+    warn_collection_errors(&collection.errors);
+    warn_collection_errors(&collection.warnings);
+
+    if collection.tests.is_empty() {
+        output::print_no_tests_found();
+        return Ok(no_tests_code);
+    }
+
+    let table = output::format_marker_table(&collection.tests);
+    if table.is_empty() {
+        println!("No markers in use.");
+    } else {
+        print!("{}", table);
+    }
+    Ok(0)
+}
+
+fn explain_test(node_id: &str, collect_timeout: Option<Duration>, cache_dir: &Path) -> Result<i32> {
+    let config = config::Config::load(Path::new("."));
+    let patterns = test_patterns(&config, collect_timeout);
+    let item = discovery::resolve_test_id(node_id, &patterns, config.rootdir.as_deref())?;
+
+    // Index the project so current block checksums are up to date
+    let mut selector = selection::TestSelector::new(cache_dir, config.fingerprint.clone());
+    selector.index_files(&[PathBuf::from(".")]);
+
+    let explanation = selector.explain(&item);
+    println!("{}", serde_json::to_string_pretty(&explanation)?);
+    Ok(0)
+}
+
+/// Find the first commit between `good` and `bad` at which `node_id` fails.
+/// Exits 0 when one commit is identified, 1 when skipped commits leave it ambiguous.
+fn bisect_test(
+    node_id: &str,
+    good: &str,
+    bad: &str,
+    collect_timeout: Option<Duration>,
+) -> Result<i32> {
+    let outcome = bisect::bisect(good, bad, |commit| {
+        let short = &commit[..commit.len().min(10)];
+        // The config and test file can change from commit to commit
+        let config = config::Config::load(Path::new("."));
+        let patterns = test_patterns(&config, collect_timeout);
+        let Ok(item) = discovery::resolve_test_id(node_id, &patterns, config.rootdir.as_deref())
+        else {
+            println!("{}  not found, skipping", short);
+            return Ok(bisect::Verdict::Skip);
+        };
+
+        let options = runner::RunOptions {
+            parallel: false,
+            ..runner::RunOptions::default()
+        };
+        let results = runner::run_tests_with_options(&[item], &options, |_| {})?;
+        let verdict = match results.results.first() {
+            Some(result) if result.skipped => bisect::Verdict::Skip,
+            Some(result) if result.passed => bisect::Verdict::Good,
+            _ => bisect::Verdict::Bad,
+        };
+        let label = match verdict {
+            bisect::Verdict::Good => "passed",
+            bisect::Verdict::Bad => "failed",
+            bisect::Verdict::Skip => "skipped",
+        };
+        println!("{}  {}", short, label);
+        Ok(verdict)
+    })?;
+
+    println!();
+    match outcome {
+        bisect::Outcome::FirstBad(commit) => {
+            println!("First bad commit: {}", commit);
+            Ok(0)
+        }
+        bisect::Outcome::Ambiguous(candidates) => {
+            println!("Skipped commits left the first bad commit ambiguous; it is one of:");
+            for commit in candidates {
+                println!("  {}", commit);
+            }
+            Ok(1)
+        }
+    }
+}
+
+/// Print taut's version and, with `verbose`, what a bug report needs to know
+/// about the environment tests run in.
+fn print_version(verbose: bool, max_workers: Option<usize>, cache_dir: &Path) -> Result<i32> {
+    println!("taut {}", env!("CARGO_PKG_VERSION"));
+    if !verbose {
+        return Ok(0);
+    }
+
+    let probe = std::process::Command::new("python3")
+        .args([
+            "-c",
+            "import sys; print(sys.executable); print(hasattr(sys, 'monitoring')); print(sys.version)",
+        ])
+        .output();
+    match probe {
+        Ok(output) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let mut lines = stdout.lines();
+            let executable = lines.next().unwrap_or_default();
+            let monitoring = lines.next() == Some("True");
+            let version = lines.collect::<Vec<_>>().join(" ");
+            println!("Python: {}", version);
+            println!("Python executable: {}", executable);
+            println!(
+                "Coverage tracer: {}",
+                if monitoring {
+                    "sys.monitoring"
+                } else {
+                    "sys.settrace (sys.monitoring needs Python 3.12+)"
+                }
+            );
+        }
+        _ => println!("Python: python3 not found or failed to start"),
+    }
+
+    println!("Cache directory: {}", cache_dir.display());
+    match max_workers {
+        Some(workers) => println!("Workers: {} (max_workers in pyproject.toml)", workers),
+        None => {
+            let cpus = std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(4);
+            println!("Workers: {} (CPU count)", cpus);
+        }
+    }
+    Ok(0)
+}
+
+fn report_flaky(json: bool, cache_dir: &Path) -> Result<i32> {
+    let flaky = depdb::DependencyDatabase::load(cache_dir).flaky_tests();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&flaky)?);
+        return Ok(0);
+    }
+
+    if flaky.is_empty() {
+        println!("No flaky tests recorded.");
+        return Ok(0);
+    }
+    for test in &flaky {
+        let history: String = test
+            .history
+            .iter()
+            .map(|&passed| if passed { '.' } else { 'F' })
+            .collect();
+        println!(
+            "{}  {} passed, {} failed  [{}]",
+            test.test, test.passes, test.failures, history
+        );
+    }
+    println!();
+    println!("{} flaky test(s)", flaky.len());
+    Ok(0)
+}
+
+/// How each watch-mode run selects and runs tests.
+struct WatchRun<'a> {
+    filter: &'a [String],
     verbose: bool,
+    /// Number of parallel jobs (falls back to config, then the CPU count)
     jobs: Option<usize>,
-    isolation: &str,
+    isolation: &'a str,
+    /// Run every test and leave the cache untouched
     no_cache: bool,
-) -> Result<i32> {
+    cache_dir: &'a Path,
+    /// Give up on files that take longer than this to parse
+    collect_timeout: Option<Duration>,
+}
+
+/// How watch mode reacts to changes (as opposed to how tests are run).
+struct WatchOptions {
+    /// Clear the screen before each re-run
+    clear: bool,
+    /// Debounce window in milliseconds (falls back to config, then the default)
+    debounce: Option<u64>,
+    /// If nonzero, stop with this exit code when the first run finds no tests
+    no_tests_code: i32,
+}
+
+fn watch_tests(paths: &[PathBuf], mut run: WatchRun, watch: WatchOptions) -> Result<i32> {
     // Load config from pyproject.toml
     let config = config::Config::load(&paths[0]);
-    let jobs = jobs.or(config.max_workers);
+    run.jobs = run.jobs.or(config.max_workers);
+    let debounce = Duration::from_millis(
+        watch
+            .debounce
+            .or(config.debounce_ms)
+            .unwrap_or(DEFAULT_DEBOUNCE_MS),
+    );
 
     let (tx, rx) = mpsc::channel();
 
     let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
-        if let Ok(event) = res {
-            if event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove() {
-                let _ = tx.send(event);
-            }
+        if let Ok(event) = res
+            && (event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove())
+        {
+            let _ = tx.send(event);
         }
     })?;
 
@@ -224,78 +856,105 @@ fn watch_tests(
     println!("Watching for changes... (Ctrl+C to stop)\n");
 
     // Initial run
-    run_tests_for_watch(paths, filter, verbose, jobs, isolation, no_cache);
-
-    // Debounce: wait for events to settle
-    loop {
-        match rx.recv() {
-            Ok(event) => {
-                // Collect changed Python files
-                let changed: Vec<_> = event
-                    .paths
-                    .iter()
-                    .filter(|p| p.extension().map(|e| e == "py").unwrap_or(false))
-                    .collect();
-
-                if !changed.is_empty() {
-                    // Drain any pending events (debounce)
-                    std::thread::sleep(Duration::from_millis(100));
-                    while rx.try_recv().is_ok() {}
-
-                    // Show changed files
-                    for path in &changed {
-                        println!("changed: {}", path.display());
-                    }
-                    println!();
+    let found = run_tests_for_watch(paths, &config, &run);
+    if !found && watch.no_tests_code != 0 {
+        return Ok(watch.no_tests_code);
+    }
 
-                    run_tests_for_watch(paths, filter, verbose, jobs, isolation, no_cache);
-                }
-            }
-            Err(_) => break,
+    // Only emit escape codes to an interactive, color-enabled terminal
+    let is_tty = io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+
+    // Debounce: wait for events to settle, then re-run for the whole batch
+    while let Ok(event) = rx.recv() {
+        let changed = debounce_changes(&rx, event, debounce);
+        if changed.is_empty() {
+            continue;
         }
+
+        if watch.clear {
+            let _ = output::clear_screen(&mut io::stdout(), is_tty);
+        }
+
+        // Show changed files
+        for path in &changed {
+            println!("changed: {}", path.display());
+        }
+        println!();
+
+        run_tests_for_watch(paths, &config, &run);
     }
 
     Ok(0)
 }
 
-fn run_tests_for_watch(
-    paths: &[PathBuf],
-    filter: Option<&str>,
-    verbose: bool,
-    jobs: Option<usize>,
-    isolation: &str,
-    no_cache: bool,
-) {
-    let test_files = match discovery::find_test_files(paths) {
+/// Default watch-mode debounce window.
+const DEFAULT_DEBOUNCE_MS: u64 = 100;
+
+/// Coalesce a burst of file events into the set of changed Python files.
+///
+/// Starting from `first`, keeps receiving until no event has arrived for
+/// `window`, so editors that save in bursts trigger a single re-run.
+pub fn debounce_changes(
+    rx: &mpsc::Receiver<notify::Event>,
+    first: notify::Event,
+    window: Duration,
+) -> Vec<PathBuf> {
+    let mut changed: Vec<PathBuf> = Vec::new();
+    let mut add = |event: notify::Event| {
+        for path in event.paths {
+            let is_python = path.extension().is_some_and(|e| e == "py");
+            if is_python && !changed.contains(&path) {
+                changed.push(path);
+            }
+        }
+    };
+
+    add(first);
+    while let Ok(event) = rx.recv_timeout(window) {
+        add(event);
+    }
+    changed
+}
+
+/// One watch-mode run. Returns false if no tests were found.
+fn run_tests_for_watch(paths: &[PathBuf], config: &config::Config, run: &WatchRun) -> bool {
+    let patterns = test_patterns(config, run.collect_timeout);
+    let test_files = match discovery::find_test_files_with(paths, &config.exclude, &patterns) {
         Ok(f) => f,
         Err(e) => {
             eprintln!("Error discovering tests: {}", e);
-            return;
+            return true;
         }
     };
 
     if test_files.is_empty() {
         output::print_no_tests_found();
-        return;
+        return false;
     }
 
-    let all_tests = match discovery::extract_tests(&test_files, filter) {
-        Ok(t) => t,
-        Err(e) => {
-            eprintln!("Error extracting tests: {}", e);
-            return;
-        }
-    };
+    let mut all_tests =
+        match discovery::extract_tests_with(&test_files, run.filter, false, &patterns) {
+            Ok(collection) => {
+                warn_collection_errors(&collection.errors);
+                warn_collection_errors(&collection.warnings);
+                collection.tests
+            }
+            Err(e) => {
+                eprintln!("Error extracting tests: {}", e);
+                return true;
+            }
+        };
+    set_rootdir(&mut all_tests, config);
 
     if all_tests.is_empty() {
         output::print_no_tests_found();
-        return;
+        return false;
     }
 
-    let mut selector = selection::TestSelector::new();
+    let mut selector = selection::TestSelector::new(run.cache_dir, config.fingerprint.clone());
     selector.index_files(paths);
 
-    let (tests_to_run, skipped_tests) = if no_cache {
+    let (tests_to_run, skipped_tests) = if run.no_cache {
         (all_tests.clone(), Vec::new())
     } else {
         let selection = selector.select_tests(&all_tests);
@@ -303,34 +962,37 @@ fn run_tests_for_watch(
         let skipped: Vec<_> = selection
             .to_skip
             .into_iter()
-            .map(|(item, reason)| runner::skipped_result(&item, &reason))
+            .map(|(item, reason)| {
+                runner::skipped_result(&item, runner::SkipKind::Unchanged, &reason)
+            })
             .collect();
         (to_run, skipped)
     };
 
-    let printer = output::ProgressPrinter::new(verbose);
+    let printer =
+        output::ProgressPrinter::new(output::Verbosity::from_flags(false, run.verbose.into()));
 
     for result in &skipped_tests {
         printer.print_result(result);
     }
 
-    let collect_coverage = !no_cache;
+    let collect_coverage = !run.no_cache;
     let run_results = match runner::run_tests(
         &tests_to_run,
         true,
-        jobs,
+        run.jobs,
         collect_coverage,
-        runner::IsolationMode::parse(isolation),
+        runner::IsolationMode::parse(run.isolation),
         |result| printer.print_result(result),
     ) {
         Ok(r) => r,
         Err(e) => {
             eprintln!("Error running tests: {}", e);
-            return;
+            return true;
         }
     };
 
-    if !no_cache {
+    if !run.no_cache {
         for result in &run_results.results {
             selector.record_result(result);
         }
@@ -346,14 +1008,21 @@ fn run_tests_for_watch(
     };
 
     let failed_tests = printer.get_failed_tests();
-    output::print_summary(&combined, &failed_tests);
+    output::print_summary(
+        &combined,
+        &failed_tests,
+        &[],
+        output::TracebackStyle::Short,
+        output::ShowCapture::Failed,
+    );
+    true
 }
 
-fn handle_cache_command(action: CacheAction) -> Result<i32> {
+fn handle_cache_command(action: CacheAction, cache_dir: &Path) -> Result<i32> {
     match action {
         CacheAction::Info => {
-            let cache_stats = cache::get_cache_stats();
-            let depdb_stats = depdb::DependencyDatabase::load().stats();
+            let cache_stats = cache::get_cache_stats(cache_dir);
+            let depdb_stats = depdb::DependencyDatabase::load(cache_dir).stats();
 
             println!("Cache location: {}", cache_stats.cache_dir.display());
             println!("Cache exists: {}", cache_stats.exists);
@@ -375,7 +1044,7 @@ fn handle_cache_command(action: CacheAction) -> Result<i32> {
             }
         }
         CacheAction::Clear => {
-            let (size_bytes, file_count) = cache::clear_cache()?;
+            let (size_bytes, file_count) = cache::clear_cache(cache_dir)?;
             if file_count > 0 {
                 let size_kb = size_bytes as f64 / 1024.0;
                 println!("Cache cleared: {:.1} KB ({} files)", size_kb, file_count);
@@ -383,53 +1052,333 @@ fn handle_cache_command(action: CacheAction) -> Result<i32> {
                 println!("Cache already empty.");
             }
         }
+        CacheAction::Prune => {
+            let mut depdb = depdb::DependencyDatabase::load(cache_dir);
+            let stats = depdb.prune();
+            depdb.save(cache_dir);
+            println!(
+                "Pruned {} tests and {} blocks.",
+                stats.removed_tests, stats.removed_blocks
+            );
+        }
+        CacheAction::Verify { repair } => {
+            let depdb_path = depdb::DependencyDatabase::path(cache_dir);
+            let depdb_health = depdb::DependencyDatabase::verify(&depdb_path);
+            let index_health = cache::verify_block_index(cache_dir);
+            for (name, health) in [
+                ("Dependency database", &depdb_health),
+                ("Block index", &index_health),
+            ] {
+                match health {
+                    cache::FileHealth::Missing => println!("{name}: not written yet"),
+                    cache::FileHealth::Valid => println!("{name}: ok"),
+                    cache::FileHealth::Corrupt(reason) => {
+                        println!("{name}: corrupt ({reason})")
+                    }
+                }
+            }
+
+            let depdb_corrupt = matches!(depdb_health, cache::FileHealth::Corrupt(_));
+            let index_corrupt = matches!(index_health, cache::FileHealth::Corrupt(_));
+            if !repair {
+                if depdb_corrupt || index_corrupt {
+                    println!("Run `taut cache verify --repair` to fix.");
+                    return Ok(1);
+                }
+                return Ok(0);
+            }
+            if depdb_corrupt {
+                let kept = depdb::DependencyDatabase::repair(&depdb_path)?;
+                println!("Repaired dependency database, keeping {kept} tests.");
+            }
+            if index_corrupt {
+                cache::remove_block_index(cache_dir)?;
+                println!("Removed block index; the next run rebuilds it.");
+            }
+        }
+        CacheAction::Export { path, root } => {
+            let root = canonical_root(&root)?;
+            let stats = depdb::DependencyDatabase::load(cache_dir).export(&root, &path)?;
+            println!(
+                "Exported {} tests and {} blocks to {}",
+                stats.total_tests,
+                stats.total_blocks,
+                path.display()
+            );
+        }
+        CacheAction::Import { path, root } => {
+            let root = canonical_root(&root)?;
+            let depdb = depdb::DependencyDatabase::import(&root, &path)?;
+            depdb.save(cache_dir);
+            let stats = depdb.stats();
+            println!(
+                "Imported {} tests and {} blocks from {}",
+                stats.total_tests,
+                stats.total_blocks,
+                path.display()
+            );
+        }
     }
     Ok(0)
 }
 
-fn run_tests(args: Args) -> Result<i32> {
+/// Run a `before_all`/`after_all` hook from the project root and show its output.
+/// Hooks ending in `.py` run with `python3`; anything else goes through `sh -c`.
+/// Returns whether the hook exited successfully.
+fn run_hook(name: &str, command: &str, dir: &Path) -> Result<bool> {
+    let mut cmd = if command.ends_with(".py") && !command.contains(char::is_whitespace) {
+        let mut cmd = std::process::Command::new("python3");
+        cmd.arg(command);
+        cmd
+    } else {
+        let mut cmd = std::process::Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+    let out = cmd
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("Failed to run {} hook `{}`", name, command))?;
+    output::print_hook_output(name, command, &out);
+    Ok(out.status.success())
+}
+
+/// Report collection problems (unreadable files, misspelled markers) on stderr.
+fn warn_collection_errors(errors: &[(PathBuf, String)]) {
+    for (_, error) in errors {
+        eprintln!("Warning: {}", error);
+    }
+}
+
+/// Report test IDs relative to the project's rootdir.
+fn set_rootdir(tests: &mut [TestItem], config: &config::Config) {
+    for test in tests {
+        test.rootdir = config.rootdir.clone();
+    }
+}
+
+/// Cached paths are canonical, so the root they're made relative to must be too.
+fn canonical_root(root: &Path) -> Result<PathBuf> {
+    root.canonicalize()
+        .with_context(|| format!("Project root not found: {}", root.display()))
+}
+
+/// Exit code when `--bail-on-worker-crash` aborts the run.
+const WORKER_CRASH_EXIT_CODE: i32 = 2;
+
+/// Exit code when `--deadline` left some tests unrun.
+const DEADLINE_EXIT_CODE: i32 = 3;
+
+fn run_tests(args: Args, collect_timeout: Option<Duration>, cache_dir: &Path) -> Result<i32> {
+    let started = Instant::now();
+
+    // Positional args are either paths to discover or explicit test IDs;
+    // --from-file replaces both with the listed IDs
+    let (test_ids, paths): (Vec<String>, Vec<PathBuf>) = match args.from_file {
+        Some(ref list) => (discovery::read_test_ids(list)?, Vec::new()),
+        None => {
+            let (ids, paths): (Vec<PathBuf>, Vec<PathBuf>) = args
+                .paths
+                .iter()
+                .cloned()
+                .partition(|p| discovery::is_test_id(&p.to_string_lossy()));
+            let ids = ids
+                .iter()
+                .map(|id| id.to_string_lossy().into_owned())
+                .collect();
+            (ids, paths)
+        }
+    };
+
     // Load config from pyproject.toml
-    let config = config::Config::load(&args.paths[0]);
+    let config_root = paths.first().cloned().unwrap_or_else(|| PathBuf::from("."));
+    let config = config::Config::load(&config_root);
 
     // Resolve jobs: CLI flag > pyproject.toml > None (will use CPU count)
     let jobs = args.jobs.or(config.max_workers);
 
-    // 1. Discover test files
-    let test_files = discovery::find_test_files(&args.paths)?;
+    // Repeated runs would record duplicate, conflicting coverage, so skip the cache.
+    // Priming exists to fill the cache, so it always records.
+    let no_cache = !args.prime && (args.no_cache || args.repeat > 1);
+    if no_cache && args.cov_fail_under.is_some() {
+        anyhow::bail!(
+            "--cov-fail-under needs coverage collection, which --no-cache and --repeat disable"
+        );
+    }
 
-    if test_files.is_empty() {
-        output::print_no_tests_found();
-        return Ok(0);
+    // A seed asks for a shuffled run, which would scramble any other order
+    if (args.seed.is_some() || args.last_seed)
+        && !matches!(args.order, OrderBy::Discovery | OrderBy::Random)
+    {
+        anyhow::bail!("--seed and --last-seed shuffle the tests, so --order must be random");
+    }
+
+    let format = args.output;
+    let coverage_report = match args.coverage_report.as_deref() {
+        Some([report_format, report_path]) => {
+            let Some(report_format) = output::CoverageReportFormat::parse(report_format) else {
+                anyhow::bail!(
+                    "Unknown coverage report format '{}' (expected lcov or html)",
+                    report_format
+                );
+            };
+            Some((report_format, PathBuf::from(report_path)))
+        }
+        _ => None,
+    };
+    let human = format == output::OutputFormat::Human;
+    let no_tests_found = || {
+        if human {
+            output::print_no_tests_found();
+        } else {
+            output::print_tap(&runner::TestResults {
+                results: Vec::new(),
+                total_duration: Duration::ZERO,
+            });
+        }
+    };
+
+    // Time spent in each phase, for --profile
+    let mut profile: Vec<(&str, Duration)> = Vec::new();
+    let mut phase = Instant::now();
+
+    // 1. Discover test files
+    let patterns = test_patterns(&config, collect_timeout);
+    let mut test_files = if paths.is_empty() {
+        Vec::new()
+    } else {
+        discovery::find_test_files_with(&paths, &config.exclude, &patterns)?
+    };
+    let mut doctest_files = if args.doctests && !paths.is_empty() {
+        discovery::find_doctest_modules(&paths, &config.exclude, &patterns)?
+    } else {
+        Vec::new()
+    };
+    let ignore = exclude::IgnoreList::new(&args.ignore);
+    test_files.retain(|file| !ignore.is_ignored(file));
+    doctest_files.retain(|file| !ignore.is_ignored(file));
+    profile.push(("find_test_files", phase.elapsed()));
+
+    if test_files.is_empty() && doctest_files.is_empty() && test_ids.is_empty() {
+        no_tests_found();
+        return Ok(i32::from(args.exitcode_on_no_tests));
     }
 
-    // 2. Parse and extract test items
-    let all_tests = discovery::extract_tests(&test_files, args.filter.as_deref())?;
+    // 2. Parse and extract test items, then add explicitly requested tests
+    phase = Instant::now();
+    let collection =
+        discovery::extract_tests_with(&test_files, &args.filter, args.exact, &patterns)?;
+    let doctests =
+        discovery::extract_doctests(&doctest_files, &args.filter, args.exact, &patterns)?;
+    let mut all_tests = collection.tests;
+    all_tests.extend(doctests.tests);
+    let mut collection_errors = collection.errors;
+    collection_errors.extend(doctests.errors);
+    // Misspelled markers would silently do nothing, so always mention them
+    if args.strict_markers {
+        collection_errors.extend(collection.warnings);
+    } else {
+        warn_collection_errors(&collection.warnings);
+    }
+    // Files that couldn't be collected fail the run unless explicitly tolerated
+    let collection_ok = collection_errors.is_empty() || args.continue_on_collection_errors;
+    let mut index_roots = paths;
+    for id in &test_ids {
+        let item = match discovery::resolve_test_id(id, &patterns, config.rootdir.as_deref()) {
+            Ok(item) => item,
+            // IDs collected from an earlier run may be stale; don't abort the rerun
+            Err(e) if args.from_file.is_some() => {
+                eprintln!("Warning: {}", e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        if !all_tests.iter().any(|t| same_test(t, &item)) {
+            index_roots.push(item.file.clone());
+            all_tests.push(item);
+        }
+    }
+    set_rootdir(&mut all_tests, &config);
+    let mut deselected = Vec::new();
+    for id in &args.deselect {
+        let (matched, kept): (Vec<_>, Vec<_>) =
+            all_tests.into_iter().partition(|t| t.matches_id(id));
+        if matched.is_empty() {
+            eprintln!("Warning: --deselect {} matched no tests", id);
+        }
+        all_tests = kept;
+        deselected.extend(matched);
+    }
+    // Like -k, this narrows the selection rather than reporting skips
+    if args.skip_slow || args.only_slow {
+        all_tests.retain(|t| t.is_slow() == args.only_slow);
+    }
 
     if all_tests.is_empty() {
-        output::print_no_tests_found();
-        return Ok(0);
+        no_tests_found();
+        if human {
+            output::print_collection_errors(&collection_errors);
+        } else {
+            warn_collection_errors(&collection_errors);
+        }
+        return Ok(if collection_ok {
+            i32::from(args.exitcode_on_no_tests)
+        } else {
+            1
+        });
     }
 
+    profile.push(("extract_tests", phase.elapsed()));
+
     // 3. Set up test selector for dependency tracking
-    let mut selector = selection::TestSelector::new();
+    phase = Instant::now();
+    let mut selector = selection::TestSelector::new(cache_dir, config.fingerprint.clone());
 
     // Index all Python files in the search paths for coverage mapping
-    selector.index_files(&args.paths);
+    selector.index_files(&index_roots);
+    profile.push(("index_files", phase.elapsed()));
 
     // 4. Determine which tests to run (handle @skip markers first)
-    let (mut tests_to_run, mut skipped_tests): (Vec<_>, Vec<_>) = if args.no_cache {
-        // Run everything without caching, but still respect @skip markers
+    // A coverage threshold is only meaningful over the whole suite, so it also runs everything
+    phase = Instant::now();
+    let full_run = no_cache || args.prime || args.cov_fail_under.is_some();
+    let (mut tests_to_run, mut skipped_tests): (Vec<_>, Vec<_>) = if full_run {
+        // Run everything (recording unless caching is off), but still respect @skip markers
         (all_tests.clone(), Vec::new())
     } else {
-        let selection = selector.select_tests(&all_tests);
+        let selection = match &args.changed_since {
+            Some(reference) => {
+                let changed = selection::ChangedBlocks::since(reference)
+                    .with_context(|| format!("--changed-since {reference}"))?;
+                selector.tests_affected_by(&all_tests, &changed)
+            }
+            None => selector.select_tests(&all_tests),
+        };
+        if selection
+            .to_run
+            .iter()
+            .any(|(_, decision)| *decision == depdb::TestRunDecision::ConfigChanged)
+        {
+            eprintln!(
+                "taut: [tool.taut] configuration changed since the last run, running all tests"
+            );
+        }
+        if args.explain && human {
+            for (item, decision) in &selection.to_run {
+                output::print_rerun_reason(&item.id(), &decision.explanation());
+            }
+        }
         let to_run: Vec<_> = selection.to_run.into_iter().map(|(item, _)| item).collect();
         let skipped: Vec<_> = selection
             .to_skip
             .into_iter()
-            .map(|(item, reason)| runner::skipped_result(&item, &reason))
+            .map(|(item, reason)| {
+                runner::skipped_result(&item, runner::SkipKind::Unchanged, &reason)
+            })
             .collect();
         (to_run, skipped)
     };
+    profile.push(("select_tests", phase.elapsed()));
 
     // Handle @skip markers - move skipped tests to skipped_tests
     let (marker_skipped, remaining): (Vec<_>, Vec<_>) =
@@ -440,36 +1389,225 @@ fn run_tests(args: Args) -> Result<i32> {
         let reason = item
             .skip_reason()
             .unwrap_or_else(|| "marked with @skip".to_string());
-        runner::skipped_result(&item, &reason)
+        runner::skipped_result(&item, runner::SkipKind::Marker, &reason)
     }));
+    skipped_tests.extend(
+        deselected
+            .iter()
+            .map(|item| runner::skipped_result(item, runner::SkipKind::Deselected, "deselected")),
+    );
+
+    order_tests(
+        &mut tests_to_run,
+        args.order,
+        |item| selector.last_duration(item),
+        |item| selector.last_failed(item),
+    );
+
+    let shuffle_seed =
+        if args.shuffle || args.seed.is_some() || args.last_seed || args.order == OrderBy::Random {
+            let seed = if args.last_seed {
+                cache::load_seed(cache_dir).ok_or_else(|| {
+                    anyhow::anyhow!("--last-seed: no recorded seed, run with --shuffle first")
+                })?
+            } else {
+                args.seed.unwrap_or_else(shuffle::random_seed)
+            };
+            shuffle::shuffle(&mut tests_to_run, seed);
+            if human && !args.summary_only {
+                output::print_seed(seed);
+            } else {
+                // Keep the TAP stream or summary line on stdout clean, but never lose the seed
+                eprintln!("Using --seed {}", seed);
+            }
+            Some(seed)
+        } else {
+            None
+        };
+
+    let selected_count = tests_to_run.len();
+    let capped = args.max_collect.filter(|&max| max < selected_count);
+    if let Some(max) = capped {
+        tests_to_run.truncate(max);
+    }
+
+    // Resume from where the last --stepwise run stopped, if that test is still selected
+    let stepwise_from = if args.stepwise {
+        cache::load_stepwise(cache_dir)
+    } else {
+        None
+    };
+    let stepwise_skipped = stepwise_from
+        .and_then(|stopped_at| {
+            tests_to_run
+                .iter()
+                .position(|t| depdb::TestId::from(t) == stopped_at)
+        })
+        .map(|pos| {
+            tests_to_run.drain(..pos);
+            (pos, tests_to_run[0].id())
+        });
+
+    if args.dry_run {
+        output::print_dry_run(&tests_to_run, &skipped_tests);
+        return Ok(0);
+    }
+
+    // Only remember seeds of runs that actually ran, so --last-seed can replay them
+    if let Some(seed) = shuffle_seed {
+        cache::save_seed(cache_dir, seed);
+    }
+
+    if args.repeat > 1 {
+        tests_to_run = tests_to_run
+            .into_iter()
+            .flat_map(|item| std::iter::repeat_n(item, args.repeat))
+            .collect();
+    }
 
     // 5. Run tests with streaming output
-    let printer = output::ProgressPrinter::new(args.verbose);
+    let mut printer = output::ProgressPrinter::new(output::Verbosity::from_flags(
+        args.quiet || args.summary_only,
+        args.verbose,
+    ))
+    .with_quiet_skips(args.quiet_skips);
+    // The counter relies on rewriting the line, which only works on a terminal
+    if args.progress && human && io::stdout().is_terminal() {
+        printer = printer.with_progress(skipped_tests.len() + tests_to_run.len());
+    }
+
+    if let Some((count, id)) = stepwise_skipped
+        .as_ref()
+        .filter(|(count, _)| human && !args.summary_only && *count > 0)
+    {
+        output::print_stepwise_resume(*count, id);
+    }
 
     // Print skipped tests first
-    for result in &skipped_tests {
-        printer.print_result(result);
+    if human {
+        for result in &skipped_tests {
+            printer.print_result(result);
+        }
+    }
+
+    // Project setup hook; tests only run if it succeeds
+    let hook_dir = config.rootdir.clone().unwrap_or_else(|| PathBuf::from("."));
+    if let Some(ref command) = config.before_all
+        && !run_hook("before_all", command, &hook_dir)?
+    {
+        anyhow::bail!("before_all hook `{}` failed, not running tests", command);
     }
 
+    let coverage_include = [args.coverage_include.as_slice(), &config.coverage_include].concat();
+    let coverage_exclude = [args.coverage_exclude.as_slice(), &config.coverage_exclude].concat();
+
     // Run actual tests with coverage collection (when caching enabled)
-    let collect_coverage = !args.no_cache;
-    let run_results = runner::run_tests(
-        &tests_to_run,
-        !args.no_parallel,
+    let options = runner::RunOptions {
+        parallel: !args.no_parallel,
         jobs,
-        collect_coverage,
-        runner::IsolationMode::parse(&args.isolation),
-        |result| printer.print_result(result),
-    )?;
+        collect_coverage: !no_cache || coverage_report.is_some(),
+        isolation: runner::IsolationMode::parse(&args.isolation),
+        fixture_setup_timeout: args
+            .fixtures_setup_timeout
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok()),
+        assert_rewrite: args.assert_rewrite,
+        capture: !args.no_capture,
+        worker_max_tests: args.worker_max_tests,
+        workers: args.workers,
+        ordered_output: args.ordered_output,
+        bail_on_worker_crash: args.bail_on_worker_crash,
+        deadline: args
+            .deadline
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            .map(|limit| started + limit),
+        kill_at_deadline: args.deadline_kill,
+        parallel_groups: args.parallel_groups,
+        async_mode: runner::AsyncMode::parse(&args.async_mode),
+        import_mode: runner::ImportMode::parse(&args.import_mode),
+        warnings: runner::WarningsMode::parse(&args.warnings),
+        coverage_include: coverage_include.clone(),
+        coverage_exclude: coverage_exclude.clone(),
+        file_affinity: args.file_affinity,
+    };
+    let on_result = |result: &runner::TestResult| {
+        if human {
+            printer.print_result(result)
+        }
+    };
+    phase = Instant::now();
+    let run_results = if args.stepwise {
+        run_until_failure(&tests_to_run, &options, on_result)
+    } else {
+        runner::run_tests_with_options(&tests_to_run, &options, on_result)
+    };
+    profile.push(("run_tests", phase.elapsed()));
+    let run_results = match run_results {
+        Ok(results) => results,
+        Err(e) => match e.downcast_ref::<worker_pool::WorkerCrashed>() {
+            Some(crash) => {
+                output::print_worker_crash(crash);
+                if let Some(ref command) = config.after_all {
+                    run_hook("after_all", command, &hook_dir)?;
+                }
+                return Ok(WORKER_CRASH_EXIT_CODE);
+            }
+            None => return Err(e),
+        },
+    };
+    let stepwise_stop = if args.stepwise {
+        let stopped_at = run_results
+            .results
+            .iter()
+            .find(|r| !r.passed && !r.skipped)
+            .map(|r| &r.item);
+        cache::save_stepwise(cache_dir, stopped_at.map(depdb::TestId::from).as_ref());
+        stopped_at.map(|item| item.id())
+    } else {
+        None
+    };
+
+    // Compare against the recorded durations before they are overwritten
+    let regressions = if args.compare_durations {
+        duration_regressions(&run_results.results, args.duration_threshold, |item| {
+            selector.last_duration(item)
+        })
+    } else {
+        Vec::new()
+    };
 
     // 6. Record coverage for dependency tracking
-    if !args.no_cache {
+    if !no_cache {
         for result in &run_results.results {
             selector.record_result(result);
         }
         selector.save();
     }
 
+    // Explicit includes can bring back files the default filter drops
+    let coverage_filter = output::CoverageFilter::new(&coverage_include, &coverage_exclude);
+    let merged_coverage = output::merge_coverage(&run_results.results, &coverage_filter);
+    let coverage_check = args.cov_fail_under.map(|threshold| {
+        let totals =
+            output::coverage_totals(&merged_coverage, selector.block_index(), &coverage_filter);
+        (totals, threshold)
+    });
+    if let Some((report_format, report_path)) = coverage_report {
+        let written = match report_format {
+            output::CoverageReportFormat::Lcov => {
+                output::write_lcov(&merged_coverage, &report_path)
+            }
+            output::CoverageReportFormat::Html => {
+                output::write_coverage_html(&merged_coverage, &report_path)
+            }
+        };
+        written.with_context(|| {
+            format!(
+                "Failed to write coverage report to {}",
+                report_path.display()
+            )
+        })?;
+    }
+
     // 7. Combine results
     let mut all_results = skipped_tests;
     all_results.extend(run_results.results);
@@ -478,11 +1616,181 @@ fn run_tests(args: Args) -> Result<i32> {
         results: all_results,
         total_duration: run_results.total_duration,
     };
+    let not_run = combined
+        .results
+        .iter()
+        .filter(|r| r.skip_kind == Some(runner::SkipKind::Deadline))
+        .count();
 
     // 8. Print summary
-    let failed_tests = printer.get_failed_tests();
-    output::print_summary(&combined, &failed_tests);
+    match format {
+        output::OutputFormat::Human if printer.verbosity() == output::Verbosity::Quiet => {
+            warn_collection_errors(&collection_errors);
+            output::print_summary_line(&combined);
+        }
+        output::OutputFormat::Human => {
+            let failed_tests = printer.get_failed_tests();
+            output::print_summary(
+                &combined,
+                &failed_tests,
+                &collection_errors,
+                args.tb,
+                output::ShowCapture::parse(&args.show_capture),
+            );
+            if args.group_failures_by.is_some() {
+                output::print_failure_groups(&failed_tests);
+            }
+            if let Some(max) = capped {
+                output::print_max_collect_note(max, selected_count);
+            }
+            if let Some(ref id) = stepwise_stop {
+                output::print_stepwise_stop(id);
+            }
+            if let Some(seed) = shuffle_seed.filter(|_| !combined.all_passed()) {
+                output::print_seed_hint(seed);
+            }
+            output::print_duration_regressions(&regressions, args.duration_threshold);
+            if let Some(secs) = args.deadline.filter(|_| not_run > 0) {
+                output::print_deadline_note(not_run, secs);
+            }
+        }
+        output::OutputFormat::Tap => {
+            warn_collection_errors(&collection_errors);
+            output::print_tap(&combined);
+        }
+    }
+    if args.profile {
+        output::print_profile(&profile, started.elapsed());
+    }
+    let coverage_ok = match coverage_check {
+        Some((totals, threshold)) => {
+            output::print_coverage_threshold(totals, threshold);
+            totals.percent() >= threshold
+        }
+        None => true,
+    };
+    let in_github_actions = std::env::var("GITHUB_ACTIONS").is_ok_and(|v| v == "true");
+    if args.github_annotations || in_github_actions {
+        // Actions reads workflow commands from stderr too, which keeps a TAP stream
+        // or the --summary-only line parseable
+        if human && !args.summary_only {
+            output::write_github_annotations(&mut io::stdout(), &combined)?;
+        } else {
+            output::write_github_annotations(&mut io::stderr(), &combined)?;
+        }
+    }
+    let after_all_ok = match config.after_all {
+        Some(ref command) => run_hook("after_all", command, &hook_dir)?,
+        None => true,
+    };
+
+    // 9. Return exit code (priming only seeds the cache, so outcomes don't matter)
+    Ok(if args.prime {
+        0
+    } else if not_run > 0 {
+        DEADLINE_EXIT_CODE
+    } else if combined.all_passed() && coverage_ok && collection_ok && after_all_ok {
+        0
+    } else {
+        1
+    })
+}
+
+/// Tests that took more than `threshold_pct` percent longer than their last recorded run.
+fn duration_regressions<F>(
+    results: &[runner::TestResult],
+    threshold_pct: f64,
+    last_duration: F,
+) -> Vec<output::DurationRegression>
+where
+    F: Fn(&TestItem) -> Option<Duration>,
+{
+    let factor = 1.0 + threshold_pct / 100.0;
+    results
+        .iter()
+        .filter(|r| !r.skipped)
+        .filter_map(|r| {
+            // A zero baseline (sub-millisecond test) would flag every run
+            let previous = last_duration(&r.item).filter(|d| !d.is_zero())?;
+            (r.duration.as_secs_f64() > previous.as_secs_f64() * factor).then(|| {
+                output::DurationRegression {
+                    id: r.item.id(),
+                    previous,
+                    current: r.duration,
+                }
+            })
+        })
+        .collect()
+}
 
-    // 9. Return exit code
-    Ok(if combined.all_passed() { 0 } else { 1 })
+/// Run `tests` one at a time in order, stopping after the first failure (`--stepwise`).
+fn run_until_failure<F>(
+    tests: &[TestItem],
+    options: &runner::RunOptions,
+    on_result: F,
+) -> Result<runner::TestResults>
+where
+    F: Fn(&runner::TestResult) + Send + Sync,
+{
+    let mut results = Vec::new();
+    let mut total_duration = Duration::ZERO;
+    for item in tests {
+        let run = runner::run_tests_with_options(std::slice::from_ref(item), options, &on_result)?;
+        total_duration += run.total_duration;
+        let failed = !run.all_passed();
+        results.extend(run.results);
+        if failed {
+            break;
+        }
+    }
+    Ok(runner::TestResults {
+        results,
+        total_duration,
+    })
+}
+
+/// Whether two items refer to the same test, regardless of how the file path was spelled.
+fn same_test(a: &TestItem, b: &TestItem) -> bool {
+    a.function == b.function
+        && a.class == b.class
+        && a.params == b.params
+        && a.canonical_file() == b.canonical_file()
+}
+
+/// Order in which selected tests are executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OrderBy {
+    /// Keep discovery order (failed-last-time tests first)
+    #[value(alias = "file")]
+    Discovery,
+    /// Slowest first; tests without recorded history are treated as slowest
+    #[value(alias = "slowest-first")]
+    DurationDesc,
+    /// Fastest first; tests without recorded history are treated as slowest
+    DurationAsc,
+    /// Alphabetical by test ID
+    Name,
+    /// Tests that failed last time first, then the rest in discovery order
+    FailedFirst,
+    /// Shuffled with a seed that is printed so `--seed` can reproduce it
+    Random,
+}
+
+/// Sort tests in place. Ties keep their existing relative order.
+pub fn order_tests<F, G>(tests: &mut [TestItem], order: OrderBy, last_duration: F, last_failed: G)
+where
+    F: Fn(&TestItem) -> Option<Duration>,
+    G: Fn(&TestItem) -> bool,
+{
+    match order {
+        // Shuffling needs the seed, so `run_tests` does it after ordering
+        OrderBy::Discovery | OrderBy::Random => {}
+        OrderBy::DurationDesc => tests
+            .sort_by_cached_key(|t| std::cmp::Reverse(last_duration(t).unwrap_or(Duration::MAX))),
+        OrderBy::DurationAsc => {
+            tests.sort_by_cached_key(|t| last_duration(t).unwrap_or(Duration::MAX))
+        }
+        OrderBy::Name => tests.sort_by_cached_key(|t| t.id()),
+        OrderBy::FailedFirst => tests.sort_by_cached_key(|t| !last_failed(t)),
+    }
 }
@@ -1,40 +1,177 @@
 //! Configuration loading from pyproject.toml.
 //!
-//! Reads taut configuration from [tool.taut] section in pyproject.toml.
+//! Reads taut configuration from [tool.taut] section in pyproject.toml, falling
+//! back to the pytest settings taut understands in pytest.ini (`[pytest]`) or
+//! setup.cfg (`[tool:pytest]`).
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Taut configuration options.
 #[derive(Debug, Default)]
 pub struct Config {
     /// Maximum number of worker processes.
     pub max_workers: Option<usize>,
+    /// Watch-mode debounce window in milliseconds.
+    pub debounce_ms: Option<u64>,
+    /// Extra glob patterns to skip during test discovery.
+    pub exclude: Vec<String>,
+    /// Substrings or globs of files to record coverage for, even installed ones.
+    pub coverage_include: Vec<String>,
+    /// Substrings or globs of files never to record coverage for.
+    pub coverage_exclude: Vec<String>,
+    /// Globs for test file names (default: `test_*.py`, `_test*.py`).
+    pub file_patterns: Vec<String>,
+    /// Globs for test function and method names (default: `test_*`, `_test*`).
+    pub function_patterns: Vec<String>,
+    /// Globs for test class names (default: `Test*`).
+    pub class_patterns: Vec<String>,
+    /// Directory of the nearest pyproject.toml; test IDs are relative to it.
+    pub rootdir: Option<PathBuf>,
+    /// Where to keep the cache (relative paths are from the pyproject.toml).
+    pub cache_dir: Option<PathBuf>,
+    /// Keep a separate cache for each git branch.
+    pub cache_per_branch: bool,
+    /// Command (or Python script) to run once before any tests.
+    pub before_all: Option<String>,
+    /// Command (or Python script) to run once after the summary.
+    pub after_all: Option<String>,
+    /// Where to look for tests when no paths are given (relative paths are
+    /// from the pyproject.toml).
+    pub testpaths: Vec<PathBuf>,
+    /// Hash of the settings that change which tests exist or how they behave
+    /// (see [`FINGERPRINT_KEYS`]); cached results recorded under a different
+    /// fingerprint are not trusted.
+    pub fingerprint: Option<String>,
+}
+
+/// `[tool.taut]` keys that go into [`Config::fingerprint`]: discovery patterns,
+/// markers and plugins, plus the coverage filters, which decide what a test's
+/// recorded dependencies cover. Options like `max_workers` or `debounce_ms`
+/// can't change a result, so editing them keeps the cache.
+const FINGERPRINT_KEYS: &[&str] = &[
+    "exclude",
+    "file_patterns",
+    "function_patterns",
+    "class_patterns",
+    "markers",
+    "plugins",
+    "coverage_include",
+    "coverage_exclude",
+];
+
+/// The pytest INI options that go into [`Config::fingerprint`].
+const INI_FINGERPRINT_KEYS: &[&str] = &[
+    "python_files",
+    "python_classes",
+    "python_functions",
+    "markers",
+    "required_plugins",
+];
+
+/// Hash of the `key=value` lines of the settings that are set.
+fn fingerprint<'a>(settings: impl Iterator<Item = (&'a str, Option<String>)>) -> String {
+    let lines: String = settings
+        .filter_map(|(key, value)| Some(format!("{}={}\n", key, value?)))
+        .collect();
+    format!("{:016x}", xxhash_rust::xxh64::xxh64(lines.as_bytes(), 0))
 }
 
 impl Config {
-    /// Load configuration from pyproject.toml in the given directory.
-    /// Falls back to parent directories until a pyproject.toml is found.
-    /// Returns default config if no pyproject.toml exists.
+    /// Load configuration from the given directory, falling back to parent
+    /// directories until one has a config source (see [`Config::parse_dir`]).
+    /// Returns default config if none does.
+    ///
+    /// The first directory containing a pyproject.toml becomes the `rootdir`,
+    /// whether or not it has a `[tool.taut]` section; failing that, the
+    /// directory the configuration came from, and failing that, the current
+    /// directory.
     pub fn load(start_dir: &Path) -> Self {
+        let start_dir = start_dir
+            .canonicalize()
+            .unwrap_or_else(|_| start_dir.to_path_buf());
         let mut dir = if start_dir.is_file() {
             start_dir.parent().map(Path::to_path_buf)
         } else {
-            Some(start_dir.to_path_buf())
+            Some(start_dir)
         };
 
+        let mut rootdir = None;
         while let Some(d) = dir {
-            let pyproject = d.join("pyproject.toml");
-            if pyproject.exists() {
-                if let Ok(content) = std::fs::read_to_string(&pyproject) {
-                    if let Some(config) = Self::parse(&content) {
-                        return config;
-                    }
-                }
+            if rootdir.is_none() && d.join("pyproject.toml").exists() {
+                rootdir = Some(d.clone());
+            }
+            if let Some(config) = Self::parse_dir(&d) {
+                let cache_dir = config.cache_dir.map(|dir| d.join(dir));
+                let testpaths = config.testpaths.iter().map(|p| d.join(p)).collect();
+                return Self {
+                    rootdir: rootdir.or(Some(d)),
+                    cache_dir,
+                    testpaths,
+                    ..config
+                };
             }
             dir = d.parent().map(Path::to_path_buf);
         }
 
-        Self::default()
+        Self {
+            // Keep test IDs stable however the paths were typed (`./x.py` vs `x.py`)
+            rootdir: rootdir.or_else(|| std::env::current_dir().ok()?.canonicalize().ok()),
+            ..Self::default()
+        }
+    }
+
+    /// The configuration in `dir`, from the first of pyproject.toml
+    /// (`[tool.taut]`), pytest.ini (`[pytest]`) and setup.cfg (`[tool:pytest]`)
+    /// that has one. Sources are not merged.
+    fn parse_dir(dir: &Path) -> Option<Self> {
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).ok();
+        read("pyproject.toml")
+            .and_then(|content| Self::parse(&content))
+            .or_else(|| {
+                read("pytest.ini").and_then(|content| Self::parse_pytest_ini(&content, "pytest"))
+            })
+            .or_else(|| {
+                read("setup.cfg")
+                    .and_then(|content| Self::parse_pytest_ini(&content, "tool:pytest"))
+            })
+    }
+
+    /// Parse the pytest options taut understands from `[section]` of an INI file:
+    /// `testpaths`, `python_files`, `python_classes` and `python_functions`.
+    fn parse_pytest_ini(content: &str, section: &str) -> Option<Self> {
+        let entries = ini_section(content, section)?;
+        let words = |key: &str| -> Vec<String> {
+            entries
+                .iter()
+                .rfind(|(k, _)| k == key)
+                .map(|(_, value)| value.split_whitespace().map(String::from).collect())
+                .unwrap_or_default()
+        };
+        // pytest takes bare prefixes for class and function names
+        let prefixes_or_globs = |key: &str| -> Vec<String> {
+            words(key)
+                .into_iter()
+                .map(|p| {
+                    if p.contains(['*', '?', '[']) {
+                        p
+                    } else {
+                        format!("{}*", p)
+                    }
+                })
+                .collect()
+        };
+
+        Some(Self {
+            file_patterns: words("python_files"),
+            function_patterns: prefixes_or_globs("python_functions"),
+            class_patterns: prefixes_or_globs("python_classes"),
+            testpaths: words("testpaths").into_iter().map(PathBuf::from).collect(),
+            fingerprint: Some(fingerprint(INI_FINGERPRINT_KEYS.iter().map(|&key| {
+                let value = entries.iter().rfind(|(k, _)| k == key);
+                (key, value.map(|(_, value)| value.clone()))
+            }))),
+            ..Self::default()
+        })
     }
 
     /// Parse configuration from pyproject.toml content.
@@ -48,8 +185,93 @@ impl Config {
             .and_then(|v| v.as_integer())
             .map(|n| n as usize);
 
-        Some(Self { max_workers })
+        let debounce_ms = taut
+            .get("debounce_ms")
+            .and_then(|v| v.as_integer())
+            .map(|n| n as u64);
+
+        let string_list = |key: &str| -> Vec<String> {
+            taut.get(key)
+                .and_then(|v| v.as_array())
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|p| p.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        Some(Self {
+            max_workers,
+            debounce_ms,
+            exclude: string_list("exclude"),
+            coverage_include: string_list("coverage_include"),
+            coverage_exclude: string_list("coverage_exclude"),
+            file_patterns: string_list("file_patterns"),
+            function_patterns: string_list("function_patterns"),
+            class_patterns: string_list("class_patterns"),
+            testpaths: string_list("testpaths")
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
+            rootdir: None,
+            cache_dir: taut
+                .get("cache_dir")
+                .and_then(|v| v.as_str())
+                .map(PathBuf::from),
+            cache_per_branch: taut
+                .get("cache_per_branch")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            before_all: taut
+                .get("before_all")
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            after_all: taut
+                .get("after_all")
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            fingerprint: Some(fingerprint(
+                FINGERPRINT_KEYS
+                    .iter()
+                    .map(|&key| (key, taut.get(key).map(toml::Value::to_string))),
+            )),
+        })
+    }
+}
+
+/// The `key = value` (or `key: value`) pairs of `[section]` in an INI file,
+/// with indented continuation lines joined onto the previous value. `None` if
+/// the section is missing.
+fn ini_section(content: &str, section: &str) -> Option<Vec<(String, String)>> {
+    let mut entries: Option<Vec<(String, String)>> = None;
+    let mut in_section = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with(['#', ';']) {
+            continue;
+        }
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = name.trim() == section;
+            if in_section {
+                entries.get_or_insert_with(Vec::new);
+            }
+            continue;
+        }
+        let Some(entries) = entries.as_mut().filter(|_| in_section) else {
+            continue;
+        };
+        if line.starts_with(char::is_whitespace) {
+            if let Some((_, value)) = entries.last_mut() {
+                value.push('\n');
+                value.push_str(trimmed);
+            }
+        } else if let Some((key, value)) = trimmed.split_once(['=', ':']) {
+            entries.push((key.trim().to_string(), value.trim().to_string()));
+        }
     }
+    entries
 }
 
 #[cfg(test)]
@@ -66,6 +288,51 @@ max_workers = 4
         assert_eq!(config.max_workers, Some(4));
     }
 
+    #[test]
+    fn parse_debounce_ms() {
+        let content = r#"
+[tool.taut]
+debounce_ms = 250
+"#;
+        let config = Config::parse(content).unwrap();
+        assert_eq!(config.debounce_ms, Some(250));
+    }
+
+    #[test]
+    fn parse_exclude() {
+        let content = r#"
+[tool.taut]
+exclude = ["build", "vendor/*"]
+"#;
+        let config = Config::parse(content).unwrap();
+        assert_eq!(config.exclude, vec!["build", "vendor/*"]);
+    }
+
+    #[test]
+    fn parse_coverage_filters() {
+        let content = r#"
+[tool.taut]
+coverage_include = ["site-packages/mylib"]
+coverage_exclude = ["generated", "*/migrations/*"]
+"#;
+        let config = Config::parse(content).unwrap();
+        assert_eq!(config.coverage_include, vec!["site-packages/mylib"]);
+        assert_eq!(config.coverage_exclude, vec!["generated", "*/migrations/*"]);
+    }
+
+    #[test]
+    fn parse_name_patterns() {
+        let content = r#"
+[tool.taut]
+file_patterns = ["check_*.py"]
+function_patterns = ["check_*"]
+"#;
+        let config = Config::parse(content).unwrap();
+        assert_eq!(config.file_patterns, vec!["check_*.py"]);
+        assert_eq!(config.function_patterns, vec!["check_*"]);
+        assert!(config.class_patterns.is_empty());
+    }
+
     #[test]
     fn parse_empty_taut_section() {
         let content = r#"
@@ -94,4 +361,172 @@ name = "myproject"
         let config = Config::parse(content);
         assert!(config.is_none());
     }
+
+    #[test]
+    fn parse_cache_dir() {
+        let content = r#"
+[tool.taut]
+cache_dir = ".taut-cache"
+"#;
+        let config = Config::parse(content).unwrap();
+        assert_eq!(config.cache_dir, Some(PathBuf::from(".taut-cache")));
+        assert!(!config.cache_per_branch);
+    }
+
+    #[test]
+    fn parse_cache_per_branch() {
+        let content = r#"
+[tool.taut]
+cache_per_branch = true
+"#;
+        let config = Config::parse(content).unwrap();
+        assert!(config.cache_per_branch);
+    }
+
+    #[test]
+    fn parse_hooks() {
+        let content = r#"
+[tool.taut]
+before_all = "docker compose up -d"
+after_all = "scripts/teardown.py"
+"#;
+        let config = Config::parse(content).unwrap();
+        assert_eq!(config.before_all.as_deref(), Some("docker compose up -d"));
+        assert_eq!(config.after_all.as_deref(), Some("scripts/teardown.py"));
+    }
+
+    #[test]
+    fn load_resolves_testpaths_from_pyproject_dir() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::write(
+            root.join("pyproject.toml"),
+            "[tool.taut]\ntestpaths = [\"tests\", \"integration\"]\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+
+        let config = Config::load(&root.join("src"));
+        assert_eq!(
+            config.testpaths,
+            vec![root.join("tests"), root.join("integration")]
+        );
+    }
+
+    #[test]
+    fn load_sets_rootdir_to_nearest_pyproject() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::write(root.join("pyproject.toml"), "[project]\nname = \"demo\"\n").unwrap();
+        std::fs::create_dir_all(root.join("tests/unit")).unwrap();
+
+        let config = Config::load(&root.join("tests/unit"));
+        assert_eq!(config.rootdir, Some(root));
+    }
+
+    #[test]
+    fn ini_section_joins_continuations_and_skips_comments() {
+        let content = "[other]\nkey = no\n\n\
+                       [pytest]\n\
+                       ; comment\n\
+                       testpaths =\n    tests\n    # not a path\n    integration\n\
+                       python_files: check_*.py\n";
+        let entries = ini_section(content, "pytest").unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                ("testpaths".to_string(), "\ntests\nintegration".to_string()),
+                ("python_files".to_string(), "check_*.py".to_string()),
+            ]
+        );
+        assert_eq!(ini_section(content, "tool:pytest"), None);
+    }
+
+    #[test]
+    fn load_reads_pytest_ini() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::write(
+            root.join("pytest.ini"),
+            "[pytest]\n\
+             testpaths =\n    tests\n    integration\n\
+             python_files = check_*.py\n\
+             python_classes = Check Suite*\n\
+             python_functions = check\n\
+             markers =\n    slow: takes a while\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("tests")).unwrap();
+
+        let config = Config::load(&root.join("tests"));
+        assert_eq!(
+            config.testpaths,
+            vec![root.join("tests"), root.join("integration")]
+        );
+        assert_eq!(config.file_patterns, vec!["check_*.py"]);
+        assert_eq!(config.class_patterns, vec!["Check*", "Suite*"]);
+        assert_eq!(config.function_patterns, vec!["check*"]);
+        assert_eq!(config.rootdir, Some(root));
+        assert!(config.fingerprint.is_some());
+    }
+
+    #[test]
+    fn fingerprint_only_covers_settings_that_affect_results() {
+        let fingerprint = |content: &str| Config::parse(content).unwrap().fingerprint;
+        let base = fingerprint("[tool.taut]\nmax_workers = 2\nexclude = [\"build\"]\n");
+        assert_eq!(
+            base,
+            fingerprint("[tool.taut]\nmax_workers = 8\ndebounce_ms = 50\nexclude = [\"build\"]\n")
+        );
+        assert_ne!(
+            base,
+            fingerprint("[tool.taut]\nmax_workers = 2\nexclude = []\n")
+        );
+    }
+
+    #[test]
+    fn load_reads_setup_cfg_tool_pytest_section() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::write(
+            root.join("setup.cfg"),
+            "[metadata]\nname = demo\n\n[tool:pytest]\n# comment\ntestpaths = tests\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&root);
+        assert_eq!(config.testpaths, vec![root.join("tests")]);
+        assert_eq!(config.rootdir, Some(root));
+    }
+
+    #[test]
+    fn load_ignores_setup_cfg_without_pytest_section() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::write(root.join("setup.cfg"), "[metadata]\nname = demo\n").unwrap();
+
+        let config = Config::load(&root);
+        assert!(config.testpaths.is_empty());
+        assert_eq!(config.fingerprint, None);
+    }
+
+    #[test]
+    fn load_prefers_pyproject_then_pytest_ini_then_setup_cfg() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::write(
+            root.join("setup.cfg"),
+            "[tool:pytest]\ntestpaths = from_cfg\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("pytest.ini"), "[pytest]\ntestpaths = from_ini\n").unwrap();
+        assert_eq!(Config::load(&root).testpaths, vec![root.join("from_ini")]);
+
+        std::fs::write(
+            root.join("pyproject.toml"),
+            "[tool.taut]\ntestpaths = [\"from_toml\"]\n",
+        )
+        .unwrap();
+        assert_eq!(Config::load(&root).testpaths, vec![root.join("from_toml")]);
+    }
 }
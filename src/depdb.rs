@@ -1,14 +1,24 @@
-use crate::blocks::{BlockId, FileBlocks};
-use crate::cache::ensure_cache_dir;
+use crate::blocks::{BlockId, BlockKind, FileBlocks};
+use crate::cache::{FileHealth, ensure_cache_dir, lock_file, write_atomic};
 use crate::discovery::TestItem;
+use crate::markers::Marker;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::{BufReader, BufWriter};
-use std::path::PathBuf;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 const DEPDB_FILE: &str = "depdb.json";
 
+/// On-disk format version. Bump whenever `BlockId`, `TestId` or
+/// `TestDependency` change shape so old databases are discarded.
+///
+/// 2: canonical file paths, nested class names, rootdir-relative test IDs
+/// and `__main__` guard blocks all changed the keys version 1 stored.
+pub const CURRENT_VERSION: u32 = 2;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct TestId {
     pub file: PathBuf,
@@ -20,13 +30,9 @@ impl From<&TestItem> for TestId {
     fn from(item: &TestItem) -> Self {
         // Canonicalize the path to ensure consistent matching
         // between relative and absolute paths
-        let file = item
-            .file
-            .canonicalize()
-            .unwrap_or_else(|_| item.file.clone());
         Self {
-            file,
-            function: item.function.clone(),
+            file: item.canonical_file(),
+            function: item.name(),
             class: item.class.clone(),
         }
     }
@@ -35,50 +41,329 @@ impl From<&TestItem> for TestId {
 impl std::fmt::Display for TestId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.class {
-            Some(class) => write!(f, "{}::{}::{}", self.file.display(), class, self.function),
+            Some(class) => write!(
+                f,
+                "{}::{}::{}",
+                self.file.display(),
+                class.replace('.', "::"),
+                self.function
+            ),
             None => write!(f, "{}::{}", self.file.display(), self.function),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct TestDependency {
     /// Map: BlockId serialized key -> expected checksum
     dependencies: HashMap<String, String>,
     last_run_passed: bool,
+    /// Wall-clock duration of the last run, in milliseconds
+    #[serde(default)]
+    last_duration_ms: Option<u64>,
+    /// `Config::fingerprint` when the test last ran
+    #[serde(default)]
+    config: Option<String>,
+    /// Outcomes of recent runs (oldest first) since the test's dependencies and
+    /// config last changed, capped at [`HISTORY_LEN`]
+    #[serde(default)]
+    history: Vec<bool>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+/// How many outcomes to keep per test for flakiness detection.
+const HISTORY_LEN: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct DependencyDatabase {
+    /// Format version, see [`CURRENT_VERSION`]
+    version: u32,
     /// All known blocks: serialized BlockId -> current checksum
     blocks: HashMap<String, String>,
     /// Test dependencies: serialized TestId -> dependency info
     tests: HashMap<String, TestDependency>,
+    /// Fingerprint of the configuration this run uses
+    #[serde(skip)]
+    config: Option<String>,
+    /// What was on disk when this database was loaded, if it was
+    #[serde(skip)]
+    loaded: Option<LoadedKeys>,
+}
+
+impl Default for DependencyDatabase {
+    fn default() -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            blocks: HashMap::new(),
+            tests: HashMap::new(),
+            config: None,
+            loaded: None,
+        }
+    }
+}
+
+/// Keys present when a database was loaded. On save, entries on disk outside
+/// these sets were written by another taut process in the meantime and are kept.
+#[derive(Debug, Default)]
+struct LoadedKeys {
+    blocks: HashSet<String>,
+    tests: HashSet<String>,
+}
+
+/// Only the version field, so it can be checked before parsing the rest.
+#[derive(Deserialize)]
+struct VersionProbe {
+    #[serde(default)]
+    version: Option<u32>,
 }
 
 impl DependencyDatabase {
-    pub fn load() -> Self {
-        let path = ensure_cache_dir()
+    /// Where the database lives in `cache_dir`.
+    pub fn path(cache_dir: &Path) -> PathBuf {
+        ensure_cache_dir(cache_dir)
             .map(|d| d.join(DEPDB_FILE))
-            .unwrap_or_else(|_| PathBuf::from(DEPDB_FILE));
+            .unwrap_or_else(|_| PathBuf::from(DEPDB_FILE))
+    }
 
-        if !path.exists() {
-            return Self::default();
+    pub fn load(cache_dir: &Path) -> Self {
+        let (db, notice) = Self::load_from(&Self::path(cache_dir));
+        if let Some(notice) = notice {
+            eprintln!("{}", notice);
         }
+        db
+    }
+
+    /// Load the database from `path`.
+    ///
+    /// Returns a notice when existing data had to be discarded because it was
+    /// written in an older (or unreadable) format.
+    pub fn load_from(path: &Path) -> (Self, Option<String>) {
+        let (mut db, notice) = Self::read(path);
+        db.loaded = Some(LoadedKeys {
+            blocks: db.blocks.keys().cloned().collect(),
+            tests: db.tests.keys().cloned().collect(),
+        });
+        (db, notice)
+    }
+
+    fn read(path: &Path) -> (Self, Option<String>) {
+        let Ok(content) = fs::read_to_string(path) else {
+            return (Self::default(), None);
+        };
 
-        fs::File::open(&path)
+        let found = serde_json::from_str::<VersionProbe>(&content)
             .ok()
-            .and_then(|f| serde_json::from_reader(BufReader::new(f)).ok())
-            .unwrap_or_default()
+            .and_then(|p| p.version);
+        if found != Some(CURRENT_VERSION) {
+            let found = found.map_or_else(|| "none".to_string(), |v| v.to_string());
+            let notice = format!(
+                "taut: cache reset due to a format upgrade (found version {}, expected {})",
+                found, CURRENT_VERSION
+            );
+            return (Self::default(), Some(notice));
+        }
+
+        match serde_json::from_str(&content) {
+            Ok(db) => (db, None),
+            Err(_) => (
+                Self::default(),
+                Some(
+                    "taut: cache reset because the dependency database was unreadable".to_string(),
+                ),
+            ),
+        }
     }
 
-    pub fn save(&self) {
-        let path = ensure_cache_dir()
-            .map(|d| d.join(DEPDB_FILE))
-            .unwrap_or_else(|_| PathBuf::from(DEPDB_FILE));
+    /// Set the configuration fingerprint (see `Config::fingerprint`) that new
+    /// results are recorded under; tests recorded under another one must rerun.
+    pub fn set_config(&mut self, fingerprint: Option<String>) {
+        self.config = fingerprint;
+    }
 
-        if let Ok(f) = fs::File::create(&path) {
-            let _ = serde_json::to_writer(BufWriter::new(f), self);
+    pub fn save(&self, cache_dir: &Path) {
+        let _ = self.save_to(&Self::path(cache_dir));
+    }
+
+    /// Write the database to `path`, replacing the old file atomically.
+    ///
+    /// A database that was loaded from disk is merged with the file's current
+    /// contents under a lock, so tests recorded by another taut process since
+    /// then (say, watch mode next to a manual run) are not lost.
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        let _lock =
+            lock_file(path).with_context(|| format!("Failed to lock {}", path.display()))?;
+        let merged;
+        let db = match self.loaded {
+            Some(ref loaded) => {
+                merged = self.merged_with(Self::read(path).0, loaded);
+                &merged
+            }
+            None => self,
+        };
+        let data = serde_json::to_vec(db)?;
+        write_atomic(path, &data).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// This database plus the entries of `on_disk` that appeared after it was
+    /// loaded. Entries it dropped itself (e.g. by pruning) stay dropped.
+    fn merged_with(&self, on_disk: Self, loaded: &LoadedKeys) -> Self {
+        let mut blocks = self.blocks.clone();
+        for (key, checksum) in on_disk.blocks {
+            if !loaded.blocks.contains(&key) {
+                blocks.entry(key).or_insert(checksum);
+            }
+        }
+        let mut tests = self.tests.clone();
+        for (key, dep) in on_disk.tests {
+            if !loaded.tests.contains(&key) {
+                tests.entry(key).or_insert(dep);
+            }
+        }
+        Self {
+            version: self.version,
+            blocks,
+            tests,
+            config: self.config.clone(),
+            loaded: None,
+        }
+    }
+
+    /// Check whether the database at `path` loads cleanly, without loading it.
+    pub fn verify(path: &Path) -> FileHealth {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return FileHealth::Missing,
+            Err(e) => return FileHealth::Corrupt(e.to_string()),
+        };
+        match serde_json::from_slice::<Self>(&data) {
+            Ok(db) if db.version == CURRENT_VERSION => FileHealth::Valid,
+            Ok(db) => FileHealth::Corrupt(format!(
+                "format version {}, expected {}",
+                db.version, CURRENT_VERSION
+            )),
+            Err(e) => FileHealth::Corrupt(e.to_string()),
+        }
+    }
+
+    /// Rewrite a damaged database at `path`, keeping as many complete entries
+    /// as can be recovered from the start of the file. If nothing is readable
+    /// the file is removed. Returns how many tests were kept.
+    pub fn repair(path: &Path) -> Result<usize> {
+        let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        match Self::salvage(&data) {
+            Some(db) => {
+                db.save_to(path)?;
+                Ok(db.tests.len())
+            }
+            None => {
+                fs::remove_file(path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+                Ok(0)
+            }
+        }
+    }
+
+    /// The longest readable prefix of a database file. A file cut short by a
+    /// crash is closed off after its last complete entry; junk after a whole
+    /// document is dropped.
+    fn salvage(data: &[u8]) -> Option<Self> {
+        let readable = |bytes: &[u8]| {
+            serde_json::from_slice::<Self>(bytes)
+                .ok()
+                .filter(|db| db.version == CURRENT_VERSION)
+        };
+
+        let leading = serde_json::Deserializer::from_slice(data)
+            .into_iter::<Self>()
+            .next()
+            .and_then(|db| db.ok())
+            .filter(|db| db.version == CURRENT_VERSION);
+        if leading.is_some() {
+            return leading;
+        }
+
+        // Entries are separated by commas; try ending the file at each one,
+        // closing the `tests` map (or `blocks`, with no tests) after it
+        data.iter()
+            .enumerate()
+            .rev()
+            .filter(|&(_, &byte)| byte == b',')
+            .find_map(|(cut, _)| {
+                ["}}", r#"},"tests":{}}"#]
+                    .iter()
+                    .find_map(|close| readable(&[&data[..cut], close.as_bytes()].concat()))
+            })
+    }
+
+    /// Write the database to `path` with file paths relative to `root`, so
+    /// [`DependencyDatabase::import`] can load it into a checkout elsewhere.
+    /// Entries for files outside `root` are left out; returns what was written.
+    pub fn export(&self, root: &Path, path: &Path) -> Result<DepDbStats> {
+        let portable = self.map_paths(|file| file.strip_prefix(root).ok().map(Path::to_path_buf));
+        let f = fs::File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        serde_json::to_writer(BufWriter::new(f), &portable)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(portable.stats())
+    }
+
+    /// Read a database written by [`DependencyDatabase::export`], resolving its
+    /// relative paths against `root`.
+    pub fn import(root: &Path, path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            anyhow::bail!("Cache export not found: {}", path.display());
+        }
+        let (portable, notice) = Self::load_from(path);
+        if notice.is_some() {
+            anyhow::bail!(
+                "{} is not a cache export from this version of taut",
+                path.display()
+            );
+        }
+        Ok(portable.map_paths(|file| Some(root.join(file))))
+    }
+
+    /// Copy of the database with every file path rewritten by `map`, dropping
+    /// entries whose path `map` rejects.
+    fn map_paths(&self, map: impl Fn(&Path) -> Option<PathBuf>) -> Self {
+        let map_block = |key: &str| -> Option<String> {
+            let mut id: BlockId = serde_json::from_str(key).ok()?;
+            id.file = map(&id.file)?;
+            Some(Self::block_key(&id))
+        };
+
+        let blocks = self
+            .blocks
+            .iter()
+            .filter_map(|(key, checksum)| Some((map_block(key)?, checksum.clone())))
+            .collect();
+        let tests = self
+            .tests
+            .iter()
+            .filter_map(|(key, dep)| {
+                let mut id: TestId = serde_json::from_str(key).ok()?;
+                id.file = map(&id.file)?;
+                let dependencies = dep
+                    .dependencies
+                    .iter()
+                    .filter_map(|(key, checksum)| Some((map_block(key)?, checksum.clone())))
+                    .collect();
+                let dep = TestDependency {
+                    dependencies,
+                    last_run_passed: dep.last_run_passed,
+                    last_duration_ms: dep.last_duration_ms,
+                    config: dep.config.clone(),
+                    history: dep.history.clone(),
+                };
+                Some((Self::test_key(&id), dep))
+            })
+            .collect();
+
+        Self {
+            version: self.version,
+            blocks,
+            tests,
+            config: self.config.clone(),
+            loaded: None,
         }
     }
 
@@ -110,11 +395,39 @@ impl DependencyDatabase {
         let test_key = Self::test_key(&test_id);
         let mut dependencies = HashMap::new();
 
-        // Map coverage lines to blocks
+        // Map coverage lines to blocks. The block index is keyed by canonical path,
+        // while the interpreter reports paths as imported (possibly via symlinks)
         for (file, lines) in coverage {
-            if let Some(file_blocks) = block_index.get(file) {
-                for &line in lines {
-                    if let Some(block) = file_blocks.get_block_for_line(line) {
+            let file_blocks = block_index.get(file).or_else(|| {
+                let canonical = file.canonicalize().ok()?;
+                block_index.get(&canonical)
+            });
+            if let Some(file_blocks) = file_blocks {
+                // Importing the module runs every `def` line, so those only count
+                // as running a function together with its body
+                for &line in lines
+                    .iter()
+                    .filter(|l| !file_blocks.header_lines.contains(l))
+                {
+                    // Tests import the module, so the `__main__` guard's condition is
+                    // evaluated but its body never runs on their behalf
+                    if let Some(block) = file_blocks
+                        .get_block_for_line(line)
+                        .filter(|block| block.id.kind != BlockKind::Main)
+                    {
+                        let block_key = Self::block_key(&block.id);
+                        dependencies.insert(block_key, block.checksum.clone());
+                    }
+                }
+
+                // Module-level code runs once at import, possibly before this test's
+                // coverage started (e.g. in a warm worker), so its lines can be missing.
+                // Any test that ran code from the module still depends on it, such as
+                // a constant like `MAX = 10`.
+                if !lines.is_empty() {
+                    for block in file_blocks.blocks.iter().filter(|block| {
+                        matches!(block.id.kind, BlockKind::TopLevel | BlockKind::Import)
+                    }) {
                         let block_key = Self::block_key(&block.id);
                         dependencies.insert(block_key, block.checksum.clone());
                     }
@@ -122,15 +435,84 @@ impl DependencyDatabase {
             }
         }
 
+        let previous = self.tests.get(&test_key);
+        let last_duration_ms = previous.and_then(|t| t.last_duration_ms);
+        // Keep the history only while the code the test ran last time is unchanged,
+        // so a flip in outcome can't be explained by an edit
+        let mut history = previous
+            .filter(|prev| prev.config == self.config && self.unchanged(prev))
+            .map(|prev| prev.history.clone())
+            .unwrap_or_default();
+        history.push(passed);
+        if history.len() > HISTORY_LEN {
+            history.drain(..history.len() - HISTORY_LEN);
+        }
+
         self.tests.insert(
             test_key,
             TestDependency {
                 dependencies,
                 last_run_passed: passed,
+                last_duration_ms,
+                config: self.config.clone(),
+                history,
             },
         );
     }
 
+    /// Whether every block the test depended on still has its recorded checksum
+    fn unchanged(&self, dep: &TestDependency) -> bool {
+        dep.dependencies
+            .iter()
+            .all(|(block_key, expected)| self.blocks.get(block_key) == Some(expected))
+    }
+
+    /// Tests that both passed and failed in their recent history without any
+    /// change to their dependencies, sorted by test ID
+    pub fn flaky_tests(&self) -> Vec<FlakyTest> {
+        let mut flaky: Vec<FlakyTest> = self
+            .tests
+            .iter()
+            .filter(|(_, dep)| dep.history.contains(&true) && dep.history.contains(&false))
+            .filter_map(|(key, dep)| {
+                let id: TestId = serde_json::from_str(key).ok()?;
+                Some(FlakyTest {
+                    test: id.to_string(),
+                    passes: dep.history.iter().filter(|&&passed| passed).count(),
+                    failures: dep.history.iter().filter(|&&passed| !passed).count(),
+                    history: dep.history.clone(),
+                })
+            })
+            .collect();
+        flaky.sort_by(|a, b| a.test.cmp(&b.test));
+        flaky
+    }
+
+    /// Record how long a test took on its last run
+    pub fn record_duration(&mut self, test: &TestItem, duration: Duration) {
+        let test_key = Self::test_key(&TestId::from(test));
+        if let Some(dep) = self.tests.get_mut(&test_key) {
+            dep.last_duration_ms = Some(duration.as_millis() as u64);
+        }
+    }
+
+    /// Duration of the test's last recorded run, if any
+    pub fn last_duration(&self, test: &TestId) -> Option<Duration> {
+        let test_key = Self::test_key(test);
+        self.tests
+            .get(&test_key)?
+            .last_duration_ms
+            .map(Duration::from_millis)
+    }
+
+    /// Whether the test's last recorded run failed
+    pub fn last_failed(&self, test: &TestItem) -> bool {
+        let test_key = Self::test_key(&TestId::from(test));
+        self.tests
+            .get(&test_key)
+            .is_some_and(|dep| !dep.last_run_passed)
+    }
+
     /// Check if a test needs to run based on changed blocks
     pub fn needs_run(&self, test: &TestItem) -> TestRunDecision {
         let test_id = TestId::from(test);
@@ -144,21 +526,119 @@ impl DependencyDatabase {
             return TestRunDecision::FailedLastTime;
         }
 
-        // Check if any dependencies changed
-        for (block_key, expected_checksum) in &dep.dependencies {
-            match self.blocks.get(block_key) {
-                Some(current_checksum) => {
-                    if current_checksum != expected_checksum {
-                        return TestRunDecision::DependencyChanged;
-                    }
-                }
-                None => {
-                    return TestRunDecision::DependencyDeleted;
+        if dep.config != self.config {
+            return TestRunDecision::ConfigChanged;
+        }
+
+        // Check if any dependencies changed, reporting the first stale block in
+        // file order so the explanation is stable across runs
+        dep.dependencies
+            .iter()
+            .filter_map(|(block_key, expected_checksum)| {
+                let changed = match self.blocks.get(block_key) {
+                    Some(current_checksum) if current_checksum == expected_checksum => return None,
+                    Some(_) => true,
+                    None => false,
+                };
+                let block = serde_json::from_str(block_key).unwrap_or_else(|_| BlockId {
+                    file: PathBuf::new(),
+                    kind: BlockKind::TopLevel,
+                    name: block_key.clone(),
+                    start_line: 0,
+                    end_line: 0,
+                });
+                Some((block, changed))
+            })
+            .min_by(|(a, _), (b, _)| (&a.file, a.start_line).cmp(&(&b.file, b.start_line)))
+            .map(|(block, changed)| {
+                if changed {
+                    TestRunDecision::DependencyChanged(block)
+                } else {
+                    TestRunDecision::DependencyDeleted(block)
                 }
-            }
+            })
+            .unwrap_or(TestRunDecision::CanSkip)
+    }
+
+    /// Like [`needs_run`](Self::needs_run), but a dependency counts as changed when
+    /// `changed` says so rather than when its checksum differs from the last run.
+    pub fn affected_by(
+        &self,
+        test: &TestItem,
+        changed: impl Fn(&BlockId) -> bool,
+    ) -> TestRunDecision {
+        let test_key = Self::test_key(&TestId::from(test));
+
+        let Some(dep) = self.tests.get(&test_key) else {
+            return TestRunDecision::NeverRun;
+        };
+
+        if !dep.last_run_passed {
+            return TestRunDecision::FailedLastTime;
+        }
+
+        if dep.config != self.config {
+            return TestRunDecision::ConfigChanged;
+        }
+
+        dep.dependencies
+            .keys()
+            .filter_map(|block_key| serde_json::from_str::<BlockId>(block_key).ok())
+            .filter(|block| changed(block))
+            .min_by(|a, b| (&a.file, a.start_line).cmp(&(&b.file, b.start_line)))
+            .map(TestRunDecision::DependencyChanged)
+            .unwrap_or(TestRunDecision::CanSkip)
+    }
+
+    /// Everything recorded about a single test, for debugging cache decisions
+    pub fn explain(&self, test: &TestItem) -> TestExplanation {
+        let test_id = TestId::from(test);
+        let dep = self.tests.get(&Self::test_key(&test_id));
+
+        let mut dependencies: Vec<DependencyInfo> = dep
+            .map(|d| {
+                d.dependencies
+                    .iter()
+                    .filter_map(|(block_key, expected)| {
+                        Some(DependencyInfo {
+                            block: serde_json::from_str(block_key).ok()?,
+                            recorded_checksum: expected.clone(),
+                            current_checksum: self.blocks.get(block_key).cloned(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        dependencies.sort_by(|a, b| {
+            (&a.block.file, a.block.start_line).cmp(&(&b.block.file, b.block.start_line))
+        });
+
+        let decision = self.needs_run(test);
+        TestExplanation {
+            test: test_id.to_string(),
+            reason: decision.reason(),
+            decision,
+            last_run_passed: dep.map(|d| d.last_run_passed),
+            last_duration_ms: dep.and_then(|d| d.last_duration_ms),
+            markers: test.markers.clone(),
+            dependencies,
         }
+    }
+
+    /// Drop tests and blocks whose source file no longer exists on disk
+    pub fn prune(&mut self) -> PruneStats {
+        let tests_before = self.tests.len();
+        let blocks_before = self.blocks.len();
 
-        TestRunDecision::CanSkip
+        self.tests
+            .retain(|key, _| serde_json::from_str::<TestId>(key).is_ok_and(|id| id.file.exists()));
+        self.blocks
+            .retain(|key, _| serde_json::from_str::<BlockId>(key).is_ok_and(|id| id.file.exists()));
+
+        PruneStats {
+            removed_tests: tests_before - self.tests.len(),
+            removed_blocks: blocks_before - self.blocks.len(),
+        }
     }
 
     /// Get statistics
@@ -175,13 +655,18 @@ impl DependencyDatabase {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TestRunDecision {
     CanSkip,
     NeverRun,
     FailedLastTime,
-    DependencyChanged,
-    DependencyDeleted,
+    /// The `[tool.taut]` configuration changed since the test last ran
+    ConfigChanged,
+    /// This block's checksum differs from when the test last ran
+    DependencyChanged(BlockId),
+    /// This block no longer exists (or moved, which changes its ID)
+    DependencyDeleted(BlockId),
 }
 
 impl TestRunDecision {
@@ -194,15 +679,81 @@ impl TestRunDecision {
             TestRunDecision::CanSkip => "unchanged",
             TestRunDecision::NeverRun => "new test",
             TestRunDecision::FailedLastTime => "failed last run",
-            TestRunDecision::DependencyChanged => "dependency changed",
-            TestRunDecision::DependencyDeleted => "dependency deleted",
+            TestRunDecision::ConfigChanged => "config changed",
+            TestRunDecision::DependencyChanged(_) => "dependency changed",
+            TestRunDecision::DependencyDeleted(_) => "dependency deleted",
+        }
+    }
+
+    /// Like [`reason`](Self::reason), but naming the block behind a dependency change.
+    pub fn explanation(&self) -> String {
+        match self {
+            TestRunDecision::DependencyChanged(block) => {
+                format!("{} changed", describe_block(block))
+            }
+            TestRunDecision::DependencyDeleted(block) => {
+                format!("{} was removed or moved", describe_block(block))
+            }
+            _ => self.reason().to_string(),
         }
     }
 }
 
+/// Human-readable name for a block, e.g. "helper() in utils.py".
+fn describe_block(block: &BlockId) -> String {
+    let what = match block.kind {
+        BlockKind::Function | BlockKind::Method => format!("{}()", block.name),
+        BlockKind::Class => format!("class {}", block.name),
+        BlockKind::Import => format!("imports at line {}", block.start_line),
+        BlockKind::TopLevel => format!("module code at line {}", block.start_line),
+        BlockKind::Main => "the `__main__` block".to_string(),
+    };
+    match block.file.file_name() {
+        Some(file) => format!("{} in {}", what, file.to_string_lossy()),
+        None => what,
+    }
+}
+
 pub struct DepDbStats {
     pub total_blocks: usize,
     pub total_tests: usize,
     pub passed_tests: usize,
     pub failed_tests: usize,
 }
+
+/// Result of [`DependencyDatabase::explain`].
+#[derive(Debug, Serialize)]
+pub struct TestExplanation {
+    pub test: String,
+    pub decision: TestRunDecision,
+    pub reason: &'static str,
+    /// None if the test has never been recorded
+    pub last_run_passed: Option<bool>,
+    pub last_duration_ms: Option<u64>,
+    pub markers: Vec<Marker>,
+    pub dependencies: Vec<DependencyInfo>,
+}
+
+/// A block a test depended on when it last ran.
+#[derive(Debug, Serialize)]
+pub struct DependencyInfo {
+    pub block: BlockId,
+    pub recorded_checksum: String,
+    /// None if the block no longer exists
+    pub current_checksum: Option<String>,
+}
+
+/// A test whose outcome changed while its code didn't.
+#[derive(Debug, Serialize)]
+pub struct FlakyTest {
+    pub test: String,
+    pub passes: usize,
+    pub failures: usize,
+    /// Recent outcomes, oldest first (`true` = passed)
+    pub history: Vec<bool>,
+}
+
+pub struct PruneStats {
+    pub removed_tests: usize,
+    pub removed_blocks: usize,
+}
@@ -1,11 +1,17 @@
 use anyhow::{Context, Result};
-use rustpython_parser::{Parse, ast};
+use regex::Regex;
+use rustpython_parser::Parse;
+use rustpython_parser::ast::{self, Ranged};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
 use walkdir::WalkDir;
 
-use crate::filter::TestFilter;
-use crate::markers::{self, Marker};
+use crate::config::Config;
+use crate::exclude::{self, ExcludeSet};
+use crate::filter::{self, TestFilter};
+use crate::markers::{self, Marker, ParamCase};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TestItem {
@@ -15,21 +21,105 @@ pub struct TestItem {
     pub class: Option<String>,
     #[serde(default)]
     pub line: usize,
-    /// Markers attached to this test (@skip, @mark, @parallel)
+    /// Line of the first decorator, when the test is decorated (`line` stays on the `def`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decorator_line: Option<usize>,
+    /// Markers attached to this test (@skip, @mark, @parallel, @parametrize)
     #[serde(default)]
     pub markers: Vec<Marker>,
+    /// Arguments for one @parametrize case, passed to the test as keywords
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: ParamCase,
+    #[serde(default)]
+    pub kind: TestKind,
+    /// Project root that test IDs are relative to (see `Config::rootdir`)
+    #[serde(skip)]
+    pub rootdir: Option<PathBuf>,
+}
+
+/// What a discovered test runs.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TestKind {
+    /// A test function or method
+    #[default]
+    Function,
+    /// The `>>>` examples in one docstring; `function` is `doctest:<qualname>`
+    Doctest,
 }
 
 impl TestItem {
     /// Returns a unique identifier for this test (e.g., "tests/test_example.py::TestMath::test_add")
     pub fn id(&self) -> String {
-        let file = self.file.display();
-        match &self.class {
-            Some(class) => format!("{}::{}::{}", file, class, self.function),
-            None => format!("{}::{}", file, self.function),
+        let file = self.id_path();
+        let file = file.display();
+        match self.class_path() {
+            Some(class) => format!("{}::{}::{}", file, class, self.name()),
+            None => format!("{}::{}", file, self.name()),
         }
     }
 
+    /// The file portion of the test ID: relative to `rootdir` when the test lives
+    /// under it, otherwise the path as discovered.
+    fn id_path(&self) -> PathBuf {
+        self.rootdir
+            .as_ref()
+            .and_then(|root| {
+                let file = self.canonical_file();
+                file.strip_prefix(root).ok().map(Path::to_path_buf)
+            })
+            .unwrap_or_else(|| self.file.clone())
+    }
+
+    /// The class portion of the test ID, with nested classes separated by `::`
+    /// (e.g., "TestOuter::TestInner" for `class: Some("TestOuter.TestInner")`).
+    pub fn class_path(&self) -> Option<String> {
+        self.class.as_ref().map(|class| class.replace('.', "::"))
+    }
+
+    /// Test function name, with the parameter case appended for parametrized tests
+    /// (e.g., "test_add[1-2]").
+    pub fn name(&self) -> String {
+        if self.params.is_empty() {
+            return self.function.clone();
+        }
+        let labels: Vec<String> = self
+            .params
+            .iter()
+            .map(|(_, value)| param_label(value))
+            .collect();
+        format!("{}[{}]", self.function, labels.join("-"))
+    }
+
+    /// Whether `id` (`file::[Class::]function`) names this test, however the
+    /// file path was spelled.
+    pub fn matches_id(&self, id: &str) -> bool {
+        if self.id() == id {
+            return true;
+        }
+        let Some((file, class, function)) = split_test_id(id) else {
+            return false;
+        };
+        if self.name() != function || self.class_path().as_deref() != class {
+            return false;
+        }
+        let file = Path::new(file);
+        self.canonical_file() == file.canonicalize().unwrap_or(file.to_path_buf())
+    }
+
+    /// The test file's canonical path, used as the key for caching and
+    /// comparisons. `file` keeps the path as discovered for display.
+    pub fn canonical_file(&self) -> PathBuf {
+        self.file
+            .canonicalize()
+            .unwrap_or_else(|_| self.file.clone())
+    }
+
+    /// Whether this item runs docstring examples rather than a test function.
+    pub fn is_doctest(&self) -> bool {
+        self.kind == TestKind::Doctest
+    }
+
     /// Check if this test has the @skip marker.
     pub fn is_skipped(&self) -> bool {
         markers::is_skipped(&self.markers)
@@ -54,6 +144,41 @@ impl TestItem {
     pub fn groups(&self) -> Vec<String> {
         markers::get_groups(&self.markers)
     }
+
+    /// Decorators that look like misspelled markers, with the marker each was
+    /// probably meant to be.
+    pub fn marker_typos(&self) -> Vec<(&str, &'static str)> {
+        self.markers
+            .iter()
+            .filter_map(|m| Some((m.name.as_str(), markers::likely_marker(&m.name)?)))
+            .collect()
+    }
+}
+
+/// Render a parameter value the way it's written in Python, strings unquoted.
+fn param_label(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "None".to_string(),
+        serde_json::Value::Bool(true) => "True".to_string(),
+        serde_json::Value::Bool(false) => "False".to_string(),
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Expand a test with `@parametrize` markers into one item per case.
+fn expand_parametrized(item: TestItem) -> Vec<TestItem> {
+    let cases = markers::get_param_cases(&item.markers);
+    if cases.is_empty() {
+        return vec![item];
+    }
+    cases
+        .into_iter()
+        .map(|params| TestItem {
+            params,
+            ..item.clone()
+        })
+        .collect()
 }
 
 /// Find all Python test files in the given paths.
@@ -61,22 +186,76 @@ impl TestItem {
 /// A file is considered a test file if its name matches either:
 /// - `test_*.py`
 /// - `*_test*.py`
+///
+/// Directories are walked skipping the default excludes (see
+/// [`crate::exclude::DEFAULT_EXCLUDES`]) and anything in the root's `.gitignore`.
 pub fn find_test_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    find_test_files_excluding(paths, &[])
+}
+
+/// Like [`find_test_files`], additionally skipping paths matching `exclude` globs.
+///
+/// Files passed explicitly in `paths` are never excluded.
+pub fn find_test_files_excluding(paths: &[PathBuf], exclude: &[String]) -> Result<Vec<PathBuf>> {
+    find_test_files_with(paths, exclude, &TestPatterns::default())
+}
+
+/// Like [`find_test_files_excluding`], using `patterns` to recognize test files.
+pub fn find_test_files_with(
+    paths: &[PathBuf],
+    exclude: &[String],
+    patterns: &TestPatterns,
+) -> Result<Vec<PathBuf>> {
+    find_python_files(paths, exclude, |p| patterns.is_test_file(p))
+}
+
+/// Find the non-test modules in the given paths, to collect doctests from.
+///
+/// `setup.py` and `conftest.py` are skipped, since importing them has side effects.
+pub fn find_doctest_modules(
+    paths: &[PathBuf],
+    exclude: &[String],
+    patterns: &TestPatterns,
+) -> Result<Vec<PathBuf>> {
+    find_python_files(paths, exclude, |p| {
+        p.extension().is_some_and(|ext| ext == "py")
+            && !patterns.is_test_file(p)
+            && !p
+                .file_name()
+                .is_some_and(|name| name == "setup.py" || name == "conftest.py")
+    })
+}
+
+/// Walk `paths` for files accepted by `keep`, honouring excludes and `.gitignore`.
+fn find_python_files(
+    paths: &[PathBuf],
+    exclude: &[String],
+    keep: impl Fn(&Path) -> bool,
+) -> Result<Vec<PathBuf>> {
     let mut test_files = Vec::new();
 
     for path in paths {
         if path.is_file() {
-            if is_test_file(path) {
+            if keep(path) {
                 test_files.push(path.clone());
             }
         } else if path.is_dir() {
+            let mut excludes = ExcludeSet::new(exclude);
+            excludes.add_gitignore(path);
+
             for entry in WalkDir::new(path)
                 .into_iter()
+                .filter_entry(|e| {
+                    e.depth() == 0
+                        || e.path()
+                            .strip_prefix(path)
+                            .is_ok_and(|rel| !excludes.is_excluded(rel))
+                })
                 .filter_map(|e| e.ok())
                 .filter(|e| e.file_type().is_file())
             {
                 let p = entry.path();
-                if is_test_file(p) {
+                if keep(p) {
                     test_files.push(p.to_path_buf());
                 }
             }
@@ -87,20 +266,97 @@ pub fn find_test_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
     Ok(test_files)
 }
 
-fn is_test_file(path: &Path) -> bool {
-    let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
-        return false;
-    };
+/// Name patterns identifying test files, functions and classes.
+///
+/// Patterns are globs (`*` and `?`). File patterns are matched against the
+/// file name without its `.py` extension, which may be included or omitted.
+#[derive(Debug, Clone)]
+pub struct TestPatterns {
+    files: Vec<Regex>,
+    functions: Vec<Regex>,
+    classes: Vec<Regex>,
+    /// How long parsing one file may take before collection gives up on it
+    collect_timeout: Option<Duration>,
+}
 
-    if !file_name.ends_with(".py") {
-        return false;
+/// Default test file patterns (`test_*.py`, `_test*.py`).
+pub const DEFAULT_FILE_PATTERNS: &[&str] = &["test_*", "_test*"];
+/// Default test function and method patterns.
+pub const DEFAULT_FUNCTION_PATTERNS: &[&str] = &["test_*", "_test*"];
+/// Default test class patterns.
+pub const DEFAULT_CLASS_PATTERNS: &[&str] = &["Test*"];
+
+impl TestPatterns {
+    /// Compile patterns, falling back to the defaults for any empty list.
+    pub fn new(files: &[String], functions: &[String], classes: &[String]) -> Self {
+        fn compile(patterns: &[String], defaults: &[&str]) -> Vec<Regex> {
+            let compiled: Vec<_> = patterns
+                .iter()
+                .filter_map(|p| exclude::glob_to_regex(p).ok())
+                .collect();
+            if compiled.is_empty() {
+                defaults
+                    .iter()
+                    .filter_map(|p| exclude::glob_to_regex(p).ok())
+                    .collect()
+            } else {
+                compiled
+            }
+        }
+
+        let files: Vec<String> = files
+            .iter()
+            .map(|p| p.strip_suffix(".py").unwrap_or(p).to_string())
+            .collect();
+
+        Self {
+            files: compile(&files, DEFAULT_FILE_PATTERNS),
+            functions: compile(functions, DEFAULT_FUNCTION_PATTERNS),
+            classes: compile(classes, DEFAULT_CLASS_PATTERNS),
+            collect_timeout: None,
+        }
+    }
+
+    /// Give up on files that take longer than `timeout` to parse, reporting them
+    /// as collection errors (`None`, the default, waits as long as it takes).
+    pub fn with_collect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.collect_timeout = timeout;
+        self
     }
 
-    file_name.starts_with("test_") || file_name.starts_with("_test")
+    /// Patterns from `[tool.taut]` (`file_patterns`, `function_patterns`, `class_patterns`).
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            &config.file_patterns,
+            &config.function_patterns,
+            &config.class_patterns,
+        )
+    }
+
+    pub fn is_test_file(&self, path: &Path) -> bool {
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+            return false;
+        };
+        let Some(stem) = file_name.strip_suffix(".py") else {
+            return false;
+        };
+
+        self.files.iter().any(|r| r.is_match(stem))
+    }
+
+    pub fn is_test_name(&self, name: &str) -> bool {
+        self.functions.iter().any(|r| r.is_match(name))
+    }
+
+    pub fn is_test_class(&self, name: &str) -> bool {
+        self.classes.iter().any(|r| r.is_match(name))
+    }
 }
 
-fn is_test_name(name: &str) -> bool {
-    name.starts_with("test_") || name.starts_with("_test")
+impl Default for TestPatterns {
+    fn default() -> Self {
+        Self::new(&[], &[], &[])
+    }
 }
 
 /// Convert byte offset to line number (1-indexed)
@@ -112,130 +368,418 @@ fn offset_to_line(source: &str, offset: usize) -> usize {
         + 1
 }
 
+/// Line of the first decorator in `decorators`, if any
+fn decorator_line(source: &str, decorators: &[ast::Expr]) -> Option<usize> {
+    decorators
+        .first()
+        .map(|d| offset_to_line(source, d.range().start().into()))
+}
+
+/// Whether a positional argument is a test ID (`file::[Class::]function`)
+/// rather than a path.
+pub fn is_test_id(arg: &str) -> bool {
+    arg.contains("::")
+}
+
+/// Split a test ID into its file, optional class and function name.
+fn split_test_id(id: &str) -> Option<(&str, Option<&str>, &str)> {
+    let (file, name) = id.split_once("::")?;
+    Some(match name.rsplit_once("::") {
+        Some((class, function)) => (file, Some(class), function),
+        None => (file, None, name),
+    })
+}
+
+/// Resolve a test ID of the form `file::[Class::]function` to its `TestItem`.
+///
+/// Fails if the file can't be parsed or doesn't define the named test.
+pub fn resolve_test_id(
+    id: &str,
+    patterns: &TestPatterns,
+    rootdir: Option<&Path>,
+) -> Result<TestItem> {
+    let Some((file, class, function)) = split_test_id(id) else {
+        anyhow::bail!("Invalid test ID '{}': expected <file>::<test>", id);
+    };
+
+    // IDs are relative to the rootdir, but paths relative to the CWD still work
+    let mut file = PathBuf::from(file);
+    if !file.is_file() {
+        match rootdir.map(|root| root.join(&file)) {
+            Some(rooted) if rooted.is_file() => file = rooted,
+            _ => anyhow::bail!("Test file not found: {}", file.display()),
+        }
+    }
+
+    let items = if function.starts_with("doctest:") {
+        extract_doctests_from_file_with(&file, patterns)?
+    } else {
+        extract_tests_from_file_with(&file, patterns)?
+    };
+    items
+        .into_iter()
+        .find(|t| t.name() == function && t.class_path().as_deref() == class)
+        .ok_or_else(|| anyhow::anyhow!("Test not found: {}", id))
+}
+
+/// Read newline-delimited test IDs from a file, skipping blank lines and `#` comments.
+pub fn read_test_ids(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+/// Parse `source`, read from `path`, within `timeout`. On timeout the parse
+/// is left to finish on its own thread and its result is dropped.
+fn parse_source(
+    source: &str,
+    source_path: &str,
+    path: &Path,
+    timeout: Option<Duration>,
+) -> Result<ast::Suite> {
+    let parse_error = |e: String| anyhow::anyhow!("Parse error in {}: {}", path.display(), e);
+    let Some(timeout) = timeout else {
+        return ast::Suite::parse(source, source_path).map_err(|e| parse_error(e.to_string()));
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let (source, source_path) = (source.to_string(), source_path.to_string());
+    std::thread::spawn(move || {
+        let _ = tx.send(ast::Suite::parse(&source, &source_path).map_err(|e| e.to_string()));
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result.map_err(parse_error),
+        Err(_) => anyhow::bail!(
+            "Timed out parsing {} after {:.1}s (see --collect-timeout)",
+            path.display(),
+            timeout.as_secs_f64()
+        ),
+    }
+}
+
 /// Parse a Python file and extract test items
 pub fn extract_tests_from_file(path: &Path) -> Result<Vec<TestItem>> {
+    extract_tests_from_file_with(path, &TestPatterns::default())
+}
+
+/// Like [`extract_tests_from_file`], using `patterns` to recognize tests.
+pub fn extract_tests_from_file_with(path: &Path, patterns: &TestPatterns) -> Result<Vec<TestItem>> {
     let source = std::fs::read_to_string(path)
         .with_context(|| format!("Failed to read {}", path.display()))?;
 
-    let ast = ast::Suite::parse(&source, "<test>")
-        .map_err(|e| anyhow::anyhow!("Parse error in {}: {}", path.display(), e))?;
+    let ast = parse_source(&source, "<test>", path, patterns.collect_timeout)?;
 
     let mut items = Vec::new();
 
     for stmt in ast {
         match stmt {
             ast::Stmt::FunctionDef(func) => {
-                if is_test_name(func.name.as_str()) {
+                if patterns.is_test_name(func.name.as_str()) {
                     let func_markers = markers::extract_markers(&func.decorator_list);
                     items.push(TestItem {
                         file: path.to_path_buf(),
                         function: func.name.to_string(),
                         class: None,
                         line: offset_to_line(&source, func.range.start().into()),
+                        decorator_line: decorator_line(&source, &func.decorator_list),
                         markers: func_markers,
+                        params: vec![],
+                        kind: TestKind::Function,
+                        rootdir: None,
                     });
                 }
             }
             ast::Stmt::AsyncFunctionDef(func) => {
-                if is_test_name(func.name.as_str()) {
+                if patterns.is_test_name(func.name.as_str()) {
                     let func_markers = markers::extract_markers(&func.decorator_list);
                     items.push(TestItem {
                         file: path.to_path_buf(),
                         function: func.name.to_string(),
                         class: None,
                         line: offset_to_line(&source, func.range.start().into()),
+                        decorator_line: decorator_line(&source, &func.decorator_list),
                         markers: func_markers,
+                        params: vec![],
+                        kind: TestKind::Function,
+                        rootdir: None,
                     });
                 }
             }
             ast::Stmt::ClassDef(class) => {
-                if class.name.as_str().starts_with("Test") {
-                    // Extract class-level markers (e.g., @parallel on class)
-                    let class_markers = markers::extract_class_markers(&class.decorator_list);
-
-                    for body_stmt in &class.body {
-                        match body_stmt {
-                            ast::Stmt::FunctionDef(method) => {
-                                if is_test_name(method.name.as_str()) {
-                                    // Combine class markers with method markers
-                                    let mut method_markers =
-                                        markers::extract_markers(&method.decorator_list);
-                                    // Class @parallel applies to all methods
-                                    for class_marker in &class_markers {
-                                        if !method_markers
-                                            .iter()
-                                            .any(|m| m.name == class_marker.name)
-                                        {
-                                            method_markers.push(class_marker.clone());
-                                        }
-                                    }
-                                    items.push(TestItem {
-                                        file: path.to_path_buf(),
-                                        function: method.name.to_string(),
-                                        class: Some(class.name.to_string()),
-                                        line: offset_to_line(&source, method.range.start().into()),
-                                        markers: method_markers,
-                                    });
-                                }
-                            }
-                            ast::Stmt::AsyncFunctionDef(method) => {
-                                if is_test_name(method.name.as_str()) {
-                                    let mut method_markers =
-                                        markers::extract_markers(&method.decorator_list);
-                                    for class_marker in &class_markers {
-                                        if !method_markers
-                                            .iter()
-                                            .any(|m| m.name == class_marker.name)
-                                        {
-                                            method_markers.push(class_marker.clone());
-                                        }
-                                    }
-                                    items.push(TestItem {
-                                        file: path.to_path_buf(),
-                                        function: method.name.to_string(),
-                                        class: Some(class.name.to_string()),
-                                        line: offset_to_line(&source, method.range.start().into()),
-                                        markers: method_markers,
-                                    });
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
+                if patterns.is_test_class(class.name.as_str()) {
+                    extract_class_tests(&class, None, &[], &source, path, patterns, &mut items);
                 }
             }
             _ => {}
         }
     }
 
-    Ok(items)
+    Ok(items.into_iter().flat_map(expand_parametrized).collect())
 }
 
-/// Extract tests from multiple files, optionally filtering by glob pattern.
+/// Collect the test methods of a test class, recursing into nested test classes.
+///
+/// Nested classes get a dotted `class` (e.g. `TestOuter.TestInner`), and
+/// markers on an enclosing class apply to everything inside it.
+fn extract_class_tests(
+    class: &ast::StmtClassDef,
+    outer: Option<&str>,
+    outer_markers: &[Marker],
+    source: &str,
+    path: &Path,
+    patterns: &TestPatterns,
+    items: &mut Vec<TestItem>,
+) {
+    let class_name = match outer {
+        Some(outer) => format!("{}.{}", outer, class.name),
+        None => class.name.to_string(),
+    };
+
+    // Extract class-level markers (e.g., @parallel on class)
+    let mut class_markers = markers::extract_class_markers(&class.decorator_list);
+    inherit_markers(&mut class_markers, outer_markers);
+
+    for body_stmt in &class.body {
+        let (name, decorators, line) = match body_stmt {
+            ast::Stmt::FunctionDef(method) => (
+                &method.name,
+                &method.decorator_list,
+                offset_to_line(source, method.range.start().into()),
+            ),
+            ast::Stmt::AsyncFunctionDef(method) => (
+                &method.name,
+                &method.decorator_list,
+                offset_to_line(source, method.range.start().into()),
+            ),
+            ast::Stmt::ClassDef(inner) => {
+                if patterns.is_test_class(inner.name.as_str()) {
+                    extract_class_tests(
+                        inner,
+                        Some(&class_name),
+                        &class_markers,
+                        source,
+                        path,
+                        patterns,
+                        items,
+                    );
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        if !patterns.is_test_name(name.as_str()) {
+            continue;
+        }
+
+        // Class @parallel applies to all methods
+        let mut method_markers = markers::extract_markers(decorators);
+        inherit_markers(&mut method_markers, &class_markers);
+        items.push(TestItem {
+            file: path.to_path_buf(),
+            function: name.to_string(),
+            class: Some(class_name.clone()),
+            line,
+            decorator_line: decorator_line(source, decorators),
+            markers: method_markers,
+            params: vec![],
+            kind: TestKind::Function,
+            rootdir: None,
+        });
+    }
+}
+
+/// Add each of `inherited` to `markers` unless a marker of that name is already set.
+fn inherit_markers(markers: &mut Vec<Marker>, inherited: &[Marker]) {
+    for marker in inherited {
+        if !markers.iter().any(|m| m.name == marker.name) {
+            markers.push(marker.clone());
+        }
+    }
+}
+
+/// Extract tests from multiple files, keeping those that match any of the
+/// glob patterns in `filters` (all tests if there are none).
 ///
 /// Filter patterns (Go-style):
 /// - `test_user` - matches any test containing "test_user"
 /// - `test_*login` - glob pattern with wildcard
 /// - `TestClass/*` - matches all methods in TestClass (/ means ::)
 /// - `file.py::test_foo` - file-specific filtering
-pub fn extract_tests(files: &[PathBuf], filter_pattern: Option<&str>) -> Result<Vec<TestItem>> {
-    let mut all_items = Vec::new();
+/// - `!test_slow*` - excludes matching tests, whatever the other patterns say
+///
+/// Files that can't be read or parsed don't abort collection; they are
+/// reported in [`Collection::errors`].
+pub fn extract_tests(files: &[PathBuf], filters: &[String]) -> Result<Collection> {
+    extract_tests_with(files, filters, false, &TestPatterns::default())
+}
+
+/// Tests collected from a set of files.
+#[derive(Debug, Default)]
+pub struct Collection {
+    pub tests: Vec<TestItem>,
+    /// Files that couldn't be collected, with the error
+    pub errors: Vec<(PathBuf, String)>,
+    /// Problems that don't stop a file from being collected, such as
+    /// misspelled markers
+    pub warnings: Vec<(PathBuf, String)>,
+}
+
+/// Like [`extract_tests`], using `patterns` to recognize tests. With `exact`,
+/// filters match whole names only (see [`TestFilter::with_exact`]).
+pub fn extract_tests_with(
+    files: &[PathBuf],
+    filters: &[String],
+    exact: bool,
+    patterns: &TestPatterns,
+) -> Result<Collection> {
+    collect_files(files, filters, exact, |file| {
+        extract_tests_from_file_with(file, patterns)
+    })
+}
+
+/// Run `extract` over `files`, then apply the glob-based filters if provided.
+fn collect_files(
+    files: &[PathBuf],
+    filters: &[String],
+    exact: bool,
+    extract: impl Fn(&Path) -> Result<Vec<TestItem>>,
+) -> Result<Collection> {
+    let mut collection = Collection::default();
 
     for file in files {
-        match extract_tests_from_file(file) {
-            Ok(items) => all_items.extend(items),
-            Err(e) => eprintln!("Warning: {}", e),
+        match extract(file) {
+            Ok(items) => collection.tests.extend(items),
+            Err(e) => collection.errors.push((file.clone(), e.to_string())),
         }
     }
 
-    // Apply glob-based filter if provided
-    if let Some(pattern) = filter_pattern {
-        if !pattern.is_empty() {
-            let test_filter = TestFilter::new(pattern)
-                .map_err(|e| anyhow::anyhow!("Invalid filter pattern '{}': {}", pattern, e))?;
-            all_items.retain(|item| test_filter.matches(&item.id()));
+    let test_filters = filters
+        .iter()
+        .filter(|pattern| !pattern.is_empty())
+        .map(|pattern| {
+            TestFilter::with_exact(pattern, exact)
+                .map_err(|e| anyhow::anyhow!("Invalid filter pattern '{}': {}", pattern, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if !test_filters.is_empty() {
+        collection
+            .tests
+            .retain(|item| filter::matches_any(&test_filters, &item.id()));
+    }
+
+    for item in &collection.tests {
+        for (typo, intended) in item.marker_typos() {
+            let message = format!(
+                "{}: unknown decorator @{}, did you mean @{}?",
+                item.id(),
+                typo,
+                intended
+            );
+            collection.warnings.push((item.file.clone(), message));
+        }
+    }
+
+    Ok(collection)
+}
+
+/// Collect doctests from `files`, optionally filtering by glob patterns
+/// (see [`extract_tests`]). Only the collect timeout of `patterns` applies.
+pub fn extract_doctests(
+    files: &[PathBuf],
+    filters: &[String],
+    exact: bool,
+    patterns: &TestPatterns,
+) -> Result<Collection> {
+    collect_files(files, filters, exact, |file| {
+        extract_doctests_from_file_with(file, patterns)
+    })
+}
+
+/// Create a doctest item for each docstring in a module that has `>>>`
+/// examples: the module's own (`doctest:<module>`), and those of its
+/// functions, classes and methods (`doctest:Class.method`).
+pub fn extract_doctests_from_file(path: &Path) -> Result<Vec<TestItem>> {
+    extract_doctests_from_file_with(path, &TestPatterns::default())
+}
+
+/// Like [`extract_doctests_from_file`], within the collect timeout of `patterns`.
+pub fn extract_doctests_from_file_with(
+    path: &Path,
+    patterns: &TestPatterns,
+) -> Result<Vec<TestItem>> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let ast = parse_source(&source, "<module>", path, patterns.collect_timeout)?;
+
+    let mut items = Vec::new();
+    if docstring_has_examples(&ast) {
+        items.push(doctest_item(path, "<module>", 1));
+    }
+    collect_doctests(&ast, None, &source, path, &mut items);
+    Ok(items)
+}
+
+/// Add doctest items for definitions in `body`, recursing into classes
+/// (nested functions aren't reachable, so doctest never runs them).
+fn collect_doctests(
+    body: &[ast::Stmt],
+    outer: Option<&str>,
+    source: &str,
+    path: &Path,
+    items: &mut Vec<TestItem>,
+) {
+    for stmt in body {
+        let (name, inner, start) = match stmt {
+            ast::Stmt::FunctionDef(func) => (&func.name, &func.body, func.range.start()),
+            ast::Stmt::AsyncFunctionDef(func) => (&func.name, &func.body, func.range.start()),
+            ast::Stmt::ClassDef(class) => (&class.name, &class.body, class.range.start()),
+            _ => continue,
+        };
+        let qualname = match outer {
+            Some(outer) => format!("{}.{}", outer, name),
+            None => name.to_string(),
+        };
+
+        if docstring_has_examples(inner) {
+            items.push(doctest_item(
+                path,
+                &qualname,
+                offset_to_line(source, start.into()),
+            ));
+        }
+        if matches!(stmt, ast::Stmt::ClassDef(_)) {
+            collect_doctests(inner, Some(&qualname), source, path, items);
         }
     }
+}
+
+fn doctest_item(path: &Path, qualname: &str, line: usize) -> TestItem {
+    TestItem {
+        file: path.to_path_buf(),
+        function: format!("doctest:{}", qualname),
+        class: None,
+        line,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Doctest,
+        rootdir: None,
+    }
+}
 
-    Ok(all_items)
+/// Whether `body` starts with a docstring containing `>>>` examples.
+fn docstring_has_examples(body: &[ast::Stmt]) -> bool {
+    let Some(ast::Stmt::Expr(expr)) = body.first() else {
+        return false;
+    };
+    matches!(
+        expr.value.as_ref(),
+        ast::Expr::Constant(c) if matches!(&c.value, ast::Constant::Str(s) if s.contains(">>>"))
+    )
 }
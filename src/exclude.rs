@@ -0,0 +1,218 @@
+//! Path exclusion for test discovery.
+//!
+//! Keeps the directory walk out of virtualenvs, dependency trees and build
+//! output. Patterns are globs:
+//! - `node_modules` - matches any file or directory with that name
+//! - `*.egg-info` - `*` matches within a single path component
+//! - `build/generated` - patterns containing `/` match the path relative to
+//!   the search root
+//! - `docs/**` - `**` matches across path components
+//!
+//! A `.gitignore` at the search root is honored with the same rules
+//! (negated `!` patterns are not supported and are ignored).
+//!
+//! `--ignore` paths are handled separately by [`IgnoreList`], which filters
+//! the discovered files themselves.
+
+use regex::Regex;
+use std::path::{Component, Path, PathBuf};
+
+/// Directories that are never searched for tests.
+pub const DEFAULT_EXCLUDES: &[&str] = &[".venv", "venv", "node_modules", ".git", "__pycache__"];
+
+/// A compiled set of exclusion patterns.
+#[derive(Debug, Default)]
+pub struct ExcludeSet {
+    /// Patterns matched against a single file or directory name
+    names: Vec<Regex>,
+    /// Patterns matched against the path relative to the search root
+    paths: Vec<Regex>,
+}
+
+impl ExcludeSet {
+    /// Build a set from the default excludes plus `extra` patterns.
+    pub fn new(extra: &[String]) -> Self {
+        let mut set = Self::default();
+        for pattern in DEFAULT_EXCLUDES.iter().copied() {
+            set.add(pattern);
+        }
+        for pattern in extra {
+            set.add(pattern);
+        }
+        set
+    }
+
+    /// Add the patterns from `<root>/.gitignore`, if it exists.
+    pub fn add_gitignore(&mut self, root: &Path) {
+        let Ok(content) = std::fs::read_to_string(root.join(".gitignore")) else {
+            return;
+        };
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+                continue;
+            }
+            self.add(line);
+        }
+    }
+
+    /// Add a single glob pattern. Invalid patterns are skipped.
+    pub fn add(&mut self, pattern: &str) {
+        let pattern = pattern.trim_end_matches('/');
+        if pattern.is_empty() {
+            return;
+        }
+
+        if pattern.contains('/') {
+            if let Ok(regex) = glob_to_regex(pattern.trim_start_matches('/')) {
+                self.paths.push(regex);
+            }
+        } else if let Ok(regex) = glob_to_regex(pattern) {
+            self.names.push(regex);
+        }
+    }
+
+    /// Check whether `relative` (a path relative to the search root) is excluded.
+    pub fn is_excluded(&self, relative: &Path) -> bool {
+        let name = relative
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        if self.names.iter().any(|r| r.is_match(&name)) {
+            return true;
+        }
+
+        let relative = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        self.paths.iter().any(|r| r.is_match(&relative))
+    }
+}
+
+/// Paths passed with `--ignore`, matched against discovered test files.
+///
+/// A plain path ignores that file or everything below that directory; a
+/// pattern containing `*` or `?` is a glob. Both are tried against the path as
+/// discovered and against its absolute form.
+#[derive(Debug, Default)]
+pub struct IgnoreList {
+    prefixes: Vec<PathBuf>,
+    globs: Vec<Regex>,
+}
+
+impl IgnoreList {
+    pub fn new(patterns: &[String]) -> Self {
+        let mut list = Self::default();
+        for pattern in patterns {
+            if pattern.contains(['*', '?']) {
+                if let Ok(regex) = glob_to_regex(&slash_path(Path::new(pattern))) {
+                    list.globs.push(regex);
+                }
+            } else {
+                let path = Path::new(pattern);
+                if let Ok(absolute) = path.canonicalize() {
+                    list.prefixes.push(absolute);
+                }
+                list.prefixes.push(normalize(path));
+            }
+        }
+        list
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty() && self.globs.is_empty()
+    }
+
+    /// Check whether a discovered test file should be skipped.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let mut candidates = vec![normalize(path)];
+        if let Ok(absolute) = path.canonicalize() {
+            candidates.push(absolute);
+        }
+        candidates.iter().any(|candidate| {
+            self.prefixes.iter().any(|p| candidate.starts_with(p))
+                || self
+                    .globs
+                    .iter()
+                    .any(|r| r.is_match(&slash_path(candidate)))
+        })
+    }
+}
+
+/// Drop `.` components so `./tests/slow` and `tests/slow` compare equal.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+/// Render a path with `/` separators for glob matching.
+fn slash_path(path: &Path) -> String {
+    normalize(path).to_string_lossy().replace('\\', "/")
+}
+
+/// Convert a path glob to an anchored regex.
+pub(crate) fn glob_to_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    let mut regex_str = String::with_capacity(pattern.len() * 2 + 2);
+    regex_str.push('^');
+
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex_str.push_str(".*");
+            }
+            '*' => regex_str.push_str("[^/]*"),
+            '?' => regex_str.push_str("[^/]"),
+            _ => regex_str.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    regex_str.push('$');
+    Regex::new(&regex_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_exclude_virtualenvs() {
+        let set = ExcludeSet::new(&[]);
+        assert!(set.is_excluded(Path::new(".venv")));
+        assert!(set.is_excluded(Path::new("pkg/node_modules")));
+        assert!(!set.is_excluded(Path::new("tests")));
+    }
+
+    #[test]
+    fn name_globs_match_single_component() {
+        let set = ExcludeSet::new(&["*.egg-info".to_string()]);
+        assert!(set.is_excluded(Path::new("src/taut.egg-info")));
+        assert!(!set.is_excluded(Path::new("src/egg")));
+    }
+
+    #[test]
+    fn path_patterns_match_relative_path() {
+        let set = ExcludeSet::new(&["build/generated".to_string(), "/docs/**".to_string()]);
+        assert!(set.is_excluded(Path::new("build/generated")));
+        assert!(!set.is_excluded(Path::new("generated")));
+        assert!(set.is_excluded(Path::new("docs/examples/test_x.py")));
+        assert!(!set.is_excluded(Path::new("src/docs")));
+    }
+
+    #[test]
+    fn ignore_prefixes_match_whole_components() {
+        let ignore = IgnoreList::new(&["./tests/slow".to_string(), "*_wip.py".to_string()]);
+        assert!(ignore.is_ignored(Path::new("./tests/slow/test_big.py")));
+        assert!(ignore.is_ignored(Path::new("tests/slow/deep/test_x.py")));
+        assert!(!ignore.is_ignored(Path::new("tests/slowish/test_x.py")));
+        assert!(ignore.is_ignored(Path::new("test_feature_wip.py")));
+        assert!(!ignore.is_ignored(Path::new("tests/test_fast.py")));
+    }
+}
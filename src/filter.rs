@@ -5,6 +5,8 @@
 //! - `test_*login` - glob pattern with wildcard
 //! - `test_user/*` - class/subtest syntax
 //! - `test_login.py::test_user` - file-specific filtering
+//! - `!test_slow*` - negation, excluding matching tests
+//! - `re:test_(foo|bar)$` - raw regex against the whole test ID
 
 use regex::Regex;
 
@@ -17,6 +19,10 @@ pub struct TestFilter {
     regex: Regex,
     /// Optional file pattern (for file.py::test syntax)
     file_pattern: Option<Regex>,
+    /// Leading `!`: the filter keeps tests the rest of the pattern doesn't match
+    negated: bool,
+    /// `re:` pattern: `regex` is matched against the whole test ID
+    whole_id: bool,
 }
 
 impl TestFilter {
@@ -27,22 +33,51 @@ impl TestFilter {
     /// - `test_*foo` → glob wildcard, matches test_bar_foo, test_foo, etc.
     /// - `TestClass/*` → matches all methods in TestClass
     /// - `file.py::test_foo` → matches test_foo only in file.py
+    /// - `!test_foo` → matches every test the pattern after `!` doesn't
+    /// - `re:test_(foo|bar)$` → case-insensitive regex over the whole test ID
     pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Self::with_exact(pattern, false)
+    }
+
+    /// Like [`new`](Self::new), but with `exact` the test part must match a whole
+    /// name rather than a substring: `test_foo` matches `file.py::test_foo` and
+    /// `file.py::TestClass::test_foo`, but not `test_foobar`. `re:` patterns
+    /// are used as written.
+    pub fn with_exact(pattern: &str, exact: bool) -> Result<Self, regex::Error> {
+        let (negated, glob) = match pattern.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+
+        if let Some(raw) = glob.strip_prefix("re:") {
+            return Ok(Self {
+                pattern: pattern.to_string(),
+                regex: Regex::new(&format!("(?i){raw}"))?,
+                file_pattern: None,
+                negated,
+                whole_id: true,
+            });
+        }
+
         // Handle file.py::test syntax
-        if let Some((file_part, test_part)) = pattern.split_once("::") {
-            let file_regex = glob_to_regex(file_part)?;
-            let test_regex = glob_to_regex(test_part)?;
+        if let Some((file_part, test_part)) = glob.split_once("::") {
+            let file_regex = glob_to_regex(file_part, false)?;
+            let test_regex = glob_to_regex(test_part, exact)?;
             Ok(Self {
                 pattern: pattern.to_string(),
                 regex: test_regex,
                 file_pattern: Some(file_regex),
+                negated,
+                whole_id: false,
             })
         } else {
-            let regex = glob_to_regex(pattern)?;
+            let regex = glob_to_regex(glob, exact)?;
             Ok(Self {
                 pattern: pattern.to_string(),
                 regex,
                 file_pattern: None,
+                negated,
+                whole_id: false,
             })
         }
     }
@@ -51,6 +86,15 @@ impl TestFilter {
     ///
     /// Test ID format: `path/to/file.py::TestClass::test_method` or `path/to/file.py::test_func`
     pub fn matches(&self, test_id: &str) -> bool {
+        self.pattern_matches(test_id) != self.negated
+    }
+
+    /// Whether the pattern itself (ignoring a leading `!`) matches the test ID.
+    fn pattern_matches(&self, test_id: &str) -> bool {
+        if self.whole_id {
+            return self.regex.is_match(test_id);
+        }
+
         // Split test ID into file and test parts
         let (file_part, test_part) = if let Some(idx) = test_id.find("::") {
             (&test_id[..idx], &test_id[idx + 2..])
@@ -73,22 +117,40 @@ impl TestFilter {
     pub fn pattern(&self) -> &str {
         &self.pattern
     }
+
+    /// Whether the pattern starts with `!`.
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+}
+
+/// Whether a test passes a set of filters (repeated `-k`): it must match at
+/// least one plain pattern, if there are any, and every negated one.
+pub fn matches_any(filters: &[TestFilter], test_id: &str) -> bool {
+    let (negated, plain): (Vec<_>, Vec<_>) = filters.iter().partition(|f| f.is_negated());
+    (plain.is_empty() || plain.iter().any(|f| f.matches(test_id)))
+        && negated.iter().all(|f| f.matches(test_id))
 }
 
-/// Convert a glob pattern to a case-insensitive regex.
+/// Convert a glob pattern to a case-insensitive regex. With `exact`, the
+/// pattern must run to the end of the text and start at its beginning or
+/// right after a `::`.
 ///
 /// Glob patterns:
 /// - `*` → matches any sequence of characters (except ::)
 /// - `?` → matches any single character
 /// - Other characters are escaped
-fn glob_to_regex(pattern: &str) -> Result<Regex, regex::Error> {
-    let mut regex_str = String::with_capacity(pattern.len() * 2 + 4);
+fn glob_to_regex(pattern: &str, exact: bool) -> Result<Regex, regex::Error> {
+    let mut regex_str = String::with_capacity(pattern.len() * 2 + 20);
 
     // Case-insensitive matching (like Go's -run and pytest's -k)
     regex_str.push_str("(?i)");
 
-    // Don't anchor at start - allow substring matching by default
+    // Unless exact, don't anchor at start - allow substring matching
     // This matches Go's -run behavior
+    if exact {
+        regex_str.push_str("^(?:.*::)?(?:");
+    }
 
     for c in pattern.chars() {
         match c {
@@ -110,6 +172,9 @@ fn glob_to_regex(pattern: &str) -> Result<Regex, regex::Error> {
             _ => regex_str.push(c),
         }
     }
+    if exact {
+        regex_str.push_str(")$");
+    }
 
     Regex::new(&regex_str)
 }
@@ -190,6 +255,116 @@ mod tests {
         assert!(!filter.matches("testXfoo")); // . is literal, not regex wildcard
     }
 
+    #[test]
+    fn test_negated_pattern() {
+        let filter = TestFilter::new("!test_slow*").unwrap();
+        assert!(filter.is_negated());
+        assert!(filter.matches("tests/api.py::test_fast"));
+        assert!(!filter.matches("tests/api.py::test_slow_upload"));
+        assert_eq!(filter.pattern(), "!test_slow*");
+    }
+
+    #[test]
+    fn test_negated_file_pattern() {
+        let filter = TestFilter::new("!legacy*::*").unwrap();
+        assert!(filter.matches("tests/api.py::test_fast"));
+        assert!(!filter.matches("tests/legacy_api.py::test_fast"));
+    }
+
+    #[test]
+    fn test_multiple_patterns_union() {
+        let filters = [
+            TestFilter::new("alpha").unwrap(),
+            TestFilter::new("beta").unwrap(),
+        ];
+        assert!(matches_any(&filters, "test_x.py::test_alpha"));
+        assert!(matches_any(&filters, "test_x.py::test_beta"));
+        assert!(!matches_any(&filters, "test_x.py::test_gamma"));
+    }
+
+    #[test]
+    fn test_multiple_patterns_with_negation() {
+        let filters = [
+            TestFilter::new("test_user*").unwrap(),
+            TestFilter::new("test_api*").unwrap(),
+            TestFilter::new("!*slow").unwrap(),
+        ];
+        assert!(matches_any(&filters, "t.py::test_user_login"));
+        assert!(matches_any(&filters, "t.py::test_api_get"));
+        assert!(!matches_any(&filters, "t.py::test_api_slow"));
+        assert!(!matches_any(&filters, "t.py::test_admin"));
+
+        // Only negations: everything else is kept
+        let filters = [TestFilter::new("!*slow").unwrap()];
+        assert!(matches_any(&filters, "t.py::test_admin"));
+        assert!(!matches_any(&filters, "t.py::test_admin_slow"));
+    }
+
+    #[test]
+    fn test_exact_match() {
+        let filter = TestFilter::with_exact("test_foo", true).unwrap();
+        assert!(filter.matches("tests/a.py::test_foo"));
+        assert!(filter.matches("tests/a.py::TestFoo::test_foo"));
+        assert!(!filter.matches("tests/a.py::test_foobar"));
+        assert!(!filter.matches("tests/a.py::test_foo_bar"));
+        assert!(!filter.matches("tests/a.py::my_test_foo"));
+
+        // Substring matching is still the default
+        assert!(
+            TestFilter::new("test_foo")
+                .unwrap()
+                .matches("tests/a.py::test_foobar")
+        );
+    }
+
+    #[test]
+    fn test_exact_match_file_qualified() {
+        let filter = TestFilter::with_exact("a.py::test_foo", true).unwrap();
+        assert!(filter.matches("tests/a.py::test_foo"));
+        assert!(!filter.matches("tests/a.py::test_foobar"));
+        assert!(!filter.matches("tests/b.py::test_foo"));
+
+        let filter = TestFilter::with_exact("TestFoo/test_*", true).unwrap();
+        assert!(filter.matches("tests/a.py::TestFoo::test_foo"));
+        assert!(!filter.matches("tests/a.py::TestFooBar::test_foo"));
+    }
+
+    #[test]
+    fn test_exact_match_negated() {
+        let filter = TestFilter::with_exact("!test_foo", true).unwrap();
+        assert!(!filter.matches("tests/a.py::test_foo"));
+        assert!(filter.matches("tests/a.py::test_foobar"));
+    }
+
+    #[test]
+    fn test_regex_pattern() {
+        let filter = TestFilter::new("re:test_(foo|bar)$").unwrap();
+        assert!(filter.matches("tests/a.py::test_foo"));
+        assert!(filter.matches("tests/a.py::TestX::test_bar"));
+        assert!(!filter.matches("tests/a.py::test_foobar"));
+        assert!(!filter.matches("tests/a.py::test_baz"));
+
+        // Case-insensitive, and matched against the whole ID including the file
+        assert!(filter.matches("tests/a.py::TEST_FOO"));
+        let filter = TestFilter::new(r"re:^tests/.*\.py::test_a").unwrap();
+        assert!(filter.matches("tests/a.py::test_a"));
+        assert!(!filter.matches("other/a.py::test_a"));
+    }
+
+    #[test]
+    fn test_negated_regex_pattern() {
+        let filter = TestFilter::new("!re:_(slow|flaky)$").unwrap();
+        assert!(filter.matches("tests/a.py::test_upload"));
+        assert!(!filter.matches("tests/a.py::test_upload_slow"));
+    }
+
+    #[test]
+    fn test_invalid_regex_pattern() {
+        assert!(TestFilter::new("re:test_(foo").is_err());
+        // The same text is fine as a glob, where parentheses are literal
+        assert!(TestFilter::new("test_(foo").is_ok());
+    }
+
     #[test]
     fn test_empty_pattern_matches_all() {
         let filter = TestFilter::new("").unwrap();
@@ -1,14 +1,17 @@
+pub mod bisect;
 pub mod blocks;
 pub mod cache;
 pub mod cli;
 pub mod config;
 pub mod depdb;
 pub mod discovery;
+pub mod exclude;
 pub mod filter;
 pub mod markers;
 pub mod output;
 pub mod runner;
 pub mod selection;
+pub mod shuffle;
 pub mod worker_pool;
 
 #[cfg(feature = "extension-module")]
@@ -1,6 +1,6 @@
 //! Marker extraction from Python AST.
 //!
-//! Parses decorators like @skip, @mark, and @parallel from test functions.
+//! Parses decorators like @skip, @mark, @parallel and @parametrize from test functions.
 
 use num_traits::cast::ToPrimitive;
 use rustpython_parser::ast;
@@ -16,6 +16,27 @@ pub struct Marker {
     pub args: MarkerArgs,
 }
 
+impl Marker {
+    /// The marker with its argument names and value types, e.g. `@mark(group=str, slow=bool)`.
+    pub fn shape(&self) -> String {
+        let mut args = Vec::new();
+        if self.args.reason.is_some() {
+            args.push("reason".to_string());
+        }
+        if !self.args.param_names.is_empty() {
+            args.push(format!("[{}]", self.args.param_names.join(", ")));
+        }
+        let mut kwargs: Vec<_> = self.args.kwargs.iter().collect();
+        kwargs.sort_by_key(|(key, _)| key.as_str());
+        args.extend(
+            kwargs
+                .into_iter()
+                .map(|(key, value)| format!("{}={}", key, value.type_name())),
+        );
+        format!("@{}({})", self.name, args.join(", "))
+    }
+}
+
 /// Arguments passed to a marker decorator.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct MarkerArgs {
@@ -23,8 +44,17 @@ pub struct MarkerArgs {
     pub reason: Option<String>,
     /// Keyword arguments (for @mark(slow=True, group="auth"))
     pub kwargs: HashMap<String, MarkerValue>,
+    /// Argument names (for @parametrize("x, y", ...))
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub param_names: Vec<String>,
+    /// Literal argument values, one row per case (for @parametrize(..., [(1, 2), (3, 4)]))
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub param_values: Vec<Vec<serde_json::Value>>,
 }
 
+/// Keyword arguments for one `@parametrize` case, in declaration order.
+pub type ParamCase = Vec<(String, serde_json::Value)>;
+
 /// A value in a marker argument.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
@@ -36,6 +66,19 @@ pub enum MarkerValue {
     List(Vec<String>),
 }
 
+impl MarkerValue {
+    /// Python-ish name of the value's type.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            MarkerValue::Bool(_) => "bool",
+            MarkerValue::String(_) => "str",
+            MarkerValue::Int(_) => "int",
+            MarkerValue::Float(_) => "float",
+            MarkerValue::List(_) => "list",
+        }
+    }
+}
+
 impl std::fmt::Display for MarkerValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -48,6 +91,35 @@ impl std::fmt::Display for MarkerValue {
     }
 }
 
+/// Names of the decorators taut recognizes as markers.
+const KNOWN_MARKERS: [&str; 4] = ["skip", "mark", "parallel", "parametrize"];
+
+/// The marker `name` was probably meant to be: one that is a single edit away.
+///
+/// Decorators like this are kept as markers under their misspelled name, so they
+/// can be reported instead of silently doing nothing.
+pub fn likely_marker(name: &str) -> Option<&'static str> {
+    KNOWN_MARKERS
+        .into_iter()
+        .find(|known| *known != name && within_one_edit(name, known))
+}
+
+/// Whether `a` can be turned into `b` by one insertion, deletion or substitution.
+fn within_one_edit(a: &str, b: &str) -> bool {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if long.len() - short.len() > 1 {
+        return false;
+    }
+    let prefix = short.iter().zip(&long).take_while(|(x, y)| x == y).count();
+    let suffix_start = if short.len() == long.len() {
+        prefix + 1
+    } else {
+        prefix
+    };
+    short.get(suffix_start..) == long.get(prefix + 1..)
+}
+
 /// Extract markers from a function's decorator list.
 pub fn extract_markers(decorators: &[ast::Expr]) -> Vec<Marker> {
     decorators.iter().filter_map(parse_decorator).collect()
@@ -113,13 +185,52 @@ pub fn get_groups(markers: &[Marker]) -> Vec<String> {
         .collect()
 }
 
+/// Expand `@parametrize` markers into one argument set per case.
+///
+/// Stacked `@parametrize` decorators combine as a cartesian product. Returns an
+/// empty list when the test isn't parametrized.
+pub fn get_param_cases(markers: &[Marker]) -> Vec<ParamCase> {
+    let mut cases: Vec<ParamCase> = Vec::new();
+    for marker in markers.iter().filter(|m| m.name == "parametrize") {
+        let rows: Vec<ParamCase> = marker
+            .args
+            .param_values
+            .iter()
+            .map(|row| {
+                marker
+                    .args
+                    .param_names
+                    .iter()
+                    .cloned()
+                    .zip(row.iter().cloned())
+                    .collect()
+            })
+            .collect();
+        cases = if cases.is_empty() {
+            rows
+        } else {
+            cases
+                .iter()
+                .flat_map(|case| {
+                    rows.iter().map(move |row| {
+                        let mut combined = case.clone();
+                        combined.extend(row.iter().cloned());
+                        combined
+                    })
+                })
+                .collect()
+        };
+    }
+    cases
+}
+
 /// Parse a single decorator expression into a Marker.
 fn parse_decorator(decorator: &ast::Expr) -> Option<Marker> {
     match decorator {
         // @skip or @parallel (no parens)
         ast::Expr::Name(name) => {
             let name_str = name.id.as_str();
-            if matches!(name_str, "skip" | "parallel") {
+            if matches!(name_str, "skip" | "parallel") || likely_marker(name_str).is_some() {
                 Some(Marker {
                     name: name_str.to_string(),
                     args: MarkerArgs::default(),
@@ -135,7 +246,7 @@ fn parse_decorator(decorator: &ast::Expr) -> Option<Marker> {
         // @taut.skip, @taut.parallel, etc. (attribute access)
         ast::Expr::Attribute(attr) => {
             let name_str = attr.attr.as_str();
-            if matches!(name_str, "skip" | "parallel") {
+            if matches!(name_str, "skip" | "parallel") || likely_marker(name_str).is_some() {
                 Some(Marker {
                     name: name_str.to_string(),
                     args: MarkerArgs::default(),
@@ -157,7 +268,11 @@ fn parse_call_decorator(call: &ast::ExprCall) -> Option<Marker> {
         _ => return None,
     };
 
-    if !matches!(name.as_str(), "skip" | "mark" | "parallel") {
+    if name == "parametrize" {
+        return parse_parametrize(call);
+    }
+
+    if !matches!(name.as_str(), "skip" | "mark" | "parallel") && likely_marker(&name).is_none() {
         return None;
     }
 
@@ -189,6 +304,113 @@ fn parse_call_decorator(call: &ast::ExprCall) -> Option<Marker> {
     Some(Marker { name, args })
 }
 
+/// Parse `@parametrize("x", [1, 2])` or `@parametrize("x, y", [(1, 2), (3, 4)])`.
+///
+/// Names and values may also be passed as `argnames=`/`argvalues=`. Only
+/// literal values can be expanded statically; anything else drops the marker.
+fn parse_parametrize(call: &ast::ExprCall) -> Option<Marker> {
+    let keyword = |name: &str| {
+        call.keywords
+            .iter()
+            .find(|k| k.arg.as_ref().is_some_and(|a| a.as_str() == name))
+            .map(|k| &k.value)
+    };
+    let names_expr = call.args.first().or_else(|| keyword("argnames"))?;
+    let values_expr = call.args.get(1).or_else(|| keyword("argvalues"))?;
+
+    // Like pytest, only a string of names lets a single name take bare values;
+    // a list or tuple of names always expects each row to be a sequence
+    let (param_names, scalar_rows): (Vec<String>, bool) = match names_expr {
+        ast::Expr::List(ast::ExprList { elts, .. })
+        | ast::Expr::Tuple(ast::ExprTuple { elts, .. }) => (
+            elts.iter().map(expr_to_string).collect::<Option<_>>()?,
+            false,
+        ),
+        _ => {
+            let names: Vec<String> = expr_to_string(names_expr)?
+                .split(',')
+                .map(|n| n.trim().to_string())
+                .filter(|n| !n.is_empty())
+                .collect();
+            let scalar_rows = names.len() == 1;
+            (names, scalar_rows)
+        }
+    };
+    if param_names.is_empty() {
+        return None;
+    }
+
+    let rows = match values_expr {
+        ast::Expr::List(ast::ExprList { elts, .. })
+        | ast::Expr::Tuple(ast::ExprTuple { elts, .. }) => elts,
+        _ => return None,
+    };
+    let param_values = rows
+        .iter()
+        .map(|row| {
+            if scalar_rows {
+                return Some(vec![expr_to_literal(row)?]);
+            }
+            let values = match row {
+                ast::Expr::List(ast::ExprList { elts, .. })
+                | ast::Expr::Tuple(ast::ExprTuple { elts, .. }) => elts,
+                _ => return None,
+            };
+            if values.len() != param_names.len() {
+                return None;
+            }
+            values.iter().map(expr_to_literal).collect()
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if param_values.is_empty() {
+        return None;
+    }
+
+    Some(Marker {
+        name: "parametrize".to_string(),
+        args: MarkerArgs {
+            param_names,
+            param_values,
+            ..MarkerArgs::default()
+        },
+    })
+}
+
+/// Convert a literal expression (constants, negative numbers, lists and tuples) to JSON.
+fn expr_to_literal(expr: &ast::Expr) -> Option<serde_json::Value> {
+    use serde_json::Value;
+
+    match expr {
+        ast::Expr::Constant(c) => match &c.value {
+            ast::Constant::None => Some(Value::Null),
+            ast::Constant::Bool(b) => Some(Value::Bool(*b)),
+            ast::Constant::Str(s) => Some(Value::String(s.to_string())),
+            ast::Constant::Int(i) => i.to_i64().map(Value::from),
+            ast::Constant::Float(f) => serde_json::Number::from_f64(*f).map(Value::Number),
+            _ => None,
+        },
+        ast::Expr::UnaryOp(op) if matches!(op.op, ast::UnaryOp::USub) => {
+            match expr_to_literal(&op.operand)? {
+                Value::Number(n) => {
+                    if let Some(i) = n.as_i64() {
+                        Some(Value::from(-i))
+                    } else {
+                        serde_json::Number::from_f64(-n.as_f64()?).map(Value::Number)
+                    }
+                }
+                _ => None,
+            }
+        }
+        ast::Expr::List(ast::ExprList { elts, .. })
+        | ast::Expr::Tuple(ast::ExprTuple { elts, .. }) => elts
+            .iter()
+            .map(expr_to_literal)
+            .collect::<Option<_>>()
+            .map(Value::Array),
+        _ => None,
+    }
+}
+
 /// Convert an AST expression to a string (for @skip("reason")).
 fn expr_to_string(expr: &ast::Expr) -> Option<String> {
     match expr {
@@ -415,4 +637,103 @@ def test_foo():
         );
         assert!(markers.is_empty());
     }
+
+    #[test]
+    fn test_misspelled_marker_kept() {
+        let markers = parse_markers(
+            r#"
+@skp
+@taut.paralel
+@mrk(slow=True)
+def test_foo():
+    pass
+"#,
+        );
+        let names: Vec<_> = markers.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["skp", "paralel", "mrk"]);
+        assert_eq!(likely_marker("skp"), Some("skip"));
+        assert_eq!(likely_marker("paralel"), Some("parallel"));
+        assert_eq!(likely_marker("mrk"), Some("mark"));
+        assert_eq!(likely_marker("skip"), None);
+        assert_eq!(likely_marker("fixture"), None);
+    }
+
+    #[test]
+    fn test_parametrize_tuples() {
+        let markers = parse_markers(
+            r#"
+@parametrize("a, b", [(1, 2.5), ("x", [True, None])])
+def test_foo(a, b):
+    pass
+"#,
+        );
+        assert_eq!(markers.len(), 1);
+        assert_eq!(markers[0].name, "parametrize");
+        assert_eq!(markers[0].args.param_names, vec!["a", "b"]);
+        assert_eq!(
+            markers[0].args.param_values,
+            vec![
+                vec![serde_json::json!(1), serde_json::json!(2.5)],
+                vec![serde_json::json!("x"), serde_json::json!([true, null])],
+            ]
+        );
+        assert_eq!(get_param_cases(&markers).len(), 2);
+    }
+
+    #[test]
+    fn test_parametrize_single_name_tuple_unpacks_rows() {
+        let markers = parse_markers(
+            r#"
+@parametrize(("flag",), [(True,), (False,)])
+def test_flag(flag):
+    pass
+"#,
+        );
+        assert_eq!(markers.len(), 1);
+        assert_eq!(markers[0].args.param_names, vec!["flag"]);
+        assert_eq!(
+            markers[0].args.param_values,
+            vec![
+                vec![serde_json::json!(true)],
+                vec![serde_json::json!(false)]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parametrize_non_literal_ignored() {
+        let markers = parse_markers(
+            r#"
+@parametrize("n", CASES)
+def test_foo(n):
+    pass
+"#,
+        );
+        assert!(markers.is_empty());
+        assert!(get_param_cases(&markers).is_empty());
+    }
+
+    #[test]
+    fn test_marker_shape() {
+        let markers = parse_markers(
+            r#"
+@skip("flaky")
+@mark(slow=True, group=["db", "api"])
+@parallel()
+@parametrize("x, y", [(1, 2)])
+def test_foo(x, y):
+    pass
+"#,
+        );
+        let shapes: Vec<String> = markers.iter().map(Marker::shape).collect();
+        assert_eq!(
+            shapes,
+            vec![
+                "@skip(reason)",
+                "@mark(group=list, slow=bool)",
+                "@parallel()",
+                "@parametrize([x, y])",
+            ]
+        );
+    }
 }
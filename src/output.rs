@@ -1,23 +1,174 @@
-use crate::runner::{TestResult, TestResults};
+use crate::blocks::FileBlocks;
+use crate::discovery::TestItem;
+use crate::runner::{ErrorCategory, SkipKind, TestResult, TestResults};
+use crate::worker_pool::WorkerCrashed;
 use colored::Colorize;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
 use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
 use std::sync::Mutex;
 
+/// Format used to report test results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Streaming progress and a colored summary
+    Human,
+    /// Test Anything Protocol (TAP v13) stream on stdout
+    Tap,
+}
+
+/// How much per-test output to print while tests run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// No per-test output, only the final summary line
+    Quiet,
+    /// One character per test
+    Normal,
+    /// One line per test, with the start of each failure's traceback
+    Verbose,
+    /// One line per test, with full tracebacks
+    Debug,
+}
+
+impl Verbosity {
+    /// Resolve `-q` and the number of `-v` flags.
+    pub fn from_flags(quiet: bool, verbose: u8) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Self::Quiet,
+            (false, 0) => Self::Normal,
+            (false, 1) => Self::Verbose,
+            (false, _) => Self::Debug,
+        }
+    }
+}
+
+/// Running `k/N` counts for `--progress`.
+#[derive(Debug)]
+pub struct Progress {
+    total: usize,
+    /// (finished, failed)
+    counts: Mutex<(usize, usize)>,
+}
+
+impl Progress {
+    pub fn new(total: usize) -> Self {
+        Self {
+            total,
+            counts: Mutex::new((0, 0)),
+        }
+    }
+
+    /// Count a finished test and redraw the counter line in place.
+    pub fn record(&self, writer: &mut impl Write, result: &TestResult) -> io::Result<()> {
+        // Hold the lock while drawing so concurrent results can't interleave
+        let mut counts = self.counts.lock().unwrap();
+        counts.0 += 1;
+        if !result.passed && !result.skipped {
+            counts.1 += 1;
+        }
+        let width = self.total.to_string().len();
+        write!(writer, "\r[ {:>width$}/{} ]", counts.0, self.total)?;
+        if counts.1 > 0 {
+            write!(writer, " {}", format!("{} failed", counts.1).red())?;
+        }
+        writer.flush()
+    }
+}
+
+/// How much of each failure's traceback the summary shows (`--tb`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TracebackStyle {
+    /// Only the innermost frame and the exception
+    Short,
+    /// The full captured traceback
+    Long,
+    /// No traceback, just the message and location
+    No,
+}
+
+impl TracebackStyle {
+    /// Select the traceback lines to show for this style.
+    pub fn format<'a>(&self, traceback: &'a str) -> Vec<&'a str> {
+        let lines: Vec<&str> = traceback.lines().collect();
+        match self {
+            Self::No => Vec::new(),
+            Self::Long => lines,
+            Self::Short => {
+                // Frames look like `  File "path", line N, in name`, followed by source lines
+                let last_frame = lines
+                    .iter()
+                    .rposition(|line| line.trim_start().starts_with("File \""));
+                match last_frame {
+                    Some(i) => lines[i..].to_vec(),
+                    None => lines,
+                }
+            }
+        }
+    }
+}
+
+/// Whose captured stdout/stderr the summary shows (`--show-capture`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShowCapture {
+    /// Failed tests only
+    Failed,
+    /// Every test that printed something
+    All,
+    /// Nobody's
+    No,
+}
+
+impl ShowCapture {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "all" => Self::All,
+            "no" => Self::No,
+            _ => Self::Failed,
+        }
+    }
+}
+
+/// Longest captured stream the summary shows in full; longer ones keep their last lines.
+const MAX_CAPTURED_LINES: usize = 50;
+
 pub struct ProgressPrinter {
-    verbose: bool,
+    verbosity: Verbosity,
+    progress: Option<Progress>,
+    /// Leave skipped tests out of the per-test output; they still count in the summary
+    quiet_skips: bool,
     printed_header: Mutex<bool>,
     failed_tests: Mutex<Vec<TestResult>>,
 }
 
 impl ProgressPrinter {
-    pub fn new(verbose: bool) -> Self {
+    pub fn new(verbosity: Verbosity) -> Self {
         Self {
-            verbose,
+            verbosity,
+            progress: None,
+            quiet_skips: false,
             printed_header: Mutex::new(false),
             failed_tests: Mutex::new(Vec::new()),
         }
     }
 
+    /// Replace the per-test dots with a `[ k/N ]` counter rewritten in place.
+    pub fn with_progress(mut self, total: usize) -> Self {
+        self.progress = Some(Progress::new(total));
+        self
+    }
+
+    /// Print nothing for skipped tests, not even their `s`.
+    pub fn with_quiet_skips(mut self, quiet_skips: bool) -> Self {
+        self.quiet_skips = quiet_skips;
+        self
+    }
+
+    pub fn verbosity(&self) -> Verbosity {
+        self.verbosity
+    }
+
     fn print_header(&self) {
         let mut printed = self.printed_header.lock().unwrap();
         if !*printed {
@@ -28,20 +179,47 @@ impl ProgressPrinter {
     }
 
     pub fn print_result(&self, result: &TestResult) {
+        if self.verbosity == Verbosity::Quiet {
+            self.record_failure(result);
+            return;
+        }
+
+        if self.verbosity == Verbosity::Normal {
+            if let Some(ref progress) = self.progress {
+                let _ = progress.record(&mut io::stdout(), result);
+                self.record_failure(result);
+                return;
+            }
+        }
+
+        if result.skipped && self.quiet_skips {
+            return;
+        }
+
         self.print_header();
 
-        if self.verbose {
+        if self.verbosity >= Verbosity::Verbose {
             self.print_verbose(result);
         } else {
             self.print_compact(result);
         }
     }
 
+    fn record_failure(&self, result: &TestResult) {
+        if !result.passed && !result.skipped {
+            if let Ok(mut failed) = self.failed_tests.lock() {
+                failed.push(result.clone());
+            }
+        }
+    }
+
     fn print_compact(&self, result: &TestResult) {
         let symbol = if result.skipped {
             "s".cyan()
         } else if result.passed {
             ".".green()
+        } else if is_fixture_error(result) {
+            "E".yellow()
         } else {
             "F".red()
         };
@@ -50,11 +228,7 @@ impl ProgressPrinter {
         let _ = io::stdout().flush();
 
         // Store failed tests for later
-        if !result.passed && !result.skipped {
-            if let Ok(mut failed) = self.failed_tests.lock() {
-                failed.push(result.clone());
-            }
-        }
+        self.record_failure(result);
     }
 
     fn print_verbose(&self, result: &TestResult) {
@@ -85,10 +259,10 @@ impl ProgressPrinter {
             format!("{}ms", result.duration.as_millis())
         };
 
-        let name = if let Some(ref class) = result.item.class {
-            format!("{}::{}", class, result.item.function)
+        let name = if let Some(class) = result.item.class_path() {
+            format!("{}::{}", class, result.item.name())
         } else {
-            result.item.function.clone()
+            result.item.name()
         };
 
         let file = result.item.file.display();
@@ -100,13 +274,19 @@ impl ProgressPrinter {
             duration_str
         );
 
-        if !result.passed && !result.skipped {
-            if let Some(ref error) = result.error {
-                println!("    {}", error.message.red());
-                if let Some(ref tb) = error.traceback {
-                    for line in tb.lines().take(10) {
-                        println!("    {}", line.dimmed());
-                    }
+        if !result.passed
+            && !result.skipped
+            && let Some(ref error) = result.error
+        {
+            println!("    {}", error.message.red());
+            if let Some(ref tb) = error.traceback {
+                let limit = if self.verbosity == Verbosity::Debug {
+                    usize::MAX
+                } else {
+                    10
+                };
+                for line in tb.lines().take(limit) {
+                    println!("    {}", line.dimmed());
                 }
             }
         }
@@ -119,36 +299,161 @@ impl ProgressPrinter {
     }
 }
 
-pub fn print_summary(results: &TestResults, failed_tests: &[TestResult]) {
+/// Whether a failure came from a fixture (setUp/tearDown) rather than the test body.
+fn is_fixture_error(result: &TestResult) -> bool {
+    result.error.as_ref().is_some_and(|e| e.fixture.is_some())
+}
+
+pub fn print_summary(
+    results: &TestResults,
+    failed_tests: &[TestResult],
+    collection_errors: &[(PathBuf, String)],
+    tb: TracebackStyle,
+    show_capture: ShowCapture,
+) {
     println!();
+    print_collection_errors(collection_errors);
 
-    // Print failures
+    // Print failures, grouped under their file
     if !failed_tests.is_empty() {
         println!();
         println!("{}", "Failures:".red().bold());
-        for result in failed_tests {
-            let name = if let Some(ref class) = result.item.class {
-                format!("{}::{}", class, result.item.function)
-            } else {
-                result.item.function.clone()
-            };
+        for (file, results) in group_failures_by_file(failed_tests) {
             println!();
             println!(
-                "  {} {}::{}",
-                "✗".red(),
-                result.item.file.display().to_string().dimmed(),
-                name
+                "  {} {}",
+                file.display().to_string().bold(),
+                format!("({} failed)", results.len()).red()
             );
-            if let Some(ref error) = result.error {
-                println!("    {}", error.message.red());
-                println!("    {}:{}", result.item.file.display(), result.item.line);
+            for result in results {
+                let name = if let Some(class) = result.item.class_path() {
+                    format!("{}::{}", class, result.item.name())
+                } else {
+                    result.item.name()
+                };
+                println!();
+                println!("    {} {}", "✗".red(), name);
+                if let Some(ref error) = result.error {
+                    println!("      {}", error.message.red());
+                    if let Some(ref traceback) = error.traceback {
+                        for line in tb.format(traceback) {
+                            println!("      {}", line.dimmed());
+                        }
+                    }
+                    println!("      {}:{}", result.item.file.display(), result.item.line);
+                }
+                if show_capture != ShowCapture::No {
+                    print_captured_output(result);
+                }
             }
         }
         println!();
     }
 
+    if show_capture == ShowCapture::All {
+        let passed: Vec<_> = results
+            .results
+            .iter()
+            .filter(|r| r.passed && !r.skipped && has_captured_output(r))
+            .collect();
+        if !passed.is_empty() {
+            println!("{}", "Captured output of passed tests:".bold());
+            for result in passed {
+                println!();
+                println!("    {} {}", "✓".green(), result.item.id());
+                print_captured_output(result);
+            }
+            println!();
+        }
+    }
+
+    print_warnings(results);
+    print_summary_line(results);
+}
+
+/// List the Python warnings each test emitted.
+fn print_warnings(results: &TestResults) {
+    let warned: Vec<_> = results
+        .results
+        .iter()
+        .filter(|r| !r.warnings.is_empty())
+        .collect();
+    if warned.is_empty() {
+        return;
+    }
+
+    println!("{}", "Warnings:".yellow().bold());
+    for result in warned {
+        println!();
+        println!("    {}", result.item.id());
+        for warning in &result.warnings {
+            println!("      {}", warning.yellow());
+        }
+    }
+    println!();
+}
+
+fn has_captured_output(result: &TestResult) -> bool {
+    [&result.stdout, &result.stderr]
+        .iter()
+        .any(|stream| stream.as_deref().is_some_and(|s| !s.trim().is_empty()))
+}
+
+/// Print a test's captured stdout and stderr under the test, keeping only the
+/// last [`MAX_CAPTURED_LINES`] lines of each.
+fn print_captured_output(result: &TestResult) {
+    for (name, stream) in [("stdout", &result.stdout), ("stderr", &result.stderr)] {
+        let Some(text) = stream.as_deref().filter(|s| !s.trim().is_empty()) else {
+            continue;
+        };
+        let lines: Vec<&str> = text.lines().collect();
+        let hidden = lines.len().saturating_sub(MAX_CAPTURED_LINES);
+        println!("      {}", format!("Captured {}:", name).dimmed());
+        if hidden > 0 {
+            println!(
+                "        {}",
+                format!("... {} earlier lines not shown", hidden).dimmed()
+            );
+        }
+        for line in &lines[hidden..] {
+            println!("        {}", line);
+        }
+    }
+}
+
+/// List test files that couldn't be collected, e.g. because of a syntax error.
+pub fn print_collection_errors(errors: &[(PathBuf, String)]) {
+    if errors.is_empty() {
+        return;
+    }
+    println!();
+    println!("{}", "Collection errors:".red().bold());
+    for (file, error) in errors {
+        println!();
+        println!("  {} {}", "✗".red(), file.display().to_string().bold());
+        println!("      {}", error.red());
+    }
+}
+
+/// Bucket failures by test file, most failures first (ties keep first-failure order).
+fn group_failures_by_file(failed_tests: &[TestResult]) -> Vec<(&Path, Vec<&TestResult>)> {
+    let mut groups: Vec<(&Path, Vec<&TestResult>)> = Vec::new();
+    for result in failed_tests {
+        let file = result.item.file.as_path();
+        match groups.iter_mut().find(|(f, _)| *f == file) {
+            Some((_, results)) => results.push(result),
+            None => groups.push((file, vec![result])),
+        }
+    }
+    groups.sort_by_key(|(_, results)| std::cmp::Reverse(results.len()));
+    groups
+}
+
+/// Print the one-line pass/fail/skip tally.
+pub fn print_summary_line(results: &TestResults) {
     let passed = results.passed_count();
     let failed = results.failed_count();
+    let errored = results.errored_count();
     let skipped = results.skipped_count();
     let duration = results.total_duration.as_secs_f64();
 
@@ -157,20 +462,787 @@ pub fn print_summary(results: &TestResults, failed_tests: &[TestResult]) {
     if failed > 0 {
         parts.push(format!("{} failed", failed));
     }
+    if errored > 0 {
+        parts.push(format!("{} errored", errored));
+    }
     if skipped > 0 {
-        parts.push(format!("{} skipped", skipped));
+        parts.push(format_skipped(skipped, &results.skipped_by_kind()));
     }
     parts.push(format!("in {:.2}s", duration));
 
     let summary = parts.join(", ");
-    if failed == 0 {
+    if failed == 0 && errored == 0 {
         println!("{}", summary.green());
     } else {
         println!("{}", summary.red());
     }
 }
 
+/// Skip tally with its breakdown, e.g. "3 skipped (1 @skip, 2 unchanged)".
+fn format_skipped(skipped: usize, by_kind: &[(SkipKind, usize)]) -> String {
+    if by_kind.is_empty() {
+        return format!("{} skipped", skipped);
+    }
+    let kinds: Vec<String> = by_kind
+        .iter()
+        .map(|(kind, count)| format!("{} {}", count, kind))
+        .collect();
+    format!("{} skipped ({})", skipped, kinds.join(", "))
+}
+
+/// Human-readable label for a failure category.
+fn category_label(category: Option<&ErrorCategory>) -> String {
+    match category {
+        Some(ErrorCategory::Assertion) => "assertion".to_string(),
+        Some(ErrorCategory::Exception { type_name }) => type_name.clone(),
+        Some(ErrorCategory::Import) => "import error".to_string(),
+        Some(ErrorCategory::Syntax) => "syntax error".to_string(),
+        Some(ErrorCategory::Timeout) => "timeout".to_string(),
+        Some(ErrorCategory::Crash) => "crash".to_string(),
+        Some(ErrorCategory::Setup) => "setup fixture".to_string(),
+        Some(ErrorCategory::Teardown) => "teardown fixture".to_string(),
+        None => "uncategorized".to_string(),
+    }
+}
+
+/// Count failures per category, most frequent first (ties sorted by label).
+pub fn group_failures_by_category(failed_tests: &[TestResult]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for result in failed_tests {
+        let category = result.error.as_ref().and_then(|e| e.category.as_ref());
+        *counts.entry(category_label(category)).or_default() += 1;
+    }
+
+    let mut groups: Vec<_> = counts.into_iter().collect();
+    groups.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    groups
+}
+
+pub fn print_failure_groups(failed_tests: &[TestResult]) {
+    if failed_tests.is_empty() {
+        return;
+    }
+
+    println!("{}", "Failures by category:".red().bold());
+    for (label, count) in group_failures_by_category(failed_tests) {
+        println!("  {:>4}  {}", count, label);
+    }
+}
+
+/// List what `--dry-run` would have run, and what it would skip and why.
+pub fn print_dry_run(to_run: &[TestItem], skipped: &[TestResult]) {
+    println!("{}", format!("Would run ({}):", to_run.len()).bold());
+    for item in to_run {
+        println!("  {}", item.id());
+    }
+    println!("{}", format!("Would skip ({}):", skipped.len()).bold());
+    for result in skipped {
+        let reason = result.skip_reason.as_deref().unwrap_or("skipped");
+        println!(
+            "  {} {}",
+            result.item.id(),
+            format!("({})", reason).dimmed()
+        );
+    }
+}
+
+pub fn print_max_collect_note(max: usize, selected: usize) {
+    println!(
+        "{}",
+        format!(
+            "Capped by --max-collect: ran {} of {} selected tests",
+            max, selected
+        )
+        .yellow()
+    );
+}
+
+/// Explain why `--bail-on-worker-crash` aborted the run.
+pub fn print_worker_crash(crash: &WorkerCrashed) {
+    eprintln!();
+    eprintln!(
+        "{}",
+        format!("{} (--bail-on-worker-crash)", crash).red().bold()
+    );
+    if let Some(ref tail) = crash.stderr_tail {
+        eprintln!("{}", "Worker stderr:".dimmed());
+        for line in tail.lines() {
+            eprintln!("  {}", line);
+        }
+    }
+}
+
+/// A test that ran noticeably slower than on its previous run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DurationRegression {
+    pub id: String,
+    pub previous: std::time::Duration,
+    pub current: std::time::Duration,
+}
+
+/// List tests that got slower since the last run (`--compare-durations`).
+pub fn print_duration_regressions(regressions: &[DurationRegression], threshold_pct: f64) {
+    if regressions.is_empty() {
+        return;
+    }
+    println!();
+    println!(
+        "{}",
+        format!("Slower than last run (by more than {}%):", threshold_pct)
+            .yellow()
+            .bold()
+    );
+    for regression in regressions {
+        let previous = regression.previous.as_secs_f64();
+        let current = regression.current.as_secs_f64();
+        println!(
+            "  {} {:.3}s -> {:.3}s (+{:.0}%)",
+            regression.id,
+            previous,
+            current,
+            (current / previous - 1.0) * 100.0
+        );
+    }
+}
+
+/// Explain that `--deadline` cut the run short.
+pub fn print_deadline_note(not_run: usize, secs: f64) {
+    println!(
+        "{}",
+        format!(
+            "Deadline of {}s reached: {} tests not run (exit code 3)",
+            secs, not_run
+        )
+        .yellow()
+    );
+}
+
+/// Show what a `before_all`/`after_all` hook printed, on stderr so TAP output stays clean.
+pub fn print_hook_output(name: &str, command: &str, out: &std::process::Output) {
+    eprintln!("{}", format!("{}: {}", name, command).dimmed());
+    for stream in [&out.stdout, &out.stderr] {
+        let text = String::from_utf8_lossy(stream);
+        if !text.trim().is_empty() {
+            eprintln!("{}", text.trim_end());
+        }
+    }
+    if !out.status.success() {
+        eprintln!("{}", format!("{} failed ({})", name, out.status).red());
+    }
+}
+
+/// Say why the cache didn't let a test be skipped (`--explain`).
+pub fn print_rerun_reason(id: &str, reason: &str) {
+    println!(
+        "{}",
+        format!("{}: re-running because {}", id, reason).dimmed()
+    );
+}
+
+pub fn print_stepwise_resume(skipped: usize, id: &str) {
+    println!(
+        "{}",
+        format!("Stepwise: skipping {} tests before {}", skipped, id).dimmed()
+    );
+}
+
+pub fn print_stepwise_stop(id: &str) {
+    println!(
+        "{}",
+        format!(
+            "Stepwise: stopped at {} (rerun with --stepwise to resume)",
+            id
+        )
+        .yellow()
+    );
+}
+
+pub fn print_seed(seed: u64) {
+    println!("{}", format!("Using --seed {}", seed).dimmed());
+}
+
+pub fn print_seed_hint(seed: u64) {
+    println!(
+        "{}",
+        format!("Reproduce this order with: taut --seed {seed}").yellow()
+    );
+}
+
+/// Time spent in each phase of the run (`--profile`), on stderr so it never
+/// mixes into TAP output.
+pub fn print_profile(phases: &[(&str, std::time::Duration)], total: std::time::Duration) {
+    eprintln!();
+    eprintln!("{}", "Profile:".bold());
+    let total = ("total", total);
+    for (name, duration) in phases.iter().chain([&total]) {
+        eprintln!("  {:<16} {:>10.1}ms", name, duration.as_secs_f64() * 1000.0);
+    }
+}
+
+/// Emit results as a TAP v13 stream.
+pub fn print_tap(results: &TestResults) {
+    println!("TAP version 13");
+    println!("1..{}", results.results.len());
+
+    for (i, result) in results.results.iter().enumerate() {
+        let n = i + 1;
+        let id = result.item.id();
+        if result.skipped {
+            let reason = result.skip_reason.as_deref().unwrap_or("skipped");
+            println!("ok {} - {} # SKIP {}", n, id, reason);
+        } else if result.passed {
+            println!("ok {} - {}", n, id);
+        } else {
+            println!("not ok {} - {}", n, id);
+            if let Some(ref error) = result.error {
+                // JSON strings are valid double-quoted YAML scalars
+                let message = serde_json::to_string(&error.message).unwrap_or_default();
+                let location = format!("{}:{}", result.item.file.display(), result.item.line);
+                let location = serde_json::to_string(&location).unwrap_or_default();
+                println!("  ---");
+                println!("  message: {}", message);
+                if let Some(ref exception_type) = error.exception_type {
+                    println!("  exception: {}", exception_type);
+                }
+                println!("  severity: fail");
+                println!("  at: {}", location);
+                println!("  ...");
+            }
+        }
+    }
+}
+
+/// Escape a workflow command message (`%`, CR and LF must be encoded).
+fn escape_annotation_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a workflow command property value (also encodes `:` and `,`).
+fn escape_annotation_property(value: &str) -> String {
+    escape_annotation_data(value)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// Emit a GitHub Actions `::error` workflow command for each failing test.
+///
+/// Parametrized cases come from the decorators, so their annotation spans
+/// from the first decorator down to the `def` line.
+pub fn write_github_annotations(writer: &mut impl Write, results: &TestResults) -> io::Result<()> {
+    for result in &results.results {
+        if result.passed || result.skipped {
+            continue;
+        }
+        let message = result
+            .error
+            .as_ref()
+            .map(|e| e.message.as_str())
+            .unwrap_or("Test failed");
+        let lines = match result.item.decorator_line {
+            Some(start) if !result.item.params.is_empty() => {
+                format!("line={},endLine={}", start, result.item.line)
+            }
+            _ => format!("line={}", result.item.line),
+        };
+        writeln!(
+            writer,
+            "::error file={},{},title={}::{}",
+            escape_annotation_property(&result.item.file.display().to_string()),
+            lines,
+            escape_annotation_property(&result.item.id()),
+            escape_annotation_data(message)
+        )?;
+    }
+    Ok(())
+}
+
+/// Clear the terminal and move the cursor home (used between watch runs).
+///
+/// Does nothing unless `is_tty` is set, so escape codes never end up in
+/// redirected output.
+pub fn clear_screen(writer: &mut impl Write, is_tty: bool) -> io::Result<()> {
+    if !is_tty {
+        return Ok(());
+    }
+    writer.write_all(b"\x1b[2J\x1b[H")?;
+    writer.flush()
+}
+
+/// Format of the aggregate coverage report written by `--coverage-report`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageReportFormat {
+    /// LCOV tracefile (`SF:`/`DA:` records)
+    Lcov,
+    /// Single-page HTML summary
+    Html,
+}
+
+impl CoverageReportFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "lcov" => Some(Self::Lcov),
+            "html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+}
+
+/// Executed lines per source file, merged across tests.
+pub type MergedCoverage = BTreeMap<PathBuf, BTreeSet<usize>>;
+
+/// Which covered files count towards coverage reports and `--cov-fail-under`.
+///
+/// Mirrors the worker's filter so reports agree with what was recorded: a
+/// `--coverage-exclude` match wins, then a `--coverage-include` match, then the
+/// default of dropping installed packages and the standard library. Patterns
+/// with `*`, `?` or `[` are globs over the whole absolute path (`*` crosses
+/// `/`, as in Python's `fnmatch`); anything else matches as a substring.
+#[derive(Debug, Default)]
+pub struct CoverageFilter {
+    include: Vec<PathPattern>,
+    exclude: Vec<PathPattern>,
+}
+
+#[derive(Debug)]
+enum PathPattern {
+    Substring(String),
+    Glob(Regex),
+}
+
+impl PathPattern {
+    fn new(pattern: &str) -> Self {
+        if pattern.contains(['*', '?', '['])
+            && let Ok(regex) = fnmatch_to_regex(pattern)
+        {
+            return Self::Glob(regex);
+        }
+        Self::Substring(pattern.to_string())
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        match self {
+            Self::Substring(pattern) => path.contains(pattern.as_str()),
+            Self::Glob(regex) => regex.is_match(path),
+        }
+    }
+}
+
+/// Convert an `fnmatch` pattern to an anchored regex.
+fn fnmatch_to_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    let mut regex_str = String::with_capacity(pattern.len() * 2 + 2);
+    regex_str.push('^');
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex_str.push_str(".*"),
+            '?' => regex_str.push('.'),
+            '[' => {
+                let class: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                regex_str.push('[');
+                let class = match class.strip_prefix('!') {
+                    Some(negated) => {
+                        regex_str.push('^');
+                        negated
+                    }
+                    None => &class,
+                };
+                for c in class.chars() {
+                    // Keep ranges like `a-z`, but nothing else with a meaning in regex classes
+                    match c {
+                        '-' => regex_str.push('-'),
+                        _ => regex_str.push_str(&regex::escape(&c.to_string())),
+                    }
+                }
+                regex_str.push(']');
+            }
+            _ => regex_str.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex_str.push('$');
+    Regex::new(&regex_str)
+}
+
+impl CoverageFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Self {
+        Self {
+            include: include.iter().map(|p| PathPattern::new(p)).collect(),
+            exclude: exclude.iter().map(|p| PathPattern::new(p)).collect(),
+        }
+    }
+
+    /// Whether coverage of `path` is reported. Pseudo-files like `<string>`
+    /// (the `-c` runner script) and `<frozen importlib._bootstrap>` never are.
+    pub fn tracks(&self, path: &Path) -> bool {
+        let pseudo = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('<'));
+        let text = path.to_string_lossy();
+        if pseudo || self.exclude.iter().any(|p| p.matches(&text)) {
+            return false;
+        }
+        self.include.iter().any(|p| p.matches(&text))
+            || !["site-packages", "lib/python", "/usr/lib"]
+                .iter()
+                .any(|x| text.contains(x))
+    }
+}
+
+/// Merge every result's per-test coverage into one line-hit set per file.
+pub fn merge_coverage(results: &[TestResult], filter: &CoverageFilter) -> MergedCoverage {
+    let mut merged = MergedCoverage::new();
+    for coverage in results.iter().filter_map(|r| r.coverage.as_ref()) {
+        for (file, lines) in &coverage.files {
+            if filter.tracks(file) {
+                merged
+                    .entry(file.clone())
+                    .or_default()
+                    .extend(lines.iter().copied());
+            }
+        }
+    }
+    merged
+}
+
+/// Write merged coverage as an LCOV tracefile.
+///
+/// Only executed lines are known, so every `DA:` record has a hit count of 1.
+pub fn write_lcov(merged: &MergedCoverage, path: &Path) -> io::Result<()> {
+    let mut out = io::BufWriter::new(File::create(path)?);
+    writeln!(out, "TN:")?;
+    for (file, lines) in merged {
+        writeln!(out, "SF:{}", file.display())?;
+        for line in lines {
+            writeln!(out, "DA:{},1", line)?;
+        }
+        writeln!(out, "LH:{}", lines.len())?;
+        writeln!(out, "LF:{}", lines.len())?;
+        writeln!(out, "end_of_record")?;
+    }
+    out.flush()
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Write merged coverage as a single HTML page listing executed lines per file.
+pub fn write_coverage_html(merged: &MergedCoverage, path: &Path) -> io::Result<()> {
+    let mut out = io::BufWriter::new(File::create(path)?);
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(
+        out,
+        "<html><head><meta charset=\"utf-8\"><title>taut coverage</title></head>"
+    )?;
+    writeln!(out, "<body><h1>taut coverage</h1>")?;
+    writeln!(
+        out,
+        "<table><tr><th>File</th><th>Lines executed</th><th>Lines</th></tr>"
+    )?;
+    for (file, lines) in merged {
+        let listed: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape_html(&file.display().to_string()),
+            lines.len(),
+            listed.join(", ")
+        )?;
+    }
+    writeln!(out, "</table></body></html>")?;
+    out.flush()
+}
+
+/// Covered and executable line counts across the project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoverageTotals {
+    pub covered: usize,
+    pub total: usize,
+}
+
+impl CoverageTotals {
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.covered as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+/// Compare merged coverage against every indexed project file.
+///
+/// Executable lines are approximated by the lines spanned by each file's blocks,
+/// so files no test touched still count towards the total.
+pub fn coverage_totals(
+    merged: &MergedCoverage,
+    block_index: &HashMap<PathBuf, FileBlocks>,
+    filter: &CoverageFilter,
+) -> CoverageTotals {
+    let executed: HashMap<PathBuf, &BTreeSet<usize>> = merged
+        .iter()
+        .map(|(file, lines)| (file.canonicalize().unwrap_or(file.clone()), lines))
+        .collect();
+
+    let mut totals = CoverageTotals {
+        covered: 0,
+        total: 0,
+    };
+    for (file, blocks) in block_index {
+        if !filter.tracks(file) {
+            continue;
+        }
+        totals.total += blocks.line_to_block.len();
+        if let Some(lines) = executed.get(file) {
+            totals.covered += lines
+                .iter()
+                .filter(|line| blocks.line_to_block.contains_key(line))
+                .count();
+        }
+    }
+    totals
+}
+
+/// Print the achieved coverage and whether it meets `--cov-fail-under`.
+pub fn print_coverage_threshold(totals: CoverageTotals, threshold: f64) {
+    let summary = format!(
+        "Coverage: {:.2}% ({}/{} lines)",
+        totals.percent(),
+        totals.covered,
+        totals.total
+    );
+    if totals.percent() >= threshold {
+        println!("{}", summary.green());
+    } else {
+        println!("{}", summary.red());
+        println!(
+            "{}",
+            format!("Coverage is below --cov-fail-under {}%", threshold).red()
+        );
+    }
+}
+
+/// A node in the `taut list --tree` view; children keep discovery order.
+#[derive(Default)]
+struct TreeNode {
+    children: Vec<(String, TreeNode)>,
+}
+
+impl TreeNode {
+    fn child(&mut self, label: String) -> &mut TreeNode {
+        let index = match self.children.iter().position(|(l, _)| *l == label) {
+            Some(index) => index,
+            None => {
+                self.children.push((label, TreeNode::default()));
+                self.children.len() - 1
+            }
+        };
+        &mut self.children[index].1
+    }
+
+    fn render(&self, depth: usize, out: &mut String) {
+        for (label, node) in &self.children {
+            out.push_str(&"  ".repeat(depth));
+            out.push_str(label);
+            out.push('\n');
+            node.render(depth + 1, out);
+        }
+    }
+}
+
+/// Render tests as an indented directory → file → class → test tree.
+pub fn format_test_tree(tests: &[TestItem]) -> String {
+    let mut root = TreeNode::default();
+    for test in tests {
+        let mut node = &mut root;
+        if let Some(dir) = test.file.parent() {
+            for component in dir.components() {
+                match component {
+                    Component::CurDir => {}
+                    Component::RootDir => node = node.child("/".to_string()),
+                    other => node = node.child(format!("{}/", other.as_os_str().to_string_lossy())),
+                }
+            }
+        }
+        let file_name = test
+            .file
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| test.file.display().to_string());
+        node = node.child(file_name);
+        if let Some(ref class) = test.class {
+            for level in class.split('.') {
+                node = node.child(level.to_string());
+            }
+        }
+        node.child(test.name());
+    }
+
+    let mut out = String::new();
+    root.render(0, &mut out);
+    out
+}
+
+/// Tabulate the distinct marker shapes in use and how many tests carry each.
+pub fn format_marker_table(tests: &[TestItem]) -> String {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for test in tests {
+        // A test counts once per shape, however many times it repeats it
+        let shapes: BTreeSet<String> = test.markers.iter().map(|m| m.shape()).collect();
+        for shape in shapes {
+            *counts.entry(shape).or_default() += 1;
+        }
+    }
+
+    let width = counts.keys().map(|shape| shape.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for (shape, count) in &counts {
+        out.push_str(&format!("{:<width$}  {}\n", shape, count));
+    }
+    out
+}
+
 pub fn print_no_tests_found() {
     println!("{}", "taut".bold());
     println!("{}", "No tests found.".yellow());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clear_screen_writes_escape_sequence_on_tty() {
+        let mut out = Vec::new();
+        clear_screen(&mut out, true).unwrap();
+        assert_eq!(out, b"\x1b[2J\x1b[H");
+    }
+
+    #[test]
+    fn format_skipped_breaks_down_by_kind() {
+        assert_eq!(format_skipped(2, &[]), "2 skipped");
+        assert_eq!(
+            format_skipped(3, &[(SkipKind::Marker, 1), (SkipKind::Unchanged, 2)]),
+            "3 skipped (1 @skip, 2 unchanged)"
+        );
+    }
+
+    #[test]
+    fn clear_screen_is_silent_without_tty() {
+        let mut out = Vec::new();
+        clear_screen(&mut out, false).unwrap();
+        assert!(out.is_empty());
+    }
+
+    fn result_with_coverage(files: &[(&str, &[usize])]) -> TestResult {
+        let files = files
+            .iter()
+            .map(|(path, lines)| (PathBuf::from(path), lines.to_vec()))
+            .collect();
+        TestResult {
+            item: crate::discovery::TestItem {
+                file: PathBuf::from("test_app.py"),
+                function: "test_app".to_string(),
+                class: None,
+                line: 1,
+                decorator_line: None,
+                markers: vec![],
+                params: vec![],
+                kind: crate::discovery::TestKind::Function,
+                rootdir: None,
+            },
+            passed: true,
+            duration: std::time::Duration::ZERO,
+            error: None,
+            errored: false,
+            skipped: false,
+            skip_reason: None,
+            skip_kind: None,
+            coverage: Some(crate::runner::TestCoverage { files }),
+            stdout: None,
+            stderr: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn merge_coverage_unions_lines_and_drops_non_project_files() {
+        let results = [
+            result_with_coverage(&[
+                ("/proj/app.py", &[1, 3]),
+                ("/proj/<frozen importlib._bootstrap>", &[10]),
+            ]),
+            result_with_coverage(&[
+                ("/proj/app.py", &[2, 3]),
+                ("/venv/lib/python3.12/site-packages/six.py", &[5]),
+            ]),
+        ];
+
+        let merged = merge_coverage(&results, &CoverageFilter::default());
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[Path::new("/proj/app.py")], BTreeSet::from([1, 2, 3]));
+    }
+
+    #[test]
+    fn coverage_filter_include_overrides_default_and_exclude_wins() {
+        let filter = CoverageFilter::new(
+            &["site-packages/mylib".to_string()],
+            &["*/migrations/*".to_string()],
+        );
+        assert!(filter.tracks(Path::new("/proj/app.py")));
+        assert!(filter.tracks(Path::new("/venv/site-packages/mylib/core.py")));
+        assert!(!filter.tracks(Path::new("/venv/site-packages/six.py")));
+        assert!(!filter.tracks(Path::new("/proj/app/migrations/0001.py")));
+        assert!(!filter.tracks(Path::new("/proj/<string>")));
+    }
+
+    fn result(passed: bool) -> TestResult {
+        TestResult {
+            passed,
+            ..result_with_coverage(&[])
+        }
+    }
+
+    #[test]
+    fn progress_rewrites_counter_line() {
+        colored::control::set_override(false);
+        let progress = Progress::new(3);
+        let mut out = Vec::new();
+        for passed in [true, false, true] {
+            progress.record(&mut out, &result(passed)).unwrap();
+        }
+
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.matches('\r').count(), 3);
+        assert_eq!(out.rsplit('\r').next(), Some("[ 3/3 ] 1 failed"));
+    }
+
+    const TRACEBACK: &str = "Traceback (most recent call last):
+  File \"runner.py\", line 10, in run_test
+    test_func()
+  File \"test_app.py\", line 4, in test_app
+    helper()
+  File \"test_app.py\", line 2, in helper
+    assert 1 == 2
+AssertionError: assert 1 == 2";
+
+    #[test]
+    fn short_traceback_keeps_only_the_last_frame() {
+        assert_eq!(
+            TracebackStyle::Short.format(TRACEBACK),
+            vec![
+                "  File \"test_app.py\", line 2, in helper",
+                "    assert 1 == 2",
+                "AssertionError: assert 1 == 2",
+            ]
+        );
+        assert_eq!(TracebackStyle::Long.format(TRACEBACK).len(), 8);
+        assert!(TracebackStyle::No.format(TRACEBACK).is_empty());
+    }
+}
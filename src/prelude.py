@@ -0,0 +1,506 @@
+# Helpers shared by the worker (src/worker.py) and the runner scripts in
+# src/runner.rs; the Rust side prepends this file to each of them
+import sys
+import traceback
+import importlib.util
+import ast
+import operator
+import inspect
+import asyncio
+import io
+import doctest
+import fnmatch
+import unittest
+import os
+import signal
+
+
+# Event loop reused by every async test and fixture with --async-mode shared
+_shared_loop = None
+
+
+def _run_maybe_async(callable_obj, kwargs=None):
+    result = callable_obj(**(kwargs or {}))
+    if inspect.isawaitable(result):
+        if _shared_loop is None:
+            return asyncio.run(result)
+        return _shared_loop.run_until_complete(result)
+    return result
+
+
+def _start_shared_loop():
+    global _shared_loop
+    _shared_loop = asyncio.new_event_loop()
+    asyncio.set_event_loop(_shared_loop)
+
+
+def _close_shared_loop():
+    global _shared_loop
+    if _shared_loop is not None:
+        _shared_loop.run_until_complete(_shared_loop.shutdown_asyncgens())
+        _shared_loop.close()
+        asyncio.set_event_loop(None)
+        _shared_loop = None
+
+
+def _run_doctest(module, test_name):
+    # Same as doctest.run_docstring_examples, but fails with the report
+    qualname = test_name[len("doctest:"):]
+    obj = module
+    if qualname != "<module>":
+        for attr in qualname.split("."):
+            obj = getattr(obj, attr)
+    finder = doctest.DocTestFinder(recurse=False)
+    runner = doctest.DocTestRunner(verbose=False)
+    report = io.StringIO()
+    for test in finder.find(obj, qualname, module=module, globs=dict(vars(module))):
+        runner.run(test, out=report.write)
+    if runner.failures:
+        raise AssertionError(report.getvalue().strip("*\n"))
+
+
+class FixtureError(Exception):
+    """A setup/teardown fixture failed; carries the fixture name and phase."""
+
+    def __init__(self, name, phase, message, timed_out=False):
+        super().__init__(message)
+        self.name = name
+        self.phase = phase
+        self.timed_out = timed_out
+
+
+class _FixtureTimeout(BaseException):
+    pass
+
+
+def _run_with_timeout(callable_obj, timeout):
+    def on_alarm(signum, frame):
+        raise _FixtureTimeout()
+
+    previous = signal.signal(signal.SIGALRM, on_alarm)
+    signal.setitimer(signal.ITIMER_REAL, timeout)
+    try:
+        _run_maybe_async(callable_obj)
+    finally:
+        signal.setitimer(signal.ITIMER_REAL, 0)
+        signal.signal(signal.SIGALRM, previous)
+
+
+def _accepts_argument(func):
+    try:
+        return len(inspect.signature(func).parameters) > 0
+    except (TypeError, ValueError):
+        return False
+
+
+def _run_fixture(owner, name, phase, timeout=None, test_func=None):
+    fixture = getattr(owner, name, None)
+    if fixture is None:
+        return
+    if test_func is not None and _accepts_argument(fixture):
+        # xunit-style setup_function(func)/teardown_function(func)
+        hook = fixture
+        fixture = lambda: hook(test_func)
+    try:
+        if timeout:
+            _run_with_timeout(fixture, timeout)
+        else:
+            _run_maybe_async(fixture)
+    except _FixtureTimeout:
+        raise FixtureError(name, phase, f"Fixture '{name}' timed out after {timeout}s during {phase}", timed_out=True) from None
+    except Exception as e:
+        raise FixtureError(name, phase, f"Fixture '{name}' failed during {phase}: {type(e).__name__}: {e}") from e
+
+
+def _run_teardowns(owner, names, error=None, test_func=None):
+    """Run teardown fixtures in order. If the test already failed with `error`, a
+    teardown failure is attached to it as a note rather than replacing it."""
+    for name in names:
+        try:
+            _run_fixture(owner, name, "teardown", test_func=test_func)
+        except FixtureError as e:
+            if error is None:
+                raise
+            # Same attribute as BaseException.add_note(), which needs Python 3.11
+            error.__notes__ = [*getattr(error, "__notes__", ()), str(e)]
+
+
+def _with_notes(message, e):
+    notes = getattr(e, "__notes__", None)
+    return "\n".join([message, *notes]) if notes else message
+
+
+class _TestSkipped(unittest.SkipTest):
+    """The test skipped itself while running (e.g. taut.skip("reason") or unittest's skipTest())."""
+
+
+def _run_test_body(test_func, kwargs=None):
+    """Run the test itself. Meanwhile taut.skip("reason") skips it on the spot
+    instead of acting as the @skip decorator."""
+    taut = sys.modules.get("taut")
+    if taut is None:
+        return _run_maybe_async(test_func, kwargs)
+    taut._running_test = True
+    try:
+        return _run_maybe_async(test_func, kwargs)
+    finally:
+        taut._running_test = False
+
+
+class _UnittestOutcome(unittest.TestResult):
+    """Keeps the first failure and any skip reason from running a single TestCase."""
+
+    def __init__(self):
+        super().__init__()
+        self.exc_info = None
+        self.skip_reason = None
+
+    def addError(self, test, err):
+        if self.exc_info is None:
+            self.exc_info = err
+
+    addFailure = addError
+
+    def addSubTest(self, test, subtest, err):
+        if err is not None and self.exc_info is None:
+            self.exc_info = err
+
+    def addSkip(self, test, reason):
+        self.skip_reason = reason
+
+    def addUnexpectedSuccess(self, test):
+        self.exc_info = (AssertionError, AssertionError("Unexpected success: test is marked @expectedFailure"), None)
+
+
+_CLASS_TEARDOWNS = ("tearDownClass", "doClassCleanups")
+
+
+def _run_unittest(cls, test_name, setup_timeout, result):
+    """Run one method of a unittest.TestCase through unittest itself, so skipTest(),
+    @expectedFailure, subTest() and addCleanup() behave as they do under unittest."""
+    if getattr(cls, "__unittest_skip__", False):
+        raise _TestSkipped(getattr(cls, "__unittest_skip_why__", ""))
+    try:
+        _run_fixture(cls, "setUpClass", "setup", setup_timeout)
+    except FixtureError as e:
+        if isinstance(e.__cause__, unittest.SkipTest):
+            raise _TestSkipped(str(e.__cause__)) from None
+        raise
+    outcome = _UnittestOutcome()
+    try:
+        result["errored"] = False
+        _run_test_body(lambda: cls(test_name).run(outcome))
+    except BaseException as e:
+        _run_teardowns(cls, _CLASS_TEARDOWNS, e)
+        raise
+    error = None
+    if outcome.exc_info is not None:
+        _, error, tb = outcome.exc_info
+        error = error.with_traceback(tb)
+    _run_teardowns(cls, _CLASS_TEARDOWNS, error)
+    if outcome.skip_reason is not None:
+        raise _TestSkipped(outcome.skip_reason)
+    if error is not None:
+        raise error
+
+
+def _conftest_fixtures(test_file, rootdir):
+    """@fixture functions from the conftest.py files between rootdir and the test's
+    directory, by name; ones closer to the test win."""
+    directory = os.path.dirname(os.path.abspath(test_file))
+    directories = [directory]
+    root = os.path.abspath(rootdir) if rootdir else None
+    while root and directory != root and directory.startswith(root + os.sep):
+        directory = os.path.dirname(directory)
+        directories.append(directory)
+    fixtures = {}
+    for directory in reversed(directories):
+        path = os.path.join(directory, "conftest.py")
+        if not os.path.isfile(path):
+            continue
+        name = f"taut_conftest_{abs(hash(path)):x}"
+        spec = importlib.util.spec_from_file_location(name, path)
+        module = importlib.util.module_from_spec(spec)
+        sys.modules[name] = module
+        spec.loader.exec_module(module)
+        for attr, value in vars(module).items():
+            if callable(value) and getattr(value, "_taut_fixture", False):
+                fixtures[attr] = value
+    return fixtures
+
+
+def _with_fixtures(test_func, params, test_file, rootdir):
+    """The test's keyword arguments: its @parametrize values, plus a fresh value from
+    each fixture named by one of its other parameters."""
+    kwargs = dict(params or {})
+    try:
+        wanted = [name for name in inspect.signature(test_func).parameters if name not in kwargs]
+    except (TypeError, ValueError):
+        return kwargs
+    if not wanted:
+        return kwargs
+    fixtures = _conftest_fixtures(test_file, rootdir)
+    values = {}
+
+    def resolve(name, requested_by):
+        if name in values:
+            return values[name]
+        if name in requested_by:
+            raise FixtureError(name, "setup", f"Fixture '{name}' depends on itself")
+        fixture = fixtures[name]
+        args = {arg: resolve(arg, requested_by + (name,)) for arg in inspect.signature(fixture).parameters if arg in fixtures}
+        try:
+            values[name] = _run_maybe_async(fixture, args)
+        except Exception as e:
+            raise FixtureError(name, "setup", f"Fixture '{name}' failed during setup: {type(e).__name__}: {e}") from e
+        return values[name]
+
+    for name in wanted:
+        if name in fixtures:
+            kwargs[name] = resolve(name, ())
+    return kwargs
+
+
+def _format_warning(w):
+    return f"{w.filename}:{w.lineno}: {w.category.__name__}: {w.message}"
+
+
+def _error_category(e):
+    if isinstance(e, FixtureError):
+        return {"kind": "timeout"} if e.timed_out else {"kind": e.phase}
+    if isinstance(e, AssertionError):
+        return {"kind": "assertion"}
+    if isinstance(e, SyntaxError):
+        return {"kind": "syntax"}
+    if isinstance(e, ImportError):
+        return {"kind": "import"}
+    return {"kind": "exception", "type_name": type(e).__name__}
+
+
+def _exception_type(e):
+    if isinstance(e, FixtureError):
+        # Report what the fixture raised, not the wrapper (timeouts raised nothing)
+        cause = e.__cause__
+        return type(cause).__name__ if cause is not None else None
+    return type(e).__name__
+
+
+_COMPARE_OPS = {
+    ast.Eq: "==",
+    ast.NotEq: "!=",
+    ast.Lt: "<",
+    ast.LtE: "<=",
+    ast.Gt: ">",
+    ast.GtE: ">=",
+    ast.Is: "is",
+    ast.IsNot: "is not",
+    ast.In: "in",
+    ast.NotIn: "not in",
+}
+
+
+_OP_FUNCS = {
+    "==": operator.eq,
+    "!=": operator.ne,
+    "<": operator.lt,
+    "<=": operator.le,
+    ">": operator.gt,
+    ">=": operator.ge,
+    "is": operator.is_,
+    "is not": operator.is_not,
+    "in": lambda a, b: a in b,
+    "not in": lambda a, b: a not in b,
+}
+
+
+def _taut_assert_compare(left, op, right, msg=None):
+    if _OP_FUNCS[op](left, right):
+        return
+    detail = f"assert {left!r} {op} {right!r}"
+    if msg is not None:
+        raise AssertionError(f"{msg()}\n{detail}")
+    raise AssertionError(detail)
+
+
+def _assertion_traceback(e):
+    """Format a failed assertion's traceback, ending at the assert itself rather
+    than inside _taut_assert_compare."""
+    tb = e.__traceback__
+    while tb is not None and tb.tb_next is not None:
+        if tb.tb_next.tb_frame.f_code is _taut_assert_compare.__code__:
+            tb.tb_next = None
+        else:
+            tb = tb.tb_next
+    return traceback.format_exc()
+
+
+class _AssertRewriter(ast.NodeTransformer):
+    """Rewrite `assert a <op> b` so failures report both operand values."""
+
+    def visit_Assert(self, node):
+        test = node.test
+        if not (isinstance(test, ast.Compare) and len(test.ops) == 1 and type(test.ops[0]) in _COMPARE_OPS):
+            return node
+        args = [test.left, ast.Constant(_COMPARE_OPS[type(test.ops[0])]), test.comparators[0]]
+        if node.msg is not None:
+            # Defer evaluating the message until the assertion fails, like a plain assert
+            no_args = ast.arguments(posonlyargs=[], args=[], kwonlyargs=[], kw_defaults=[], defaults=[])
+            args.append(ast.Lambda(args=no_args, body=node.msg))
+        call = ast.Call(func=ast.Name("_taut_assert_compare", ast.Load()), args=args, keywords=[])
+        return ast.copy_location(ast.Expr(call), node)
+
+
+def _find_package(test_file):
+    """Dotted package containing test_file (from the __init__.py files above it)
+    and the directory it's importable from, or (None, None) outside a package."""
+    directory = os.path.dirname(os.path.abspath(test_file))
+    parts = []
+    while os.path.isfile(os.path.join(directory, "__init__.py")):
+        parts.append(os.path.basename(directory))
+        directory = os.path.dirname(directory)
+    if not parts:
+        return None, None
+    return ".".join(reversed(parts)), directory
+
+
+def _add_import_paths(test_file, import_mode, rootdir):
+    """Put the directories the test imports from on sys.path (see --import-mode)."""
+    if import_mode == "importlib":
+        return
+    test_dir = os.path.dirname(os.path.abspath(test_file))
+    if test_dir not in sys.path:
+        sys.path.insert(0, test_dir)
+    if import_mode == "rootdir" and rootdir and rootdir not in sys.path:
+        sys.path.insert(0, rootdir)
+
+
+# Assert-rewritten code of test files, keyed by path and mtime. A worker running
+# several tests from one file (see --file-affinity) compiles it once; each test
+# still executes it in a fresh module.
+_rewritten_code = {}
+
+
+def _rewritten_code_for(test_file):
+    key = (test_file, os.stat(test_file).st_mtime_ns)
+    code = _rewritten_code.get(key)
+    if code is None:
+        with open(test_file, "rb") as f:
+            tree = ast.parse(f.read(), filename=test_file)
+        tree = ast.fix_missing_locations(_AssertRewriter().visit(tree))
+        code = compile(tree, test_file, "exec")
+        _rewritten_code[key] = code
+    return code
+
+
+def _load_module(mod_name, test_file, rewrite_asserts, import_mode="prepend"):
+    # Inside a package, import under the real dotted name so relative imports resolve
+    package, package_root = _find_package(test_file)
+    if package is not None and import_mode != "importlib":
+        if package_root not in sys.path:
+            sys.path.insert(0, package_root)
+        mod_name = package + "." + os.path.splitext(os.path.basename(test_file))[0]
+    spec = importlib.util.spec_from_file_location(mod_name, test_file)
+    module = importlib.util.module_from_spec(spec)
+    sys.modules[mod_name] = module
+    try:
+        if not rewrite_asserts:
+            spec.loader.exec_module(module)
+            return module
+        module.__dict__["_taut_assert_compare"] = _taut_assert_compare
+        exec(_rewritten_code_for(test_file), module.__dict__)
+        return module
+    except ImportError as e:
+        if package is None and "no known parent package" in str(e):
+            raise ImportError(f"{e}: add an __init__.py next to {os.path.basename(test_file)} to use relative imports") from e
+        raise
+
+
+_DEFAULT_COVERAGE_EXCLUDES = ("site-packages", "lib/python", "/usr/lib")
+
+
+def _path_matches(path, pattern):
+    # Globs match the whole absolute path; anything else matches as a substring
+    if any(c in pattern for c in "*?["):
+        return fnmatch.fnmatch(path, pattern)
+    return pattern in path
+
+
+def _coverage_filter(include=(), exclude=()):
+    """Whether to record coverage for a file: --coverage-exclude wins, then
+    --coverage-include, then the built-in exclusion of installed packages."""
+    decided = {}
+
+    def should_track(filename):
+        tracked = decided.get(filename)
+        if tracked is None:
+            path = os.path.abspath(filename) if filename and not filename.startswith("<") else None
+            if path is None or any(_path_matches(path, p) for p in exclude):
+                tracked = False
+            elif any(_path_matches(path, p) for p in include):
+                tracked = True
+            else:
+                tracked = not any(x in path for x in _DEFAULT_COVERAGE_EXCLUDES)
+            decided[filename] = tracked
+        return tracked
+
+    return should_track
+
+
+def _install_taut_shim():
+    # Let tests `import taut` even when the package isn't installed where they run
+    try:
+        import taut  # noqa: F401
+        return
+    except ImportError:
+        pass
+
+    import types
+
+    shim = types.ModuleType("taut")
+
+    def marker(arg=None, *args, **kwargs):
+        # Markers are read statically, so at runtime they leave the test unchanged
+        if callable(arg):
+            return arg
+        return lambda func: func
+
+    class raises:
+        def __init__(self, expected_exception):
+            self.expected_exception = expected_exception
+            self.value = None
+
+        def __enter__(self):
+            return self
+
+        def __exit__(self, exc_type, exc, tb):
+            if exc_type is None:
+                name = getattr(self.expected_exception, "__name__", str(self.expected_exception))
+                raise AssertionError(f"DID NOT RAISE {name}")
+            if not issubclass(exc_type, self.expected_exception):
+                return False
+            self.value = exc
+            return True
+
+    def fail(msg=""):
+        raise AssertionError(msg or "failed")
+
+    def fixture(func=None):
+        def decorator(func):
+            func._taut_fixture = True
+            return func
+        return decorator(func) if func is not None else decorator
+
+    def skip(arg=None, *args, reason=None, **kwargs):
+        # Called from a running test, skip("reason") skips it there and then
+        if shim._running_test and not callable(arg):
+            raise _TestSkipped(arg or reason or "")
+        return marker(arg)
+
+    for name in ("mark", "parallel", "parametrize"):
+        setattr(shim, name, marker)
+    shim._running_test = False
+    shim.skip = skip
+    shim.raises = raises
+    shim.fail = fail
+    shim.fixture = fixture
+    sys.modules["taut"] = shim
@@ -2,15 +2,98 @@ use crate::discovery::TestItem;
 use anyhow::Result;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, BufReader, Read, Write};
 use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::{Mutex, MutexGuard};
 use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TestError {
     pub message: String,
     pub traceback: Option<String>,
+    /// Set when the failure came from a fixture rather than the test body.
+    #[serde(default)]
+    pub fixture: Option<FixtureFailure>,
+    #[serde(default)]
+    pub category: Option<ErrorCategory>,
+    /// Name of the Python exception raised (e.g., "ValueError"), when there was one.
+    #[serde(default)]
+    pub exception_type: Option<String>,
+}
+
+/// Broad classification of why a test failed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// `assert` failed
+    Assertion,
+    /// Any other uncaught exception
+    Exception { type_name: String },
+    /// The test module (or something it imports) failed to import
+    Import,
+    /// The test module has a syntax error
+    Syntax,
+    /// A time limit was exceeded
+    Timeout,
+    /// The Python process died or produced unreadable output
+    Crash,
+    /// A `setUp` fixture failed
+    Setup,
+    /// A `tearDown` fixture failed
+    Teardown,
+}
+
+/// Identifies which fixture failed and in which phase.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FixtureFailure {
+    /// Fixture name (e.g., "setUp")
+    pub name: String,
+    pub phase: FixturePhase,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FixturePhase {
+    Setup,
+    Teardown,
+}
+
+impl std::fmt::Display for FixturePhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FixturePhase::Setup => write!(f, "setup"),
+            FixturePhase::Teardown => write!(f, "teardown"),
+        }
+    }
+}
+
+/// Why a test was skipped instead of run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SkipKind {
+    /// Marked with `@skip`
+    Marker,
+    /// Nothing it depends on changed since it last passed
+    Unchanged,
+    /// Excluded with `--deselect`
+    Deselected,
+    /// Not started, or killed, because `--deadline` passed
+    Deadline,
+    /// Skipped itself while running, e.g. with unittest's `self.skipTest()`
+    Runtime,
+}
+
+impl std::fmt::Display for SkipKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipKind::Marker => write!(f, "@skip"),
+            SkipKind::Unchanged => write!(f, "unchanged"),
+            SkipKind::Deselected => write!(f, "deselected"),
+            SkipKind::Deadline => write!(f, "deadline"),
+            SkipKind::Runtime => write!(f, "runtime"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -24,11 +107,17 @@ pub struct TestResult {
     pub passed: bool,
     pub duration: Duration,
     pub error: Option<TestError>,
+    /// The test couldn't run at all (its module failed to import, the function
+    /// doesn't exist, a setup fixture failed), as opposed to failing while it ran.
+    pub errored: bool,
     pub skipped: bool,
     pub skip_reason: Option<String>,
+    pub skip_kind: Option<SkipKind>,
     pub coverage: Option<TestCoverage>,
     pub stdout: Option<String>,
     pub stderr: Option<String>,
+    /// Python warnings the test emitted, as `file:line: Category: message`
+    pub warnings: Vec<String>,
 }
 
 pub struct TestResults {
@@ -48,16 +137,31 @@ impl TestResults {
             .count()
     }
 
+    /// Tests that ran and failed (see [`errored_count`](Self::errored_count)).
     pub fn failed_count(&self) -> usize {
         self.results
             .iter()
-            .filter(|r| !r.passed && !r.skipped)
+            .filter(|r| !r.passed && !r.skipped && !r.errored)
             .count()
     }
 
+    /// Tests that couldn't be run.
+    pub fn errored_count(&self) -> usize {
+        self.results.iter().filter(|r| r.errored).count()
+    }
+
     pub fn skipped_count(&self) -> usize {
         self.results.iter().filter(|r| r.skipped).count()
     }
+
+    /// Skipped tests counted per kind, in `SkipKind` order (kinds with none are left out).
+    pub fn skipped_by_kind(&self) -> Vec<(SkipKind, usize)> {
+        let mut counts = std::collections::BTreeMap::new();
+        for kind in self.results.iter().filter_map(|r| r.skip_kind) {
+            *counts.entry(kind).or_insert(0) += 1;
+        }
+        counts.into_iter().collect()
+    }
 }
 
 /// Basic runner script without coverage.
@@ -66,118 +170,197 @@ impl TestResults {
 /// - sync tests
 /// - async tests (`async def test_*`)
 /// - class-based tests with optional `setUp`/`tearDown`
-const RUNNER_SCRIPT: &str = r#"
+const RUNNER_SCRIPT: &str = concat!(
+    include_str!("prelude.py"),
+    r#"
 import sys
 import json
 import traceback
-import importlib.util
-import inspect
-import asyncio
 import io
 import contextlib
-import time
+import warnings
+import unittest
+
 
+class _StreamForwarder(io.TextIOBase):
+    """Forwards test output to taut as it is written (used when capture is disabled)."""
 
+    def __init__(self, stream):
+        self._stream = stream
 
-def _run_maybe_async(callable_obj):
-    result = callable_obj()
-    if inspect.isawaitable(result):
-        asyncio.run(result)
+    def writable(self):
+        return True
 
+    def write(self, data):
+        if data:
+            line = json.dumps({"stream": self._stream, "data": data})
+            sys.__stdout__.write(line + "\n")
+            sys.__stdout__.flush()
+        return len(data)
 
-def run_test(test_file, test_name, class_name=None):
+    def getvalue(self):
+        return None
+
+
+def run_test(test_file, test_name, class_name=None, setup_timeout=None, rewrite_asserts=True, capture=True, params=None, is_doctest=False, import_mode="prepend", rootdir=None, warnings_mode="default"):
+    caught = []
     result = {"passed": False, "error": None, "stdout": "", "stderr": ""}
 
     try:
-        import os
-        test_dir = os.path.dirname(os.path.abspath(test_file))
-        if test_dir not in sys.path:
-            sys.path.insert(0, test_dir)
-
-        out_buf = io.StringIO()
-        err_buf = io.StringIO()
-
-        with contextlib.redirect_stdout(out_buf), contextlib.redirect_stderr(err_buf):
-            spec = importlib.util.spec_from_file_location("test_module", test_file)
-            module = importlib.util.module_from_spec(spec)
-            sys.modules["test_module"] = module
-            spec.loader.exec_module(module)
-
-            if class_name:
-                cls = getattr(module, class_name)
-                instance = cls()
-                try:
-                    if hasattr(instance, "setUp"):
-                        instance.setUp()
-                    test_func = getattr(instance, test_name)
-                    _run_maybe_async(test_func)
-                    result["passed"] = True
-                finally:
-                    # Always run tearDown, even if test fails
-                    if hasattr(instance, "tearDown"):
-                        instance.tearDown()
+        # Until the test body starts, an exception means the test couldn't run
+        result["errored"] = True
+        _add_import_paths(test_file, import_mode, rootdir)
+
+        if capture:
+            out_buf = io.StringIO()
+            err_buf = io.StringIO()
+        else:
+            out_buf = _StreamForwarder("stdout")
+            err_buf = _StreamForwarder("stderr")
+
+        with contextlib.redirect_stdout(out_buf), contextlib.redirect_stderr(err_buf), warnings.catch_warnings(record=True) as caught:
+            # "error" turns a warning into an exception that fails the test
+            warnings.simplefilter(warnings_mode)
+            module = _load_module("test_module", test_file, rewrite_asserts, import_mode)
+
+            if is_doctest:
+                result["errored"] = False
+                _run_doctest(module, test_name)
+                result["passed"] = True
+            elif class_name:
+                # Nested classes are dotted (Outer.Inner)
+                cls = module
+                for attr in class_name.split("."):
+                    cls = getattr(cls, attr)
+                if isinstance(cls, type) and issubclass(cls, unittest.TestCase):
+                    _run_unittest(cls, test_name, setup_timeout, result)
+                else:
+                    instance = cls()
+                    try:
+                        _run_fixture(instance, "setUp", "setup", setup_timeout)
+                        test_func = getattr(instance, test_name)
+                        kwargs = _with_fixtures(test_func, params, test_file, rootdir)
+                        result["errored"] = False
+                        _run_test_body(test_func, kwargs)
+                    except BaseException as e:
+                        # Always run tearDown, even if the test fails, but report the test's failure
+                        _run_teardowns(instance, ["tearDown"], e)
+                        raise
+                    _run_teardowns(instance, ["tearDown"])
+                result["passed"] = True
             else:
                 test_func = getattr(module, test_name)
-                _run_maybe_async(test_func)
+                try:
+                    _run_fixture(module, "setup_function", "setup", setup_timeout, test_func)
+                    kwargs = _with_fixtures(test_func, params, test_file, rootdir)
+                    result["errored"] = False
+                    _run_test_body(test_func, kwargs)
+                except BaseException as e:
+                    # Always run teardown_function, even if the test fails, but report the test's failure
+                    _run_teardowns(module, ["teardown_function"], e, test_func)
+                    raise
+                _run_teardowns(module, ["teardown_function"], test_func=test_func)
                 result["passed"] = True
 
         result["stdout"] = out_buf.getvalue()
         result["stderr"] = err_buf.getvalue()
-    except AssertionError as e:
+    except unittest.SkipTest as e:
+        result["stdout"] = out_buf.getvalue() if 'out_buf' in dir() else ""
+        result["stderr"] = err_buf.getvalue() if 'err_buf' in dir() else ""
+        result["errored"] = False
+        result["skipped"] = True
+        result["skip_reason"] = str(e)
+    except FixtureError as e:
         result["stdout"] = out_buf.getvalue() if 'out_buf' in dir() else ""
         result["stderr"] = err_buf.getvalue() if 'err_buf' in dir() else ""
         result["error"] = {
-            "message": str(e) or "Assertion failed",
+            "message": _with_notes(str(e), e),
             "traceback": traceback.format_exc(),
+            "fixture": {"name": e.name, "phase": e.phase},
+            "category": _error_category(e),
+            "exception_type": _exception_type(e),
+        }
+    except AssertionError as e:
+        result["stdout"] = out_buf.getvalue() if 'out_buf' in dir() else ""
+        result["stderr"] = err_buf.getvalue() if 'err_buf' in dir() else ""
+        result["error"] = {
+            "message": _with_notes(str(e) or "Assertion failed", e),
+            "traceback": _assertion_traceback(e),
+            "category": _error_category(e),
+            "exception_type": _exception_type(e),
         }
     except Exception as e:
         result["stdout"] = out_buf.getvalue() if 'out_buf' in dir() else ""
         result["stderr"] = err_buf.getvalue() if 'err_buf' in dir() else ""
         result["error"] = {
-            "message": f"{type(e).__name__}: {e}",
+            "message": _with_notes(f"{type(e).__name__}: {e}", e),
             "traceback": traceback.format_exc(),
+            "category": _error_category(e),
+            "exception_type": _exception_type(e),
         }
 
+    result["warnings"] = [_format_warning(w) for w in caught]
     return result
 
 
 if __name__ == "__main__":
+    _install_taut_shim()
     info = json.loads(sys.argv[1])
-    result = run_test(info["file"], info["function"], info.get("class"))
+    if info.get("async_mode") == "shared":
+        _start_shared_loop()
+    result = run_test(info["file"], info["function"], info.get("class"), info.get("setup_timeout"), info.get("assert_rewrite", True), info.get("capture", True), info.get("params"), info.get("doctest", False), info.get("import_mode", "prepend"), info.get("rootdir"), info.get("warnings", "default"))
+    _close_shared_loop()
     print(json.dumps(result))
-"#;
+"#
+);
 
 /// Runner script with sys.settrace coverage collection.
 ///
 /// Note: this will be replaced with `sys.monitoring` (Python 3.12+) to reduce overhead,
 /// but for now this keeps existing behavior while adding async support.
-const RUNNER_SCRIPT_WITH_COVERAGE: &str = r#"
+const RUNNER_SCRIPT_WITH_COVERAGE: &str = concat!(
+    include_str!("prelude.py"),
+    r#"
 import sys
 import json
 import traceback
-import importlib.util
 import os
-import inspect
-import asyncio
 import io
 import contextlib
+import warnings
+import unittest
+
+
+class _StreamForwarder(io.TextIOBase):
+    """Forwards test output to taut as it is written (used when capture is disabled)."""
 
+    def __init__(self, stream):
+        self._stream = stream
 
-def _run_maybe_async(callable_obj):
-    result = callable_obj()
-    if inspect.isawaitable(result):
-        asyncio.run(result)
+    def writable(self):
+        return True
 
+    def write(self, data):
+        if data:
+            line = json.dumps({"stream": self._stream, "data": data})
+            sys.__stdout__.write(line + "\n")
+            sys.__stdout__.flush()
+        return len(data)
 
-def run_test(test_file, test_name, class_name=None):
+    def getvalue(self):
+        return None
+
+
+def run_test(test_file, test_name, class_name=None, setup_timeout=None, rewrite_asserts=True, capture=True, params=None, is_doctest=False, import_mode="prepend", rootdir=None, warnings_mode="default", coverage_include=(), coverage_exclude=()):
+    caught = []
     result = {"passed": False, "error": None, "coverage": {}, "stdout": "", "stderr": ""}
     executed_lines = {}
+    should_track = _coverage_filter(coverage_include, coverage_exclude)
 
     def trace_function(frame, event, arg):
         if event == 'line':
             filename = frame.f_code.co_filename
-            # Only track project files (skip stdlib, site-packages)
-            if not any(x in filename for x in ['site-packages', 'lib/python', '/usr/lib']):
+            if should_track(filename):
                 # Normalize to absolute path
                 abs_path = os.path.abspath(filename)
                 if abs_path not in executed_lines:
@@ -186,67 +369,298 @@ def run_test(test_file, test_name, class_name=None):
         return trace_function
 
     try:
-        test_dir = os.path.dirname(os.path.abspath(test_file))
-        if test_dir not in sys.path:
-            sys.path.insert(0, test_dir)
+        # Until the test body starts, an exception means the test couldn't run
+        result["errored"] = True
+        _add_import_paths(test_file, import_mode, rootdir)
 
         sys.settrace(trace_function)
 
-        out_buf = io.StringIO()
-        err_buf = io.StringIO()
-
-        with contextlib.redirect_stdout(out_buf), contextlib.redirect_stderr(err_buf):
-            spec = importlib.util.spec_from_file_location("test_module", test_file)
-            module = importlib.util.module_from_spec(spec)
-            sys.modules["test_module"] = module
-            spec.loader.exec_module(module)
-
-            if class_name:
-                cls = getattr(module, class_name)
-                instance = cls()
-                if hasattr(instance, "setUp"):
-                    instance.setUp()
-                test_func = getattr(instance, test_name)
-                _run_maybe_async(test_func)
-                if hasattr(instance, "tearDown"):
-                    instance.tearDown()
+        if capture:
+            out_buf = io.StringIO()
+            err_buf = io.StringIO()
+        else:
+            out_buf = _StreamForwarder("stdout")
+            err_buf = _StreamForwarder("stderr")
+
+        with contextlib.redirect_stdout(out_buf), contextlib.redirect_stderr(err_buf), warnings.catch_warnings(record=True) as caught:
+            # "error" turns a warning into an exception that fails the test
+            warnings.simplefilter(warnings_mode)
+            module = _load_module("test_module", test_file, rewrite_asserts, import_mode)
+
+            if is_doctest:
+                result["errored"] = False
+                _run_doctest(module, test_name)
+                result["passed"] = True
+            elif class_name:
+                # Nested classes are dotted (Outer.Inner)
+                cls = module
+                for attr in class_name.split("."):
+                    cls = getattr(cls, attr)
+                if isinstance(cls, type) and issubclass(cls, unittest.TestCase):
+                    _run_unittest(cls, test_name, setup_timeout, result)
+                else:
+                    instance = cls()
+                    try:
+                        _run_fixture(instance, "setUp", "setup", setup_timeout)
+                        test_func = getattr(instance, test_name)
+                        kwargs = _with_fixtures(test_func, params, test_file, rootdir)
+                        result["errored"] = False
+                        _run_test_body(test_func, kwargs)
+                    except BaseException as e:
+                        # Always run tearDown, even if the test fails, but report the test's failure
+                        _run_teardowns(instance, ["tearDown"], e)
+                        raise
+                    _run_teardowns(instance, ["tearDown"])
             else:
                 test_func = getattr(module, test_name)
-                _run_maybe_async(test_func)
+                try:
+                    _run_fixture(module, "setup_function", "setup", setup_timeout, test_func)
+                    kwargs = _with_fixtures(test_func, params, test_file, rootdir)
+                    result["errored"] = False
+                    _run_test_body(test_func, kwargs)
+                except BaseException as e:
+                    # Always run teardown_function, even if the test fails, but report the test's failure
+                    _run_teardowns(module, ["teardown_function"], e, test_func)
+                    raise
+                _run_teardowns(module, ["teardown_function"], test_func=test_func)
 
         result["stdout"] = out_buf.getvalue()
         result["stderr"] = err_buf.getvalue()
         result["passed"] = True
-    except AssertionError as e:
+    except unittest.SkipTest as e:
+        result["errored"] = False
+        result["skipped"] = True
+        result["skip_reason"] = str(e)
+    except FixtureError as e:
         result["error"] = {
-            "message": str(e) or "Assertion failed",
+            "message": _with_notes(str(e), e),
             "traceback": traceback.format_exc(),
+            "fixture": {"name": e.name, "phase": e.phase},
+            "category": _error_category(e),
+            "exception_type": _exception_type(e),
+        }
+    except AssertionError as e:
+        result["error"] = {
+            "message": _with_notes(str(e) or "Assertion failed", e),
+            "traceback": _assertion_traceback(e),
+            "category": _error_category(e),
+            "exception_type": _exception_type(e),
         }
     except Exception as e:
         result["error"] = {
-            "message": f"{type(e).__name__}: {e}",
+            "message": _with_notes(f"{type(e).__name__}: {e}", e),
             "traceback": traceback.format_exc(),
+            "category": _error_category(e),
+            "exception_type": _exception_type(e),
         }
     finally:
         sys.settrace(None)
         # Convert sets to sorted lists for JSON
         result["coverage"] = {k: sorted(v) for k, v in executed_lines.items()}
+        result["warnings"] = [_format_warning(w) for w in caught]
 
     print(json.dumps(result))
 
 
 if __name__ == "__main__":
+    _install_taut_shim()
     info = json.loads(sys.argv[1])
-    run_test(info["file"], info["function"], info.get("class"))
-"#;
+    if info.get("async_mode") == "shared":
+        _start_shared_loop()
+    run_test(info["file"], info["function"], info.get("class"), info.get("setup_timeout"), info.get("assert_rewrite", True), info.get("capture", True), info.get("params"), info.get("doctest", False), info.get("import_mode", "prepend"), info.get("rootdir"), info.get("warnings", "default"), info.get("coverage_include") or (), info.get("coverage_exclude") or ())
+    _close_shared_loop()
+"#
+);
+
+/// A chunk of test output streamed live from Python when capture is disabled.
+#[derive(Debug, Deserialize)]
+pub(crate) struct StreamedOutput {
+    pub stream: String,
+    pub data: String,
+}
+
+/// Echo streamed test output to taut's own stdout/stderr.
+pub(crate) fn forward_test_output(output: &StreamedOutput) {
+    if output.stream == "stderr" {
+        eprint!("{}", output.data);
+        let _ = io::stderr().flush();
+    } else {
+        print!("{}", output.data);
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Passes finished results to the `on_result` callback, either as they arrive or,
+/// when `ordered`, in index order (holding back results that finish early).
+pub(crate) struct ResultEmitter<'a, F> {
+    on_result: &'a F,
+    ordered: bool,
+    next: usize,
+    pending: HashMap<usize, TestResult>,
+}
+
+impl<'a, F: Fn(&TestResult)> ResultEmitter<'a, F> {
+    pub(crate) fn new(on_result: &'a F, ordered: bool) -> Self {
+        Self {
+            on_result,
+            ordered,
+            next: 0,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Report the result for the test at `idx`, plus any held-back results it unblocks.
+    pub(crate) fn emit(&mut self, idx: usize, result: &TestResult) {
+        if !self.ordered {
+            (self.on_result)(result);
+            return;
+        }
+        if idx != self.next {
+            self.pending.insert(idx, result.clone());
+            return;
+        }
+        (self.on_result)(result);
+        self.next += 1;
+        while let Some(result) = self.pending.remove(&self.next) {
+            (self.on_result)(&result);
+            self.next += 1;
+        }
+    }
+
+    /// Report results still held back behind one that never arrived.
+    pub(crate) fn flush(&mut self) {
+        let mut rest: Vec<_> = self.pending.drain().collect();
+        rest.sort_by_key(|(idx, _)| *idx);
+        for (_, result) in rest {
+            (self.on_result)(&result);
+        }
+    }
+}
+
+/// One lock per `@mark(group=...)` name, so tests sharing a group never overlap
+/// (`--parallel-groups`).
+#[derive(Default)]
+pub(crate) struct GroupLocks {
+    locks: BTreeMap<String, Mutex<()>>,
+}
+
+impl GroupLocks {
+    pub(crate) fn new(items: &[TestItem], enabled: bool) -> Self {
+        let locks = if enabled {
+            items
+                .iter()
+                .flat_map(|item| item.groups())
+                .map(|group| (group, Mutex::new(())))
+                .collect()
+        } else {
+            BTreeMap::new()
+        };
+        Self { locks }
+    }
+
+    /// Block until no other test in any of `item`'s groups is running.
+    pub(crate) fn acquire(&self, item: &TestItem) -> Vec<MutexGuard<'_, ()>> {
+        if self.locks.is_empty() {
+            return Vec::new();
+        }
+        let groups = item.groups();
+        // Taking locks in map order keeps tests with several groups from deadlocking
+        self.locks
+            .iter()
+            .filter(|(group, _)| groups.contains(group))
+            .map(|(_, lock)| lock.lock().unwrap_or_else(|e| e.into_inner()))
+            .collect()
+    }
+}
+
+/// Forward streamed output lines as they arrive and return the remaining
+/// lines (the JSON result).
+fn forward_streamed_lines(stdout: impl Read) -> String {
+    let mut rest = String::new();
+    for line in BufReader::new(stdout).lines().map_while(|line| line.ok()) {
+        match serde_json::from_str::<StreamedOutput>(&line) {
+            Ok(output) => forward_test_output(&output),
+            Err(_) => {
+                rest.push_str(&line);
+                rest.push('\n');
+            }
+        }
+    }
+    rest
+}
+
+/// Wait for a test process to exit, or kill it once `kill_at` passes (returning `None`).
+fn wait_for_test(
+    mut child: Child,
+    capture: bool,
+    kill_at: Option<Instant>,
+) -> io::Result<Option<Output>> {
+    let mut stdout = child.stdout.take().expect("stdout not captured");
+    let mut stderr = child.stderr.take().expect("stderr not captured");
+
+    // Drain both pipes on their own threads so the test never blocks on a full pipe
+    let stdout_reader = std::thread::spawn(move || {
+        if capture {
+            let mut buf = Vec::new();
+            let _ = stdout.read_to_end(&mut buf);
+            buf
+        } else {
+            forward_streamed_lines(stdout).into_bytes()
+        }
+    });
+    let stderr_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stderr.read_to_end(&mut buf);
+        buf
+    });
+
+    let status = match kill_at {
+        None => Some(child.wait()?),
+        Some(kill_at) => loop {
+            if let Some(status) = child.try_wait()? {
+                break Some(status);
+            }
+            if Instant::now() >= kill_at {
+                let _ = child.kill();
+                let _ = child.wait();
+                break None;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        },
+    };
+
+    let stdout = stdout_reader.join().unwrap_or_default();
+    let stderr = stderr_reader.join().unwrap_or_default();
+    Ok(status.map(|status| Output {
+        status,
+        stdout,
+        stderr,
+    }))
+}
+
+fn run_single_test(item: &TestItem, options: &RunOptions) -> TestResult {
+    if options.deadline_passed() {
+        return skipped_result(item, SkipKind::Deadline, "not started before --deadline");
+    }
 
-fn run_single_test(item: &TestItem, collect_coverage: bool) -> TestResult {
     let start = Instant::now();
+    let collect_coverage = options.collect_coverage;
 
     let test_info = serde_json::json!({
-        "file": item.file.canonicalize().unwrap_or(item.file.clone()).to_string_lossy(),
+        "file": item.canonical_file().to_string_lossy(),
         "function": &item.function,
         "class": &item.class,
+        "params": item.params.iter().cloned().collect::<serde_json::Map<_, _>>(),
+        "doctest": item.is_doctest(),
+        "setup_timeout": options.fixture_setup_timeout.map(|t| t.as_secs_f64()),
+        "assert_rewrite": options.assert_rewrite,
+        "capture": options.capture,
+        "async_mode": options.async_mode,
+        "import_mode": options.import_mode,
+        "warnings": options.warnings,
+        "coverage_include": &options.coverage_include,
+        "coverage_exclude": &options.coverage_exclude,
+        "rootdir": item.rootdir.as_ref().map(|dir| dir.to_string_lossy()),
     });
 
     let script = if collect_coverage {
@@ -256,16 +670,25 @@ fn run_single_test(item: &TestItem, collect_coverage: bool) -> TestResult {
         RUNNER_SCRIPT
     };
 
-    let output = Command::new("python3")
+    let mut command = Command::new("python3");
+    command
         .args(["-c", script, &test_info.to_string()])
         .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output();
+        .stderr(Stdio::piped());
+
+    if options.capture {
+        // As with `Command::output`, captured tests don't read the terminal
+        command.stdin(Stdio::null());
+    }
+    let output = command
+        .spawn()
+        .and_then(|child| wait_for_test(child, options.capture, options.kill_deadline()));
 
     let duration = start.elapsed();
 
     match output {
-        Ok(output) => {
+        Ok(None) => skipped_result(item, SkipKind::Deadline, "killed at --deadline"),
+        Ok(Some(output)) => {
             let stdout = String::from_utf8_lossy(&output.stdout);
 
             if let Ok(result) = serde_json::from_str::<serde_json::Value>(&stdout) {
@@ -292,6 +715,7 @@ fn run_single_test(item: &TestItem, collect_coverage: bool) -> TestResult {
                 } else {
                     None
                 };
+                let skipped = result["skipped"].as_bool().unwrap_or(false);
 
                 TestResult {
                     item: item.clone(),
@@ -307,11 +731,20 @@ fn run_single_test(item: &TestItem, collect_coverage: bool) -> TestResult {
                                     .unwrap_or("Unknown error")
                                     .to_string(),
                                 traceback: e["traceback"].as_str().map(String::from),
+                                fixture: e
+                                    .get("fixture")
+                                    .and_then(|f| serde_json::from_value(f.clone()).ok()),
+                                category: e
+                                    .get("category")
+                                    .and_then(|c| serde_json::from_value(c.clone()).ok()),
+                                exception_type: e["exception_type"].as_str().map(String::from),
                             })
                         }
                     }),
-                    skipped: false,
-                    skip_reason: None,
+                    errored: result["errored"].as_bool().unwrap_or(false),
+                    skipped,
+                    skip_reason: result["skip_reason"].as_str().map(String::from),
+                    skip_kind: skipped.then_some(SkipKind::Runtime),
                     coverage,
                     stdout: result
                         .get("stdout")
@@ -319,6 +752,10 @@ fn run_single_test(item: &TestItem, collect_coverage: bool) -> TestResult {
                     stderr: result
                         .get("stderr")
                         .and_then(|v| v.as_str().map(String::from)),
+                    warnings: result
+                        .get("warnings")
+                        .and_then(|w| serde_json::from_value(w.clone()).ok())
+                        .unwrap_or_default(),
                 }
             } else {
                 let stderr = String::from_utf8_lossy(&output.stderr);
@@ -329,12 +766,18 @@ fn run_single_test(item: &TestItem, collect_coverage: bool) -> TestResult {
                     error: Some(TestError {
                         message: "Failed to parse test output".to_string(),
                         traceback: Some(format!("stdout: {}\nstderr: {}", stdout, stderr)),
+                        fixture: None,
+                        category: Some(ErrorCategory::Crash),
+                        exception_type: None,
                     }),
+                    errored: false,
                     skipped: false,
                     skip_reason: None,
+                    skip_kind: None,
                     coverage: None,
                     stdout: None,
                     stderr: None,
+                    warnings: Vec::new(),
                 }
             }
         }
@@ -345,12 +788,18 @@ fn run_single_test(item: &TestItem, collect_coverage: bool) -> TestResult {
             error: Some(TestError {
                 message: format!("Failed to spawn Python: {}", e),
                 traceback: None,
+                fixture: None,
+                category: Some(ErrorCategory::Crash),
+                exception_type: None,
             }),
+            errored: true,
             skipped: false,
             skip_reason: None,
+            skip_kind: None,
             coverage: None,
             stdout: None,
             stderr: None,
+            warnings: Vec::new(),
         },
     }
 }
@@ -370,6 +819,156 @@ impl IsolationMode {
     }
 }
 
+/// How async tests and fixtures get an event loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AsyncMode {
+    /// A fresh loop for each coroutine (`asyncio.run`).
+    PerTest,
+    /// One loop per Python process, reused until it exits.
+    Shared,
+}
+
+impl AsyncMode {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "shared" => Self::Shared,
+            _ => Self::PerTest,
+        }
+    }
+}
+
+/// How test files are imported, like pytest's `--import-mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportMode {
+    /// Put the test's directory (and its package root, if any) on `sys.path`.
+    Prepend,
+    /// Like `Prepend`, and also put the rootdir on `sys.path` so imports
+    /// qualified from the project root work.
+    Rootdir,
+    /// Load the file by location only, leaving `sys.path` alone.
+    Importlib,
+}
+
+impl ImportMode {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "rootdir" => Self::Rootdir,
+            "importlib" => Self::Importlib,
+            _ => Self::Prepend,
+        }
+    }
+}
+
+/// What happens to Python warnings raised while a test runs, like `python -W`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WarningsMode {
+    /// Record each warning once per location and report it with the test.
+    Default,
+    /// Raise warnings as exceptions, failing the test.
+    Error,
+    /// Drop warnings entirely.
+    Ignore,
+}
+
+impl WarningsMode {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "error" => Self::Error,
+            "ignore" => Self::Ignore,
+            _ => Self::Default,
+        }
+    }
+}
+
+/// Options controlling how tests are executed.
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    /// Run every test concurrently. When false (`--no-parallel`) only `@parallel`
+    /// tests do, after the rest have run one at a time.
+    pub parallel: bool,
+    pub jobs: Option<usize>,
+    pub collect_coverage: bool,
+    pub isolation: IsolationMode,
+    /// Fail a test if its `setUp` fixture takes longer than this.
+    pub fixture_setup_timeout: Option<Duration>,
+    /// Rewrite `assert a == b` so failures report the operand values.
+    pub assert_rewrite: bool,
+    /// Capture test stdout/stderr into the result instead of streaming it live.
+    pub capture: bool,
+    /// In process-per-run mode, restart each worker after this many tests.
+    pub worker_max_tests: Option<usize>,
+    /// In process-per-run mode, the worker pool size (overrides `jobs`).
+    pub workers: Option<usize>,
+    /// Report results in input order rather than as they finish.
+    pub ordered_output: bool,
+    /// In process-per-run mode, abort the run if a worker dies mid-test
+    /// instead of respawning it and retrying the test.
+    pub bail_on_worker_crash: bool,
+    /// Stop starting tests once this instant passes (`--deadline`).
+    pub deadline: Option<Instant>,
+    /// Kill tests still running at the deadline instead of letting them finish.
+    pub kill_at_deadline: bool,
+    /// Never run two parallel tests sharing a `@mark(group=...)` at the same time.
+    pub parallel_groups: bool,
+    /// Whether async tests share one event loop per process.
+    pub async_mode: AsyncMode,
+    /// Which directories go on `sys.path` when importing a test file.
+    pub import_mode: ImportMode,
+    /// Filter applied to Python warnings during each test.
+    pub warnings: WarningsMode,
+    /// Record coverage for files matching these substrings or globs, even
+    /// ones that would be skipped as installed packages.
+    pub coverage_include: Vec<String>,
+    /// Never record coverage for files matching these substrings or globs.
+    pub coverage_exclude: Vec<String>,
+    /// In process-per-run mode, keep each worker on one file's tests as long as
+    /// possible so it can reuse the compiled module.
+    pub file_affinity: bool,
+}
+
+impl RunOptions {
+    /// Whether the run's deadline has passed, so no more tests should start.
+    pub fn deadline_passed(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// When tests still running should be killed, if ever.
+    pub fn kill_deadline(&self) -> Option<Instant> {
+        self.deadline.filter(|_| self.kill_at_deadline)
+    }
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            parallel: true,
+            jobs: None,
+            collect_coverage: false,
+            isolation: IsolationMode::ProcessPerTest,
+            fixture_setup_timeout: None,
+            assert_rewrite: true,
+            capture: true,
+            worker_max_tests: None,
+            workers: None,
+            ordered_output: false,
+            bail_on_worker_crash: false,
+            deadline: None,
+            kill_at_deadline: false,
+            parallel_groups: false,
+            async_mode: AsyncMode::PerTest,
+            import_mode: ImportMode::Prepend,
+            warnings: WarningsMode::Default,
+            coverage_include: Vec::new(),
+            coverage_exclude: Vec::new(),
+            file_affinity: false,
+        }
+    }
+}
+
 /// Run tests with optional coverage collection
 pub fn run_tests<F>(
     items: &[TestItem],
@@ -379,25 +978,40 @@ pub fn run_tests<F>(
     isolation: IsolationMode,
     on_result: F,
 ) -> Result<TestResults>
+where
+    F: Fn(&TestResult) + Send + Sync,
+{
+    let options = RunOptions {
+        parallel,
+        jobs,
+        collect_coverage,
+        isolation,
+        ..RunOptions::default()
+    };
+    run_tests_with_options(items, &options, on_result)
+}
+
+/// Run tests using the given execution options
+pub fn run_tests_with_options<F>(
+    items: &[TestItem],
+    options: &RunOptions,
+    on_result: F,
+) -> Result<TestResults>
 where
     F: Fn(&TestResult) + Send + Sync,
 {
     let start = Instant::now();
 
-    if let Some(n) = jobs {
+    if let Some(n) = options.jobs {
         rayon::ThreadPoolBuilder::new()
             .num_threads(n)
             .build_global()
             .ok();
     }
 
-    let results: Vec<TestResult> = match isolation {
-        IsolationMode::ProcessPerRun => {
-            run_tests_process_per_run(items, parallel, jobs, collect_coverage, &on_result)?
-        }
-        IsolationMode::ProcessPerTest => {
-            run_tests_process_per_test(items, parallel, collect_coverage, &on_result)?
-        }
+    let results: Vec<TestResult> = match options.isolation {
+        IsolationMode::ProcessPerRun => run_tests_process_per_run(items, options, &on_result)?,
+        IsolationMode::ProcessPerTest => run_tests_process_per_test(items, options, &on_result)?,
     };
 
     Ok(TestResults {
@@ -408,64 +1022,57 @@ where
 
 fn run_tests_process_per_test<F>(
     items: &[TestItem],
-    parallel: bool,
-    collect_coverage: bool,
+    options: &RunOptions,
     on_result: &F,
 ) -> Result<Vec<TestResult>>
 where
     F: Fn(&TestResult) + Send + Sync,
 {
-    use std::sync::Mutex;
+    let (parallel_tests, sequential_tests): (Vec<_>, Vec<_>) = items
+        .iter()
+        .partition(|item| runs_concurrently(item, options));
 
-    // If parallel execution is enabled, separate tests by @parallel marker
-    // Tests with @parallel run concurrently, others run sequentially
-    if parallel && items.len() > 1 {
-        let (parallel_tests, sequential_tests): (Vec<_>, Vec<_>) =
-            items.iter().partition(|item| item.is_parallel());
+    let mut results = Vec::new();
 
-        let mut results = Vec::new();
+    // Run sequential tests first (unmarked tests under --no-parallel)
+    for item in &sequential_tests {
+        let result = run_single_test(item, options);
+        on_result(&result);
+        results.push(result);
+    }
 
-        // Run sequential tests first (no @parallel marker)
-        for item in &sequential_tests {
-            let result = run_single_test(item, collect_coverage);
-            on_result(&result);
-            results.push(result);
-        }
+    // Run parallel tests concurrently
+    if !parallel_tests.is_empty() {
+        let emitter = Mutex::new(ResultEmitter::new(on_result, options.ordered_output));
+        let group_locks = GroupLocks::new(items, options.parallel_groups);
+        let parallel_results: Vec<TestResult> = parallel_tests
+            .par_iter()
+            .enumerate()
+            .map(|(idx, item)| {
+                let guards = group_locks.acquire(item);
+                let result = run_single_test(item, options);
+                drop(guards);
+                if let Ok(mut emitter) = emitter.lock() {
+                    emitter.emit(idx, &result);
+                }
+                result
+            })
+            .collect();
+        results.extend(parallel_results);
+    }
 
-        // Run parallel tests concurrently
-        if !parallel_tests.is_empty() {
-            let callback = Mutex::new(on_result);
-            let parallel_results: Vec<TestResult> = parallel_tests
-                .par_iter()
-                .map(|item| {
-                    let result = run_single_test(item, collect_coverage);
-                    if let Ok(cb) = callback.lock() {
-                        cb(&result);
-                    }
-                    result
-                })
-                .collect();
-            results.extend(parallel_results);
-        }
+    Ok(results)
+}
 
-        Ok(results)
-    } else {
-        // Sequential execution (--no-parallel flag or single test)
-        let mut results = Vec::new();
-        for item in items {
-            let result = run_single_test(item, collect_coverage);
-            on_result(&result);
-            results.push(result);
-        }
-        Ok(results)
-    }
+/// Whether a test may run alongside others: every test by default, only
+/// `@parallel` ones under `--no-parallel`.
+fn runs_concurrently(item: &TestItem, options: &RunOptions) -> bool {
+    options.parallel || item.is_parallel()
 }
 
 fn run_tests_process_per_run<F>(
     items: &[TestItem],
-    parallel: bool,
-    jobs: Option<usize>,
-    collect_coverage: bool,
+    options: &RunOptions,
     on_result: &F,
 ) -> Result<Vec<TestResult>>
 where
@@ -475,33 +1082,33 @@ where
         return Ok(Vec::new());
     }
 
-    // Separate tests by @parallel marker
-    let (parallel_tests, sequential_tests): (Vec<_>, Vec<_>) =
-        items.iter().cloned().partition(|item| item.is_parallel());
+    let (parallel_tests, sequential_tests): (Vec<_>, Vec<_>) = items
+        .iter()
+        .cloned()
+        .partition(|item| runs_concurrently(item, options));
 
     let mut results = Vec::new();
 
     // Run sequential tests first with single worker
     if !sequential_tests.is_empty() {
-        let pool = crate::worker_pool::WorkerPool::new(1);
-        let sequential_results = pool.run_tests(&sequential_tests, collect_coverage, on_result)?;
+        let pool = crate::worker_pool::WorkerPool::new(1)
+            .with_max_tests_per_worker(options.worker_max_tests);
+        let sequential_results = pool.run_tests(&sequential_tests, options, on_result)?;
         results.extend(sequential_results);
     }
 
-    // Run parallel tests with worker pool (if parallel flag is set)
+    // Run parallel tests with worker pool
     if !parallel_tests.is_empty() {
-        let num_workers = if parallel {
-            jobs.unwrap_or_else(|| {
-                std::thread::available_parallelism()
-                    .map(|n| n.get())
-                    .unwrap_or(4)
-            })
-        } else {
-            1
-        };
-
-        let pool = crate::worker_pool::WorkerPool::new(num_workers);
-        let parallel_results = pool.run_tests(&parallel_tests, collect_coverage, on_result)?;
+        // Pool size: --workers > -j (or config max_workers) > CPU count
+        let num_workers = options.workers.or(options.jobs).unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(4)
+        });
+
+        let pool = crate::worker_pool::WorkerPool::new(num_workers)
+            .with_max_tests_per_worker(options.worker_max_tests);
+        let parallel_results = pool.run_tests(&parallel_tests, options, on_result)?;
         results.extend(parallel_results);
     }
 
@@ -509,16 +1116,19 @@ where
 }
 
 /// Create a skipped test result
-pub fn skipped_result(item: &TestItem, reason: &str) -> TestResult {
+pub fn skipped_result(item: &TestItem, kind: SkipKind, reason: &str) -> TestResult {
     TestResult {
         item: item.clone(),
         passed: true,
         duration: Duration::ZERO,
         error: None,
+        errored: false,
         skipped: true,
         skip_reason: Some(reason.to_string()),
+        skip_kind: Some(kind),
         coverage: None,
         stdout: None,
         stderr: None,
+        warnings: Vec::new(),
     }
 }
@@ -1,9 +1,13 @@
-use crate::blocks::FileBlocks;
-use crate::depdb::{DependencyDatabase, TestRunDecision};
+use crate::blocks::{BlockId, BlockKind, FileBlocks};
+use crate::cache::{self, BlockIndex, FileStamp};
+use crate::depdb::{DependencyDatabase, TestId, TestRunDecision};
 use crate::discovery::TestItem;
 use crate::runner::TestResult;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
 use walkdir::WalkDir;
 
 pub struct TestSelection {
@@ -21,16 +25,79 @@ impl TestSelection {
     }
 }
 
+/// Code that differs between a git revision and the working tree, for `--changed-since`.
+#[derive(Debug, Default)]
+pub struct ChangedBlocks {
+    /// Files that were added or deleted, or couldn't be compared block by block
+    files: HashSet<PathBuf>,
+    /// Blocks whose source differs, by file, kind and name (line numbers shift)
+    blocks: HashSet<(PathBuf, BlockKind, String)>,
+}
+
+impl ChangedBlocks {
+    /// Blocks of Python files that differ between `reference` and the working tree,
+    /// according to `git diff --name-only <reference>`.
+    pub fn since(reference: &str) -> Result<Self> {
+        let toplevel = git(&["rev-parse", "--show-toplevel"])?;
+        let toplevel = PathBuf::from(toplevel.trim());
+        let toplevel = toplevel.canonicalize().unwrap_or(toplevel);
+
+        let mut changed = Self::default();
+        let diff = git(&["diff", "--name-only", "-z", reference, "--"])?;
+        for relative in diff.split('\0').filter(|name| name.ends_with(".py")) {
+            let path = toplevel.join(relative);
+            let path = path.canonicalize().unwrap_or(path);
+
+            // Added and deleted files (and ones that don't parse) change as a whole
+            let current = FileBlocks::from_file(&path).ok();
+            let previous = git(&["show", &format!("{reference}:{relative}")])
+                .ok()
+                .and_then(|source| FileBlocks::from_source(&source, &path).ok());
+
+            match (current, previous) {
+                (Some(current), Some(previous)) => {
+                    changed.blocks.extend(differing_blocks(&current, &previous))
+                }
+                _ => {
+                    changed.files.insert(path);
+                }
+            }
+        }
+        Ok(changed)
+    }
+
+    pub fn contains(&self, block: &BlockId) -> bool {
+        self.files.contains(&block.file)
+            || self
+                .blocks
+                .contains(&(block.file.clone(), block.kind.clone(), block.name.clone()))
+    }
+}
+
 pub struct TestSelector {
     depdb: DependencyDatabase,
     block_index: HashMap<PathBuf, FileBlocks>,
+    /// Stamp of each indexed file at the time its blocks were parsed
+    file_stamps: HashMap<PathBuf, FileStamp>,
+    /// Blocks persisted by a previous run, consumed as files are indexed
+    cached_blocks: BlockIndex,
+    /// Where the database and block index are loaded from and saved to
+    cache_dir: PathBuf,
 }
 
 impl TestSelector {
-    pub fn new() -> Self {
+    /// Load the caches in `cache_dir`. `config` is the current
+    /// `Config::fingerprint`; tests that last ran under a different
+    /// configuration are selected to run again.
+    pub fn new(cache_dir: &Path, config: Option<String>) -> Self {
+        let mut depdb = DependencyDatabase::load(cache_dir);
+        depdb.set_config(config);
         Self {
-            depdb: DependencyDatabase::load(),
+            depdb,
             block_index: HashMap::new(),
+            file_stamps: HashMap::new(),
+            cached_blocks: cache::load_block_index(cache_dir),
+            cache_dir: cache_dir.to_path_buf(),
         }
     }
 
@@ -56,21 +123,63 @@ impl TestSelector {
 
     fn index_single_file(&mut self, path: &std::path::Path) {
         let abs_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let stamp = FileStamp::of(&abs_path);
 
-        if let Ok(file_blocks) = FileBlocks::from_file(&abs_path) {
-            self.depdb.update_blocks(&file_blocks);
-            self.block_index.insert(abs_path, file_blocks);
+        // Reuse blocks from the previous run if the file is unchanged
+        let cached = self
+            .cached_blocks
+            .remove(&abs_path)
+            .filter(|(cached_stamp, _)| Some(*cached_stamp) == stamp)
+            .map(|(_, file_blocks)| file_blocks);
+
+        let file_blocks = match cached {
+            Some(file_blocks) => file_blocks,
+            None => match FileBlocks::from_file(&abs_path) {
+                Ok(file_blocks) => file_blocks,
+                Err(_) => return,
+            },
+        };
+
+        self.depdb.update_blocks(&file_blocks);
+        if let Some(stamp) = stamp {
+            self.file_stamps.insert(abs_path.clone(), stamp);
         }
+        self.block_index.insert(abs_path, file_blocks);
+    }
+
+    /// Whether `test` needs to run, and why
+    pub fn decision(&self, test: &TestItem) -> TestRunDecision {
+        self.depdb.needs_run(test)
     }
 
     /// Select which tests need to run based on dependency changes.
     /// Tests are sorted with failed tests first (fail-first strategy).
     pub fn select_tests(&self, all_tests: &[TestItem]) -> TestSelection {
+        self.select_by(all_tests, |test| self.decision(test))
+    }
+
+    /// Select the tests that depend on `changed` code, plus new and failing tests.
+    pub fn tests_affected_by(
+        &self,
+        all_tests: &[TestItem],
+        changed: &ChangedBlocks,
+    ) -> TestSelection {
+        self.select_by(all_tests, |test| {
+            self.depdb
+                .affected_by(test, |block| changed.contains(block))
+        })
+    }
+
+    fn select_by(
+        &self,
+        all_tests: &[TestItem],
+        decide: impl Fn(&TestItem) -> TestRunDecision,
+    ) -> TestSelection {
         let mut to_run = Vec::new();
         let mut to_skip = Vec::new();
 
         for test in all_tests {
-            let decision = self.depdb.needs_run(test);
+            let decision = decide(test);
             if decision.should_run() {
                 to_run.push((test.clone(), decision));
             } else {
@@ -96,6 +205,10 @@ impl TestSelector {
 
     /// Record test result with coverage data
     pub fn record_result(&mut self, result: &TestResult) {
+        // A test that skipped itself may run next time, so it has nothing to record
+        if result.skipped {
+            return;
+        }
         if let Some(ref coverage) = result.coverage {
             self.depdb.record_test_coverage(
                 &result.item,
@@ -103,7 +216,7 @@ impl TestSelector {
                 result.passed,
                 &self.block_index,
             );
-        } else if !result.skipped {
+        } else {
             // Test ran without coverage - record empty dependency set
             self.depdb.record_test_coverage(
                 &result.item,
@@ -112,11 +225,29 @@ impl TestSelector {
                 &self.block_index,
             );
         }
+
+        self.depdb.record_duration(&result.item, result.duration);
+    }
+
+    /// Explain the selection decision for a single test
+    pub fn explain(&self, test: &TestItem) -> crate::depdb::TestExplanation {
+        self.depdb.explain(test)
     }
 
-    /// Save the dependency database
+    /// Duration of the test's last recorded run, if any
+    pub fn last_duration(&self, test: &TestItem) -> Option<Duration> {
+        self.depdb.last_duration(&TestId::from(test))
+    }
+
+    /// Whether the test's last recorded run failed
+    pub fn last_failed(&self, test: &TestItem) -> bool {
+        self.depdb.last_failed(test)
+    }
+
+    /// Save the dependency database and the parsed block index
     pub fn save(&self) {
-        self.depdb.save();
+        self.depdb.save(&self.cache_dir);
+        cache::save_block_index(&self.cache_dir, &self.block_index, &self.file_stamps);
     }
 
     /// Get database statistics
@@ -132,6 +263,42 @@ impl TestSelector {
 
 impl Default for TestSelector {
     fn default() -> Self {
-        Self::new()
+        Self::new(&cache::resolve_cache_dir(None, None, false), None)
+    }
+}
+
+/// Blocks present in only one of `a` and `b`, or in both with different source.
+fn differing_blocks(a: &FileBlocks, b: &FileBlocks) -> Vec<(PathBuf, BlockKind, String)> {
+    let checksums = |file_blocks: &FileBlocks| -> HashMap<(BlockKind, String), String> {
+        file_blocks
+            .blocks
+            .iter()
+            .map(|block| {
+                let key = (block.id.kind.clone(), block.id.name.clone());
+                (key, block.checksum.clone())
+            })
+            .collect()
+    };
+    let (a_checksums, b_checksums) = (checksums(a), checksums(b));
+    a_checksums
+        .keys()
+        .chain(b_checksums.keys())
+        .filter(|key| a_checksums.get(*key) != b_checksums.get(*key))
+        .map(|(kind, name)| (a.file.clone(), kind.clone(), name.clone()))
+        .collect()
+}
+
+fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
     }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
 }
@@ -1,35 +1,33 @@
 import sys
 import struct
 import traceback
-import importlib.util
-import inspect
-import asyncio
 import io
 import contextlib
+import warnings
+import unittest
 import os
 import time
 import msgpack
 
 
-def _run_maybe_async(callable_obj):
-    result = callable_obj()
-    if inspect.isawaitable(result):
-        asyncio.run(result)
+# How this process collects coverage ("monitoring" or "settrace"), probed once at startup
+_coverage_strategy = None
 
+# sys.monitoring tool id that worked last time, tried first for the next test
+_monitoring_tool_id = None
 
-def _should_track(filename):
-    if not filename or filename.startswith("<"):
-        return False
-    return not any(x in filename for x in ["site-packages", "lib/python", "/usr/lib"])
 
+# Project files under the default rules, whose modules are reset between tests
+_should_track = _coverage_filter()
 
-def _collect_coverage_with_settrace():
+
+def _collect_coverage_with_settrace(should_track):
     executed_lines = {}
 
     def trace_function(frame, event, arg):
         if event == "line":
             filename = frame.f_code.co_filename
-            if _should_track(filename):
+            if should_track(filename):
                 abs_path = os.path.abspath(filename)
                 executed_lines.setdefault(abs_path, set()).add(frame.f_lineno)
         return trace_function
@@ -37,14 +35,41 @@ def _collect_coverage_with_settrace():
     return executed_lines, trace_function
 
 
-def _collect_coverage_with_monitoring():
+def _probe_coverage_strategy():
+    """Pick the coverage strategy for this process: sys.monitoring (3.12+) is much
+    cheaper than a settrace hook. TAUT_COVERAGE_TRACER=settrace forces the fallback."""
+    global _coverage_strategy
+    if os.environ.get("TAUT_COVERAGE_TRACER") == "settrace" or not hasattr(sys, "monitoring"):
+        _coverage_strategy = "settrace"
+    else:
+        _coverage_strategy = "monitoring"
+
+
+def _start_coverage(should_track):
+    """Start collecting coverage; returns the lines dict and a function that stops it."""
+    global _coverage_strategy
+    if _coverage_strategy is None:
+        _probe_coverage_strategy()
+    if _coverage_strategy == "monitoring":
+        try:
+            return _collect_coverage_with_monitoring(should_track)
+        except Exception:
+            # e.g. every tool id is taken; settrace works regardless, so stay on it
+            _coverage_strategy = "settrace"
+    executed_lines, trace_fn = _collect_coverage_with_settrace(should_track)
+    sys.settrace(trace_fn)
+    return executed_lines, lambda: sys.settrace(None)
+
+
+def _collect_coverage_with_monitoring(should_track):
+    global _monitoring_tool_id
     mon = sys.monitoring
     executed_lines = {}
     seen_code = set()
 
     def on_call(code, instruction_offset):
         filename = getattr(code, "co_filename", "")
-        if not _should_track(filename):
+        if not should_track(filename):
             return
         if code in seen_code:
             return
@@ -53,13 +78,16 @@ def _collect_coverage_with_monitoring():
 
     def on_line(code, line_number):
         filename = getattr(code, "co_filename", "")
-        if not _should_track(filename):
+        if not should_track(filename):
             return
         abs_path = os.path.abspath(filename)
         executed_lines.setdefault(abs_path, set()).add(line_number)
 
     tool_id = None
-    for tid in range(1, mon.MAX_TOOL_ID + 1):
+    candidates = list(range(1, mon.MAX_TOOL_ID + 1))
+    if _monitoring_tool_id is not None:
+        candidates.insert(0, _monitoring_tool_id)
+    for tid in candidates:
         try:
             mon.use_tool_id(tid, "taut_worker")
         except ValueError:
@@ -69,6 +97,7 @@ def _collect_coverage_with_monitoring():
 
     if tool_id is None:
         raise RuntimeError("No free sys.monitoring tool id")
+    _monitoring_tool_id = tool_id
 
     mon.register_callback(tool_id, mon.events.CALL, on_call)
     mon.register_callback(tool_id, mon.events.LINE, on_line)
@@ -88,6 +117,16 @@ def run_test(req):
     test_name = req["function"]
     class_name = req.get("class")
     collect_coverage = req.get("collect_coverage", False)
+    coverage_include = req.get("coverage_include") or ()
+    coverage_exclude = req.get("coverage_exclude") or ()
+    setup_timeout = req.get("setup_timeout")
+    rewrite_asserts = req.get("assert_rewrite", True)
+    capture = req.get("capture", True)
+    params = req.get("params") or {}
+    is_doctest = req.get("doctest", False)
+    import_mode = req.get("import_mode", "prepend")
+    rootdir = req.get("rootdir")
+    warnings_mode = req.get("warnings", "default")
     request_id = req.get("id", 0)
 
     result = {
@@ -99,72 +138,116 @@ def run_test(req):
         "duration_sec": 0.0,
     }
 
+    caught = []
     executed_lines = None
     uninstall = None
-    trace_fn = None
 
     start = time.perf_counter()
 
     try:
-        test_dir = os.path.dirname(os.path.abspath(test_file))
-        if test_dir not in sys.path:
-            sys.path.insert(0, test_dir)
+        # Until the test body starts, an exception means the test couldn't run
+        result["errored"] = True
+        _add_import_paths(test_file, import_mode, rootdir)
 
         if collect_coverage:
-            try:
-                executed_lines, uninstall = _collect_coverage_with_monitoring()
-            except Exception:
-                executed_lines, trace_fn = _collect_coverage_with_settrace()
-                sys.settrace(trace_fn)
+            executed_lines, uninstall = _start_coverage(_coverage_filter(coverage_include, coverage_exclude))
 
-        out_buf = io.StringIO()
-        err_buf = io.StringIO()
+        if capture:
+            out_buf = io.StringIO()
+            err_buf = io.StringIO()
+        else:
+            out_buf = _StreamForwarder(request_id, "stdout")
+            err_buf = _StreamForwarder(request_id, "stderr")
 
         # Use unique module name to avoid cache issues
         mod_name = f"taut_test_{request_id}"
 
-        with contextlib.redirect_stdout(out_buf), contextlib.redirect_stderr(err_buf):
-            spec = importlib.util.spec_from_file_location(mod_name, test_file)
-            module = importlib.util.module_from_spec(spec)
-            sys.modules[mod_name] = module
-            spec.loader.exec_module(module)
+        with contextlib.redirect_stdout(out_buf), contextlib.redirect_stderr(err_buf), warnings.catch_warnings(record=True) as caught:
+            # "error" turns a warning into an exception that fails the test
+            warnings.simplefilter(warnings_mode)
+            module = _load_module(mod_name, test_file, rewrite_asserts, import_mode)
 
-            if class_name:
-                cls = getattr(module, class_name)
-                instance = cls()
-                try:
-                    if hasattr(instance, "setUp"):
-                        instance.setUp()
-                    test_func = getattr(instance, test_name)
-                    _run_maybe_async(test_func)
-                    result["passed"] = True
-                finally:
-                    # Always run tearDown, even if test fails
-                    if hasattr(instance, "tearDown"):
-                        instance.tearDown()
+            if is_doctest:
+                result["errored"] = False
+                _run_doctest(module, test_name)
+                result["passed"] = True
+            elif class_name:
+                # Nested classes are dotted (Outer.Inner)
+                cls = module
+                for attr in class_name.split("."):
+                    cls = getattr(cls, attr)
+                if isinstance(cls, type) and issubclass(cls, unittest.TestCase):
+                    _run_unittest(cls, test_name, setup_timeout, result)
+                else:
+                    instance = cls()
+                    try:
+                        _run_fixture(instance, "setUp", "setup", setup_timeout)
+                        test_func = getattr(instance, test_name)
+                        kwargs = _with_fixtures(test_func, params, test_file, rootdir)
+                        result["errored"] = False
+                        _run_test_body(test_func, kwargs)
+                    except BaseException as e:
+                        # Always run tearDown, even if the test fails, but report the test's failure
+                        _run_teardowns(instance, ["tearDown"], e)
+                        raise
+                    _run_teardowns(instance, ["tearDown"])
+                result["passed"] = True
             else:
                 test_func = getattr(module, test_name)
-                _run_maybe_async(test_func)
+                try:
+                    _run_fixture(module, "setup_function", "setup", setup_timeout, test_func)
+                    kwargs = _with_fixtures(test_func, params, test_file, rootdir)
+                    result["errored"] = False
+                    _run_test_body(test_func, kwargs)
+                except BaseException as e:
+                    # Always run teardown_function, even if the test fails, but report the test's failure
+                    _run_teardowns(module, ["teardown_function"], e, test_func)
+                    raise
+                _run_teardowns(module, ["teardown_function"], test_func=test_func)
                 result["passed"] = True
 
-        # Clean up module from sys.modules
-        sys.modules.pop(mod_name, None)
+        # Clean up module from sys.modules (packaged tests load under their dotted name)
+        sys.modules.pop(module.__name__, None)
 
         result["stdout"] = out_buf.getvalue()
         result["stderr"] = err_buf.getvalue()
 
+    except unittest.SkipTest as e:
+        result["stdout"] = out_buf.getvalue() if 'out_buf' in dir() else ""
+        result["stderr"] = err_buf.getvalue() if 'err_buf' in dir() else ""
+        result["errored"] = False
+        result["skipped"] = True
+        result["skip_reason"] = str(e)
+    except FixtureError as e:
+        result["stdout"] = out_buf.getvalue() if 'out_buf' in dir() else ""
+        result["stderr"] = err_buf.getvalue() if 'err_buf' in dir() else ""
+        result["error"] = {
+            "message": _with_notes(str(e), e),
+            "traceback": traceback.format_exc(),
+            "fixture": {"name": e.name, "phase": e.phase},
+            "category": _error_category(e),
+            "exception_type": _exception_type(e),
+        }
     except AssertionError as e:
         result["stdout"] = out_buf.getvalue() if 'out_buf' in dir() else ""
         result["stderr"] = err_buf.getvalue() if 'err_buf' in dir() else ""
-        result["error"] = {"message": str(e) or "Assertion failed", "traceback": traceback.format_exc()}
+        result["error"] = {
+            "message": _with_notes(str(e) or "Assertion failed", e),
+            "traceback": _assertion_traceback(e),
+            "category": _error_category(e),
+            "exception_type": _exception_type(e),
+        }
     except Exception as e:
         result["stdout"] = out_buf.getvalue() if 'out_buf' in dir() else ""
         result["stderr"] = err_buf.getvalue() if 'err_buf' in dir() else ""
-        result["error"] = {"message": f"{type(e).__name__}: {e}", "traceback": traceback.format_exc()}
+        result["error"] = {
+            "message": _with_notes(f"{type(e).__name__}: {e}", e),
+            "traceback": traceback.format_exc(),
+            "category": _error_category(e),
+            "exception_type": _exception_type(e),
+        }
 
     finally:
-        if trace_fn is not None:
-            sys.settrace(None)
         if uninstall is not None:
             try:
                 uninstall()
@@ -175,6 +258,7 @@ def run_test(req):
             result["coverage"] = {k: sorted(v) for k, v in executed_lines.items()}
 
         result["duration_sec"] = time.perf_counter() - start
+        result["warnings"] = [_format_warning(w) for w in caught]
 
     return result
 
@@ -192,14 +276,55 @@ def _read_message():
 
     return msgpack.unpackb(data, raw=False)
 
+class _StreamForwarder(io.TextIOBase):
+    """Forwards test output to taut as it is written (used when capture is disabled)."""
+
+    def __init__(self, request_id, stream):
+        self._request_id = request_id
+        self._stream = stream
+
+    def writable(self):
+        return True
+
+    def write(self, data):
+        if data:
+            _send_message({"id": self._request_id, "stream": self._stream, "data": data})
+        return len(data)
+
+    def getvalue(self):
+        return None
+
+# Keep the protocol on a private copy of stdout and point fd 1 at stderr, so output
+# that bypasses sys.stdout (C extensions, os.write(1, ...)) can't corrupt the framing
+sys.__stdout__.flush()
+_PROTOCOL_OUT = os.fdopen(os.dup(1), "wb")
+os.dup2(2, 1)
+
+
 def _send_message(msg):
-    """Send length-prefixed msgpack message to stdout."""
+    """Send length-prefixed msgpack message to taut."""
     data = msgpack.packb(msg, use_bin_type=True)
     length = struct.pack('<I', len(data))
-    sys.stdout.buffer.write(length + data)
-    sys.stdout.buffer.flush()
+    _PROTOCOL_OUT.write(length + data)
+    _PROTOCOL_OUT.flush()
+
+
+_install_taut_shim()
+
+# Modules loaded before any test ran (stdlib, msgpack, the worker itself)
+_BASELINE_MODULES = frozenset(sys.modules)
+
+
+def _reset_project_modules():
+    """Forget project modules imported by the last test so the next one re-imports them fresh."""
+    for name in [n for n in sys.modules if n not in _BASELINE_MODULES]:
+        module = sys.modules.get(name)
+        if _should_track(getattr(module, "__file__", None)):
+            del sys.modules[name]
+
 
 def main():
+    _probe_coverage_strategy()
     while True:
         try:
             req = _read_message()
@@ -213,13 +338,24 @@ def main():
                 _send_message({"id": req.get("id", 0), "pong": True})
                 continue
 
-            resp = run_test(req)
+            if req.get("async_mode") == "shared" and _shared_loop is None:
+                # Created with the first request, since that's where options arrive
+                _start_shared_loop()
+
+            try:
+                resp = run_test(req)
+            finally:
+                _reset_project_modules()
 
         except Exception as e:
             resp = {
                 "id": req.get("id", -1) if isinstance(req, dict) else -1,
                 "passed": False,
-                "error": {"message": f"Worker error: {e}", "traceback": traceback.format_exc()},
+                "error": {
+                    "message": f"Worker error: {e}",
+                    "traceback": traceback.format_exc(),
+                    "category": {"kind": "crash"},
+                },
                 "stdout": "",
                 "stderr": "",
                 "duration_sec": 0.0,
@@ -227,6 +363,8 @@ def main():
 
         _send_message(resp)
 
+    _close_shared_loop()
+
 
 if __name__ == "__main__":
     main()
@@ -5,24 +5,37 @@
 //! interpreter startup overhead.
 
 use crate::discovery::TestItem;
-use crate::runner::{TestCoverage, TestError, TestResult};
+use crate::runner::{
+    AsyncMode, ErrorCategory, FixtureFailure, GroupLocks, ImportMode, ResultEmitter, RunOptions,
+    SkipKind, StreamedOutput, TestCoverage, TestError, TestResult, WarningsMode,
+    forward_test_output, skipped_result,
+};
 use anyhow::Result;
-use crossbeam_channel::{Sender, bounded};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, bounded, unbounded};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-// Worker script is embedded at build time from src/worker.py
+// Worker script is embedded at build time from src/prelude.py and src/worker.py
 include!(concat!(env!("OUT_DIR"), "/worker_script.rs"));
 
 static REQUEST_ID: AtomicU64 = AtomicU64::new(1);
 
+/// Number of recent worker stderr lines kept for crash reports.
+const STDERR_TAIL_LINES: usize = 50;
+
+/// A worker idle for this long is pinged before it is handed another test.
+const IDLE_PING_AFTER: Duration = Duration::from_secs(1);
+
+/// How long a pinged worker has to answer before it is considered hung.
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
 fn next_request_id() -> u64 {
     REQUEST_ID.fetch_add(1, Ordering::SeqCst)
 }
@@ -35,7 +48,29 @@ struct WorkerRequest {
     function: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     class: Option<String>,
+    /// Keyword arguments for a @parametrize case
+    params: serde_json::Map<String, serde_json::Value>,
+    /// Run the docstring examples named by `function` instead of a test function
+    doctest: bool,
     collect_coverage: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    setup_timeout: Option<f64>,
+    assert_rewrite: bool,
+    capture: bool,
+    async_mode: AsyncMode,
+    import_mode: ImportMode,
+    warnings: WarningsMode,
+    coverage_include: Vec<String>,
+    coverage_exclude: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rootdir: Option<String>,
+}
+
+/// Health check sent to an idle worker; it answers with a pong carrying the same id.
+#[derive(Serialize)]
+struct PingRequest {
+    cmd: &'static str,
+    id: u64,
 }
 
 /// Response from worker (serialized as MessagePack).
@@ -45,8 +80,21 @@ struct WorkerResponse {
     passed: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<WorkerError>,
-    stdout: String,
-    stderr: String,
+    /// The error happened before the test body started
+    #[serde(default)]
+    errored: bool,
+    /// The test skipped itself while running (e.g. `self.skipTest()`)
+    #[serde(default)]
+    skipped: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    skip_reason: Option<String>,
+    /// `None` when capture is disabled (output was streamed instead).
+    #[serde(default)]
+    stdout: Option<String>,
+    #[serde(default)]
+    stderr: Option<String>,
+    #[serde(default)]
+    warnings: Vec<String>,
     duration_sec: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     coverage: Option<HashMap<String, Vec<usize>>>,
@@ -57,13 +105,84 @@ struct WorkerError {
     message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     traceback: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fixture: Option<FixtureFailure>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    category: Option<ErrorCategory>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exception_type: Option<String>,
+}
+
+/// Message read from a worker: either live output or the final response.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WorkerMessage {
+    Output(StreamedOutput),
+    Response(Box<WorkerResponse>),
+    Pong { id: u64, pong: bool },
+}
+
+/// A worker process exited while a test was running.
+#[derive(Debug)]
+struct WorkerDied {
+    /// Last lines the worker wrote to stderr before exiting
+    stderr_tail: String,
+}
+
+impl std::fmt::Display for WorkerDied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Worker EOF (process died)")
+    }
+}
+
+impl std::error::Error for WorkerDied {}
+
+/// `--deadline` passed while a test was still running and it should be killed.
+#[derive(Debug)]
+struct DeadlineReached;
+
+impl std::fmt::Display for DeadlineReached {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Deadline reached while the test was running")
+    }
+}
+
+impl std::error::Error for DeadlineReached {}
+
+/// A worker died mid-test while `--bail-on-worker-crash` was set, aborting the run.
+#[derive(Debug)]
+pub struct WorkerCrashed {
+    /// ID of the test the worker was running
+    pub test_id: String,
+    /// Last lines the worker wrote to stderr before exiting
+    pub stderr_tail: Option<String>,
+}
+
+impl std::fmt::Display for WorkerCrashed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Worker crashed while running {}", self.test_id)
+    }
+}
+
+impl std::error::Error for WorkerCrashed {}
+
+/// Captured stderr of a dead worker, if any, for use as a traceback.
+fn crash_traceback(error: &anyhow::Error) -> Option<String> {
+    error
+        .downcast_ref::<WorkerDied>()
+        .map(|died| died.stderr_tail.clone())
+        .filter(|tail| !tail.is_empty())
 }
 
 /// A single Python worker process.
 struct Worker {
     child: Child,
     stdin: std::process::ChildStdin,
-    stdout: std::process::ChildStdout,
+    /// Messages decoded from the worker's stdout by a background reader thread
+    messages: Receiver<Result<WorkerMessage>>,
+    /// Recent stderr lines, filled by a background reader thread
+    stderr_tail: Arc<Mutex<VecDeque<String>>>,
+    stderr_reader: Option<thread::JoinHandle<()>>,
 }
 
 impl Worker {
@@ -72,21 +191,60 @@ impl Worker {
             .args(["-u", "-c", WORKER_SCRIPT])
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::inherit()) // Let Python errors go to terminal
+            .stderr(Stdio::piped())
             .spawn()?;
 
         let stdin = child.stdin.take().expect("stdin not captured");
         let stdout = child.stdout.take().expect("stdout not captured");
+        let stderr = child.stderr.take().expect("stderr not captured");
+
+        // Read stdout on its own thread so waiting for a reply can time out
+        let (message_tx, messages) = unbounded();
+        thread::spawn(move || read_messages(stdout, message_tx));
+
+        // Drain stderr continuously so the worker never blocks on a full pipe,
+        // keeping the tail around in case the worker dies mid-test. Stray writes to
+        // fd 1 land here too, so tolerate bytes that aren't UTF-8.
+        let stderr_tail = Arc::new(Mutex::new(VecDeque::new()));
+        let tail = Arc::clone(&stderr_tail);
+        let stderr_reader = thread::spawn(move || {
+            for line in BufReader::new(stderr)
+                .split(b'\n')
+                .map_while(|line| line.ok())
+            {
+                let mut tail = tail.lock().unwrap();
+                if tail.len() == STDERR_TAIL_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(String::from_utf8_lossy(&line).into_owned());
+            }
+        });
 
         Ok(Self {
             child,
             stdin,
-            stdout,
+            messages,
+            stderr_tail,
+            stderr_reader: Some(stderr_reader),
         })
     }
 
-    fn send_request(&mut self, req: &WorkerRequest) -> Result<()> {
-        let data = rmp_serde::to_vec(req)?;
+    /// Wait for a dead worker to exit and return the tail of its stderr.
+    fn collect_stderr_tail(&mut self) -> String {
+        let _ = self.child.wait();
+        if let Some(reader) = self.stderr_reader.take() {
+            let _ = reader.join();
+        }
+        let tail = self.stderr_tail.lock().unwrap();
+        tail.iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn send_request<T: Serialize>(&mut self, req: &T) -> Result<()> {
+        // The worker reads fields by name, so structs must go out as maps
+        let data = rmp_serde::to_vec_named(req)?;
         let len = (data.len() as u32).to_le_bytes();
         self.stdin.write_all(&len)?;
         self.stdin.write_all(&data)?;
@@ -94,38 +252,91 @@ impl Worker {
         Ok(())
     }
 
-    fn read_response(&mut self) -> Result<WorkerResponse> {
-        let mut len_bytes = [0u8; 4];
-        if self.stdout.read_exact(&mut len_bytes).is_err() {
-            anyhow::bail!("Worker EOF (process died)");
+    fn read_response(&mut self, kill_at: Option<Instant>) -> Result<WorkerResponse> {
+        loop {
+            match self.read_message(kill_at)? {
+                WorkerMessage::Output(output) => forward_test_output(&output),
+                WorkerMessage::Response(resp) => return Ok(*resp),
+                // Late answer to a ping that already timed out
+                WorkerMessage::Pong { .. } => {}
+            }
         }
-        let len = u32::from_le_bytes(len_bytes) as usize;
+    }
 
-        let mut data = vec![0u8; len];
-        self.stdout.read_exact(&mut data)?;
+    /// Wait for the next message, giving up with [`DeadlineReached`] once `kill_at` passes.
+    fn read_message(&mut self, kill_at: Option<Instant>) -> Result<WorkerMessage> {
+        let msg = match kill_at {
+            Some(kill_at) => match self.messages.recv_deadline(kill_at) {
+                Ok(msg) => Some(msg),
+                Err(RecvTimeoutError::Timeout) => return Err(DeadlineReached.into()),
+                Err(RecvTimeoutError::Disconnected) => None,
+            },
+            None => self.messages.recv().ok(),
+        };
+        match msg {
+            Some(msg) => msg,
+            None => {
+                let stderr_tail = self.collect_stderr_tail();
+                Err(WorkerDied { stderr_tail }.into())
+            }
+        }
+    }
 
-        let resp: WorkerResponse = rmp_serde::from_slice(&data)?;
-        Ok(resp)
+    /// Check that the worker is alive and answers a ping within `timeout`.
+    fn ping(&mut self, timeout: Duration) -> bool {
+        if !self.is_alive() {
+            return false;
+        }
+        let id = next_request_id();
+        if self.send_request(&PingRequest { cmd: "ping", id }).is_err() {
+            return false;
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.messages.recv_timeout(remaining) {
+                Ok(Ok(WorkerMessage::Pong { id: pong_id, pong })) if pong_id == id => return pong,
+                // Stray output or a stale pong; keep waiting for ours
+                Ok(Ok(_)) => {}
+                Ok(Err(_)) | Err(_) => return false,
+            }
+        }
     }
 
-    fn run_test(&mut self, item: &TestItem, collect_coverage: bool) -> Result<TestResult> {
+    /// Whether the worker can take another test, pinging it if it has been idle.
+    fn is_responsive(&mut self, idle: Duration) -> bool {
+        self.is_alive() && (idle < IDLE_PING_AFTER || self.ping(PING_TIMEOUT))
+    }
+
+    fn run_test(&mut self, item: &TestItem, options: &RunOptions) -> Result<TestResult> {
         let request_id = next_request_id();
+        let collect_coverage = options.collect_coverage;
 
         let req = WorkerRequest {
             id: request_id,
-            file: item
-                .file
-                .canonicalize()
-                .unwrap_or(item.file.clone())
-                .to_string_lossy()
-                .into_owned(),
+            file: item.canonical_file().to_string_lossy().into_owned(),
             function: item.function.clone(),
             class: item.class.clone(),
+            params: item.params.iter().cloned().collect(),
+            doctest: item.is_doctest(),
             collect_coverage,
+            setup_timeout: options.fixture_setup_timeout.map(|t| t.as_secs_f64()),
+            assert_rewrite: options.assert_rewrite,
+            capture: options.capture,
+            async_mode: options.async_mode,
+            import_mode: options.import_mode,
+            warnings: options.warnings,
+            coverage_include: options.coverage_include.clone(),
+            coverage_exclude: options.coverage_exclude.clone(),
+            rootdir: item
+                .rootdir
+                .as_ref()
+                .map(|dir| dir.to_string_lossy().into_owned()),
         };
 
         self.send_request(&req)?;
-        let resp = self.read_response()?;
+        let resp = self.read_response(options.kill_deadline())?;
 
         let duration = Duration::from_secs_f64(resp.duration_sec);
 
@@ -144,6 +355,9 @@ impl Worker {
         let error = resp.error.map(|e| TestError {
             message: e.message,
             traceback: e.traceback,
+            fixture: e.fixture,
+            category: e.category,
+            exception_type: e.exception_type,
         });
 
         Ok(TestResult {
@@ -151,19 +365,14 @@ impl Worker {
             passed: resp.passed,
             duration,
             error,
-            skipped: false,
-            skip_reason: None,
+            errored: resp.errored,
+            skipped: resp.skipped,
+            skip_reason: resp.skip_reason,
+            skip_kind: resp.skipped.then_some(SkipKind::Runtime),
             coverage,
-            stdout: if resp.stdout.is_empty() {
-                None
-            } else {
-                Some(resp.stdout)
-            },
-            stderr: if resp.stderr.is_empty() {
-                None
-            } else {
-                Some(resp.stderr)
-            },
+            stdout: resp.stdout.filter(|s| !s.is_empty()),
+            stderr: resp.stderr.filter(|s| !s.is_empty()),
+            warnings: resp.warnings,
         })
     }
 
@@ -180,16 +389,92 @@ impl Worker {
         let _ = self.child.wait();
     }
 
+    /// Stop a worker that may be hung and can't be asked to shut down.
+    fn kill(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+
     fn is_alive(&mut self) -> bool {
         matches!(self.child.try_wait(), Ok(None))
     }
 }
 
+/// Decode length-prefixed messages from a worker's stdout until it closes.
+fn read_messages(mut stdout: std::process::ChildStdout, tx: Sender<Result<WorkerMessage>>) {
+    loop {
+        let mut len_bytes = [0u8; 4];
+        if stdout.read_exact(&mut len_bytes).is_err() {
+            // EOF: the worker exited
+            return;
+        }
+        let len = u32::from_le_bytes(len_bytes) as usize;
+
+        let mut data = vec![0u8; len];
+        if let Err(e) = stdout.read_exact(&mut data) {
+            let _ = tx.send(Err(e.into()));
+            return;
+        }
+
+        let msg = rmp_serde::from_slice(&data).map_err(anyhow::Error::from);
+        if tx.send(msg).is_err() {
+            return;
+        }
+    }
+}
+
 /// Task to be executed by a worker.
 struct Task {
     idx: usize,
     item: TestItem,
-    collect_coverage: bool,
+}
+
+/// Tests waiting for a worker.
+#[derive(Default)]
+struct TaskQueue {
+    tasks: VecDeque<Task>,
+    /// With file affinity, how many workers are working through each file
+    claimed: HashMap<PathBuf, usize>,
+}
+
+impl TaskQueue {
+    /// Next task for a worker whose last test came from `last_file`.
+    ///
+    /// With `affinity`, a worker keeps taking tests from the same file, so its
+    /// Python process can reuse the compiled module, and otherwise moves on to a
+    /// file no other worker is on.
+    fn next(&mut self, last_file: &mut Option<PathBuf>, affinity: bool) -> Option<Task> {
+        if !affinity {
+            return self.tasks.pop_front();
+        }
+        let pos = last_file
+            .as_ref()
+            .and_then(|file| self.tasks.iter().position(|t| &t.item.file == file))
+            .or_else(|| {
+                self.tasks
+                    .iter()
+                    .position(|t| !self.claimed.contains_key(&t.item.file))
+            })
+            .unwrap_or(0);
+        let task = self.tasks.remove(pos)?;
+        if last_file.as_ref() != Some(&task.item.file) {
+            if let Some(previous) = last_file.take() {
+                self.release(&previous);
+            }
+            *self.claimed.entry(task.item.file.clone()).or_default() += 1;
+            *last_file = Some(task.item.file.clone());
+        }
+        Some(task)
+    }
+
+    fn release(&mut self, file: &Path) {
+        if let Some(count) = self.claimed.get_mut(file) {
+            *count -= 1;
+            if *count == 0 {
+                self.claimed.remove(file);
+            }
+        }
+    }
 }
 
 /// Completed task result.
@@ -201,18 +486,29 @@ struct Completed {
 /// A pool of warm Python workers.
 pub struct WorkerPool {
     num_workers: usize,
+    /// Replace each worker with a fresh process after this many tests
+    max_tests_per_worker: Option<usize>,
 }
 
 impl WorkerPool {
     pub fn new(num_workers: usize) -> Self {
-        Self { num_workers }
+        Self {
+            num_workers,
+            max_tests_per_worker: None,
+        }
+    }
+
+    /// Recycle workers after `max` tests to bound memory growth and leaked state.
+    pub fn with_max_tests_per_worker(mut self, max: Option<usize>) -> Self {
+        self.max_tests_per_worker = max.filter(|&n| n > 0);
+        self
     }
 
     /// Run tests using the worker pool.
     pub fn run_tests<F>(
         &self,
         items: &[TestItem],
-        collect_coverage: bool,
+        options: &RunOptions,
         on_result: F,
     ) -> Result<Vec<TestResult>>
     where
@@ -226,20 +522,17 @@ impl WorkerPool {
         let num_workers = self.num_workers.min(items.len());
 
         // Create a shared work queue
-        let queue: Arc<(Mutex<std::collections::VecDeque<Task>>, Condvar)> = Arc::new((
-            Mutex::new(std::collections::VecDeque::new()),
-            Condvar::new(),
-        ));
+        let queue: Arc<(Mutex<TaskQueue>, Condvar)> =
+            Arc::new((Mutex::new(TaskQueue::default()), Condvar::new()));
 
         // Populate the queue
         {
             let (lock, cvar) = &*queue;
             let mut q = lock.lock().unwrap();
             for (idx, item) in items.iter().enumerate() {
-                q.push_back(Task {
+                q.tasks.push_back(Task {
                     idx,
                     item: item.clone(),
-                    collect_coverage,
                 });
             }
             cvar.notify_all();
@@ -249,14 +542,29 @@ impl WorkerPool {
         let (tx, rx) = bounded::<Completed>(items.len().max(1));
 
         // Spawn worker threads
+        let options = Arc::new(options.clone());
+        let crashed = Arc::new(Mutex::new(None));
+        let group_locks = Arc::new(GroupLocks::new(items, options.parallel_groups));
         let mut handles = Vec::with_capacity(num_workers);
         for _ in 0..num_workers {
             let queue = Arc::clone(&queue);
             let tx = tx.clone();
+            let options = Arc::clone(&options);
+            let crashed = Arc::clone(&crashed);
+            let group_locks = Arc::clone(&group_locks);
             let total_tasks = items.len();
+            let max_tests = self.max_tests_per_worker;
 
             handles.push(thread::spawn(move || {
-                worker_thread(queue, tx, total_tasks);
+                worker_thread(
+                    queue,
+                    tx,
+                    options,
+                    crashed,
+                    &group_locks,
+                    total_tasks,
+                    max_tests,
+                );
             }));
         }
 
@@ -264,24 +572,29 @@ impl WorkerPool {
         drop(tx);
 
         // Collect results with streaming callback
-        let on_result = Arc::new(on_result);
+        let mut emitter = ResultEmitter::new(&on_result, options.ordered_output);
         let mut results_by_idx: Vec<Option<TestResult>> = vec![None; items.len()];
         let mut received = 0;
 
         for completed in rx {
-            on_result(&completed.result);
+            emitter.emit(completed.idx, &completed.result);
             results_by_idx[completed.idx] = Some(completed.result);
             received += 1;
             if received >= items.len() {
                 break;
             }
         }
+        emitter.flush();
 
         // Wait for all worker threads to finish
         for handle in handles {
             let _ = handle.join();
         }
 
+        if let Some(crash) = crashed.lock().unwrap().take() {
+            return Err(crash.into());
+        }
+
         // Collect results in order
         let results = results_by_idx
             .into_iter()
@@ -294,12 +607,18 @@ impl WorkerPool {
                     error: Some(TestError {
                         message: "Test was not executed (worker pool error)".to_string(),
                         traceback: None,
+                        fixture: None,
+                        category: Some(ErrorCategory::Crash),
+                        exception_type: None,
                     }),
+                    errored: true,
                     skipped: false,
                     skip_reason: None,
+                    skip_kind: None,
                     coverage: None,
                     stdout: None,
                     stderr: None,
+                    warnings: Vec::new(),
                 })
             })
             .collect();
@@ -309,9 +628,13 @@ impl WorkerPool {
 }
 
 fn worker_thread(
-    queue: Arc<(Mutex<std::collections::VecDeque<Task>>, Condvar)>,
+    queue: Arc<(Mutex<TaskQueue>, Condvar)>,
     tx: Sender<Completed>,
+    options: Arc<RunOptions>,
+    crashed: Arc<Mutex<Option<WorkerCrashed>>>,
+    group_locks: &GroupLocks,
     total_tasks: usize,
+    max_tests: Option<usize>,
 ) {
     let mut worker = match Worker::spawn() {
         Ok(w) => w,
@@ -322,13 +645,19 @@ fn worker_thread(
     };
 
     let mut tasks_completed = 0;
+    // Tests run by the current worker process (reset when it is replaced)
+    let mut tests_on_worker = 0;
+    // When the current worker last finished a test (or was spawned)
+    let mut last_active = Instant::now();
+    // Source file of the last test taken, for --file-affinity
+    let mut last_file = None;
 
     loop {
         // Try to get a task from the queue
         let task = {
             let (lock, _cvar) = &*queue;
             let mut q = lock.lock().unwrap();
-            q.pop_front()
+            q.next(&mut last_file, options.file_affinity)
         };
 
         let Some(task) = task else {
@@ -336,16 +665,69 @@ fn worker_thread(
             break;
         };
 
-        // Execute the task
-        let result = match worker.run_test(&task.item, task.collect_coverage) {
+        if options.deadline_passed() {
+            let result = skipped_result(
+                &task.item,
+                SkipKind::Deadline,
+                "not started before --deadline",
+            );
+            if tx
+                .send(Completed {
+                    idx: task.idx,
+                    result,
+                })
+                .is_err()
+            {
+                break;
+            }
+            continue;
+        }
+
+        // Swap in a fresh process once this one has run its quota, or if it died or
+        // hung while idle, so the test isn't spent discovering that
+        let exhausted = max_tests.is_some_and(|max| tests_on_worker >= max);
+        if exhausted || !worker.is_responsive(last_active.elapsed()) {
+            if exhausted {
+                worker.shutdown();
+            } else {
+                worker.kill();
+            }
+            worker = match Worker::spawn() {
+                Ok(w) => w,
+                Err(e) => {
+                    eprintln!("Failed to respawn worker: {}", e);
+                    return;
+                }
+            };
+            tests_on_worker = 0;
+        }
+
+        // Execute the task, waiting for any other test in its groups to finish
+        let guards = group_locks.acquire(&task.item);
+        let result = match worker.run_test(&task.item, &options) {
             Ok(r) => r,
+            Err(e) if e.is::<DeadlineReached>() => {
+                // Killed mid-test; nothing starts after the deadline, so no respawn
+                worker.kill();
+                skipped_result(&task.item, SkipKind::Deadline, "killed at --deadline")
+            }
+            Err(e) if options.bail_on_worker_crash && e.is::<WorkerDied>() => {
+                // Strict mode: stop handing out tests and abort the run
+                crashed.lock().unwrap().get_or_insert(WorkerCrashed {
+                    test_id: task.item.id(),
+                    stderr_tail: crash_traceback(&e),
+                });
+                queue.0.lock().unwrap().tasks.clear();
+                return;
+            }
             Err(e) => {
                 // Worker might have died; try to respawn
                 if !worker.is_alive() {
                     if let Ok(new_worker) = Worker::spawn() {
                         worker = new_worker;
+                        tests_on_worker = 0;
                         // Retry the test
-                        match worker.run_test(&task.item, task.collect_coverage) {
+                        match worker.run_test(&task.item, &options) {
                             Ok(r) => r,
                             Err(e2) => TestResult {
                                 item: task.item.clone(),
@@ -353,13 +735,19 @@ fn worker_thread(
                                 duration: Duration::ZERO,
                                 error: Some(TestError {
                                     message: format!("Worker error after respawn: {}", e2),
-                                    traceback: None,
+                                    traceback: crash_traceback(&e2),
+                                    fixture: None,
+                                    category: Some(ErrorCategory::Crash),
+                                    exception_type: None,
                                 }),
+                                errored: false,
                                 skipped: false,
                                 skip_reason: None,
+                                skip_kind: None,
                                 coverage: None,
                                 stdout: None,
                                 stderr: None,
+                                warnings: Vec::new(),
                             },
                         }
                     } else {
@@ -369,13 +757,19 @@ fn worker_thread(
                             duration: Duration::ZERO,
                             error: Some(TestError {
                                 message: format!("Worker crashed and respawn failed: {}", e),
-                                traceback: None,
+                                traceback: crash_traceback(&e),
+                                fixture: None,
+                                category: Some(ErrorCategory::Crash),
+                                exception_type: None,
                             }),
+                            errored: false,
                             skipped: false,
                             skip_reason: None,
+                            skip_kind: None,
                             coverage: None,
                             stdout: None,
                             stderr: None,
+                            warnings: Vec::new(),
                         }
                     }
                 } else {
@@ -385,17 +779,24 @@ fn worker_thread(
                         duration: Duration::ZERO,
                         error: Some(TestError {
                             message: format!("Worker error: {}", e),
-                            traceback: None,
+                            traceback: crash_traceback(&e),
+                            fixture: None,
+                            category: Some(ErrorCategory::Crash),
+                            exception_type: None,
                         }),
+                        errored: false,
                         skipped: false,
                         skip_reason: None,
+                        skip_kind: None,
                         coverage: None,
                         stdout: None,
                         stderr: None,
+                        warnings: Vec::new(),
                     }
                 }
             }
         };
+        drop(guards);
 
         // Send result back
         if tx
@@ -409,6 +810,8 @@ fn worker_thread(
         }
 
         tasks_completed += 1;
+        tests_on_worker += 1;
+        last_active = Instant::now();
 
         // Early exit if we've done all tasks
         if tasks_completed >= total_tasks {
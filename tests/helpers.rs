@@ -95,6 +95,18 @@ impl TempProject {
     pub fn file_path(&self, name: &str) -> PathBuf {
         self.dir.path().join(name)
     }
+
+    /// Run git in the project and return its trimmed stdout.
+    pub fn git(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .args(["-c", "user.name=taut", "-c", "user.email=taut@example.com"])
+            .args(args)
+            .current_dir(self.dir.path())
+            .output()
+            .context("Failed to run git")?;
+        anyhow::ensure!(output.status.success(), "git {args:?} failed");
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
 }
 
 /// Result of running taut on a project.
@@ -184,8 +196,25 @@ pub fn run_taut(project: &TempProject, args: &[&str]) -> Result<TautResult> {
     run_taut_in_dir(project.path(), args)
 }
 
+/// Run taut in a project with extra environment variables set.
+pub fn run_taut_with_env(
+    project: &TempProject,
+    args: &[&str],
+    envs: &[(&str, &str)],
+) -> Result<TautResult> {
+    run_taut_in_dir_with_env(project.path(), args, envs)
+}
+
 /// Run taut in a specific directory.
 pub fn run_taut_in_dir(dir: &Path, args: &[&str]) -> Result<TautResult> {
+    run_taut_in_dir_with_env(dir, args, &[])
+}
+
+fn run_taut_in_dir_with_env(
+    dir: &Path,
+    args: &[&str],
+    envs: &[(&str, &str)],
+) -> Result<TautResult> {
     // Find the taut binary - either in target/debug or target/release
     let taut_binary = find_taut_binary()?;
 
@@ -193,6 +222,7 @@ pub fn run_taut_in_dir(dir: &Path, args: &[&str]) -> Result<TautResult> {
         .args(args)
         .current_dir(dir)
         .env("NO_COLOR", "1") // Disable colors for easier testing
+        .envs(envs.iter().copied())
         .output()
         .with_context(|| format!("Failed to run taut: {}", taut_binary.display()))?;
 
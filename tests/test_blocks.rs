@@ -408,6 +408,37 @@ fn extract_class_with_methods() {
     assert!(method_two.is_some());
 }
 
+#[test]
+fn extract_nested_class_methods_with_dotted_names() {
+    let code = &dedent(
+        r#"
+        class TestOuter:
+            class TestInner:
+                def test_inner(self):
+                    pass
+    "#,
+    );
+
+    let blocks = FileBlocks::from_source(code, "test.py").unwrap();
+
+    let outer = blocks
+        .blocks
+        .iter()
+        .find(|b| b.id.name == "TestOuter")
+        .unwrap();
+    assert_eq!((outer.id.start_line, outer.id.end_line), (1, 1));
+
+    let inner = blocks
+        .blocks
+        .iter()
+        .find(|b| b.id.name == "TestOuter.TestInner");
+    assert_eq!(inner.unwrap().id.kind, BlockKind::Class);
+
+    let method = blocks.get_block_for_line(4).unwrap();
+    assert_eq!(method.id.name, "TestOuter.TestInner.test_inner");
+    assert_eq!(method.id.kind, BlockKind::Method);
+}
+
 #[test]
 fn class_variables_before_methods_in_header() {
     let code = &dedent(
@@ -440,10 +471,6 @@ fn class_variables_before_methods_in_header() {
 
 #[test]
 fn class_variables_after_methods_belong_to_some_block() {
-    // BUG: Class variables defined after methods are orphaned
-    // They belong to NO block currently
-    //
-    // This test documents the issue.
     let code = &dedent(
         r#"
         class Foo:
@@ -451,21 +478,61 @@ fn class_variables_after_methods_belong_to_some_block() {
                 pass
 
             after_var = 1
+            other_var = 2
+
+            def other(self):
+                pass
+
+            last_var = 3
     "#,
     );
 
     let blocks = FileBlocks::from_source(code, "test.py").unwrap();
 
-    // Check what line 6 (after_var = 1) maps to
-    // In the current implementation, it maps to nothing
-    let line_6_block = blocks.get_block_for_line(6);
+    let after_var = blocks.get_block_for_line(5).unwrap();
+    assert_eq!(after_var.id.kind, BlockKind::Class);
+    assert_eq!(after_var.id.name, "Foo.<body_0>");
+    assert_eq!((after_var.id.start_line, after_var.id.end_line), (5, 6));
+    assert_eq!(
+        blocks.get_block_for_line(6).unwrap().id.name,
+        "Foo.<body_0>"
+    );
+
+    let last_var = blocks.get_block_for_line(11).unwrap();
+    assert_eq!(last_var.id.name, "Foo.<body_1>");
 
-    // This SHOULD map to something (either the class or a separate block)
-    // Currently it's orphaned
-    if line_6_block.is_none() {
-        // Document the bug but don't fail (yet)
-        eprintln!("BUG: Class variable after method is orphaned (maps to no block)");
-    }
+    // Methods keep their own blocks
+    assert_eq!(blocks.get_block_for_line(8).unwrap().id.name, "Foo.other");
+}
+
+#[test]
+fn class_variable_after_method_is_checksummed() {
+    let before = &dedent(
+        r#"
+        class Foo:
+            def method(self):
+                pass
+
+            after_var = 1
+    "#,
+    );
+    let after = &dedent(
+        r#"
+        class Foo:
+            def method(self):
+                pass
+
+            after_var = 2
+    "#,
+    );
+
+    let blocks_before = FileBlocks::from_source(before, "test.py").unwrap();
+    let blocks_after = FileBlocks::from_source(after, "test.py").unwrap();
+
+    assert_ne!(
+        blocks_before.get_block_for_line(5).unwrap().checksum,
+        blocks_after.get_block_for_line(5).unwrap().checksum
+    );
 }
 
 #[test]
@@ -560,11 +627,7 @@ fn imports_grouped_into_single_block() {
 }
 
 #[test]
-fn scattered_imports_behavior() {
-    // BUG: When imports are scattered, the import block spans
-    // the entire range, including intermediate code
-    //
-    // This test documents the issue.
+fn scattered_imports_split_around_intermediate_code() {
     let code = &dedent(
         r#"
         import os
@@ -577,22 +640,77 @@ fn scattered_imports_behavior() {
 
     let blocks = FileBlocks::from_source(code, "test.py").unwrap();
 
-    let import_block = blocks
+    let import_ranges: Vec<_> = blocks
         .blocks
         .iter()
-        .find(|b| b.id.kind == BlockKind::Import);
-
-    if let Some(block) = import_block {
-        // The import block currently spans from line 1 to line 5
-        // This means x = 1 is included in the import block checksum
-        // which is probably not desired
-        if block.id.end_line > 2 {
-            eprintln!(
-                "BUG: Scattered imports create block from line {} to {} (includes intermediate code)",
-                block.id.start_line, block.id.end_line
-            );
-        }
-    }
+        .filter(|b| b.id.kind == BlockKind::Import)
+        .map(|b| (b.id.start_line, b.id.end_line))
+        .collect();
+    assert_eq!(import_ranges, vec![(1, 1), (5, 5)]);
+
+    // The assignment belongs to top-level code, not an import block
+    let x_block = blocks.get_block_for_line(3).unwrap();
+    assert_eq!(x_block.id.kind, BlockKind::TopLevel);
+}
+
+#[test]
+fn imports_separated_by_one_blank_line_stay_grouped() {
+    let code = &dedent(
+        r#"
+        import os
+        import sys
+
+        from pathlib import Path
+
+
+        from typing import List
+    "#,
+    );
+
+    let blocks = FileBlocks::from_source(code, "test.py").unwrap();
+
+    let import_ranges: Vec<_> = blocks
+        .blocks
+        .iter()
+        .filter(|b| b.id.kind == BlockKind::Import)
+        .map(|b| (b.id.start_line, b.id.end_line))
+        .collect();
+    assert_eq!(import_ranges, vec![(1, 4), (7, 7)]);
+}
+
+#[test]
+fn changing_code_between_imports_leaves_import_blocks_unchanged() {
+    let before = &dedent(
+        r#"
+        import os
+        x = 1
+        import sys
+    "#,
+    );
+    let after = &dedent(
+        r#"
+        import os
+        x = 2
+        import sys
+    "#,
+    );
+
+    let blocks_before = FileBlocks::from_source(before, "test.py").unwrap();
+    let blocks_after = FileBlocks::from_source(after, "test.py").unwrap();
+
+    let import_checksums = |blocks: &FileBlocks| -> Vec<String> {
+        blocks
+            .blocks
+            .iter()
+            .filter(|b| b.id.kind == BlockKind::Import)
+            .map(|b| b.checksum.clone())
+            .collect()
+    };
+    assert_eq!(import_checksums(&blocks_before).len(), 2);
+    assert_eq!(
+        import_checksums(&blocks_before),
+        import_checksums(&blocks_after)
+    );
 }
 
 #[test]
@@ -620,10 +738,15 @@ fn conditional_import_not_in_import_block() {
         .unwrap();
 
     // Import block should only cover "import os" (line 1)
-    assert!(
-        import_block.id.end_line <= 2,
+    assert_eq!(
+        (import_block.id.start_line, import_block.id.end_line),
+        (1, 1),
         "Import block should not include conditional import"
     );
+    assert_eq!(
+        blocks.get_block_for_line(4).unwrap().id.kind,
+        BlockKind::TopLevel
+    );
 }
 
 // =============================================================================
@@ -652,7 +775,7 @@ fn top_level_code_extracted() {
 }
 
 #[test]
-fn if_name_main_is_top_level() {
+fn if_name_main_is_its_own_block() {
     let code = &dedent(
         r#"
         def foo():
@@ -665,14 +788,21 @@ fn if_name_main_is_top_level() {
 
     let blocks = FileBlocks::from_source(code, "test.py").unwrap();
 
-    // The if __name__ block should be extracted as top-level
-    let top_level: Vec<_> = blocks
+    // The if __name__ block gets its own kind so tests never depend on it
+    let main: Vec<_> = blocks
         .blocks
         .iter()
-        .filter(|b| b.id.kind == BlockKind::TopLevel)
+        .filter(|b| b.id.kind == BlockKind::Main)
         .collect();
 
-    assert!(!top_level.is_empty());
+    assert_eq!(main.len(), 1);
+    assert_eq!((main[0].id.start_line, main[0].id.end_line), (4, 5));
+    assert!(
+        !blocks
+            .blocks
+            .iter()
+            .any(|b| b.id.kind == BlockKind::TopLevel)
+    );
 }
 
 // =============================================================================
@@ -18,8 +18,9 @@ use tempfile::TempDir;
 
 use helpers::dedent;
 use taut::blocks::FileBlocks;
-use taut::depdb::{DependencyDatabase, TestRunDecision};
-use taut::discovery::TestItem;
+use taut::cache::FileHealth;
+use taut::depdb::{CURRENT_VERSION, DependencyDatabase, TestRunDecision};
+use taut::discovery::{TestItem, TestKind};
 
 // =============================================================================
 // Test Selection - Basic Cases
@@ -34,7 +35,11 @@ fn new_test_always_runs() {
         function: "test_new".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let decision = depdb.needs_run(&test);
@@ -60,7 +65,11 @@ fn failed_test_always_reruns() -> Result<()> {
         function: "test_fail".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     // Record that the test failed
@@ -98,7 +107,11 @@ fn unchanged_passing_test_skips() -> Result<()> {
         function: "test_pass".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     // Record that the test passed with some coverage
@@ -148,7 +161,11 @@ fn changed_dependency_reruns() -> Result<()> {
         function: "test_uses_helper".to_string(),
         class: None,
         line: 5,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     // Record coverage: test touched lines 1-2 (helper) and 5-6 (test)
@@ -176,7 +193,7 @@ fn changed_dependency_reruns() -> Result<()> {
     let decision = depdb.needs_run(&test);
 
     assert!(
-        matches!(decision, TestRunDecision::DependencyChanged),
+        matches!(decision, TestRunDecision::DependencyChanged(_)),
         "Test should re-run when dependency changed, got {:?}",
         decision
     );
@@ -184,10 +201,317 @@ fn changed_dependency_reruns() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn changed_dependency_names_the_block() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_foo.py");
+    let code_v1 = &dedent(
+        r#"
+        def helper():
+            return 1
+
+        def test_uses_helper():
+            assert helper() == 1
+    "#,
+    );
+    fs::write(&test_file, code_v1)?;
+
+    let mut depdb = DependencyDatabase::default();
+    let file_blocks_v1 = FileBlocks::from_file(&test_file)?;
+    depdb.update_blocks(&file_blocks_v1);
+
+    let mut block_index = HashMap::new();
+    block_index.insert(test_file.canonicalize()?, file_blocks_v1);
+
+    let test = TestItem {
+        file: test_file.canonicalize()?,
+        function: "test_uses_helper".to_string(),
+        class: None,
+        line: 5,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    let mut coverage = HashMap::new();
+    coverage.insert(test_file.canonicalize()?, vec![1, 2, 5, 6]);
+    depdb.record_test_coverage(&test, &coverage, true, &block_index);
+
+    // Same line span, different body: only helper's checksum changes
+    fs::write(&test_file, code_v1.replace("return 1", "return 2"))?;
+    depdb.update_blocks(&FileBlocks::from_file(&test_file)?);
+
+    let decision = depdb.needs_run(&test);
+    match decision {
+        TestRunDecision::DependencyChanged(ref block) => assert_eq!(block.name, "helper"),
+        ref other => panic!("expected DependencyChanged, got {:?}", other),
+    }
+    assert_eq!(decision.explanation(), "helper() in test_foo.py changed");
+
+    Ok(())
+}
+
+#[test]
+fn editing_main_guard_does_not_rerun_tests() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_main.py");
+    let code_v1 = &dedent(
+        r#"
+        def test_one():
+            assert True
+
+        if __name__ == "__main__":
+            test_one()
+    "#,
+    );
+    fs::write(&test_file, code_v1)?;
+
+    let mut depdb = DependencyDatabase::default();
+    let file_blocks_v1 = FileBlocks::from_file(&test_file)?;
+    depdb.update_blocks(&file_blocks_v1);
+
+    let mut block_index = HashMap::new();
+    block_index.insert(test_file.canonicalize()?, file_blocks_v1);
+
+    let test = TestItem {
+        file: test_file.canonicalize()?,
+        function: "test_one".to_string(),
+        class: None,
+        line: 1,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    // Importing the module evaluates the guard's condition (line 4)
+    let mut coverage = HashMap::new();
+    coverage.insert(test_file.canonicalize()?, vec![1, 2, 4]);
+    depdb.record_test_coverage(&test, &coverage, true, &block_index);
+
+    fs::write(
+        &test_file,
+        code_v1.replace("    test_one()", "    test_one()\n    print(\"done\")"),
+    )?;
+    depdb.update_blocks(&FileBlocks::from_file(&test_file)?);
+
+    let decision = depdb.needs_run(&test);
+    assert!(
+        matches!(decision, TestRunDecision::CanSkip),
+        "Editing only the __main__ block should not re-run tests, got {:?}",
+        decision
+    );
+
+    Ok(())
+}
+
+#[test]
+fn changed_module_constant_reruns_tests_using_the_module() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let config_file = tmp.path().join("config.py");
+    let test_file = tmp.path().join("test_config.py");
+    let config_v1 = &dedent(
+        r#"
+        MAX = 10
+
+        def limit():
+            return MAX
+    "#,
+    );
+    fs::write(&config_file, config_v1)?;
+    fs::write(
+        &test_file,
+        dedent(
+            r#"
+            from config import limit
+
+            def test_limit():
+                assert limit() == 10
+        "#,
+        ),
+    )?;
+    let config_file = config_file.canonicalize()?;
+    let test_file = test_file.canonicalize()?;
+
+    let mut depdb = DependencyDatabase::default();
+    let mut block_index = HashMap::new();
+    for file in [&config_file, &test_file] {
+        let file_blocks = FileBlocks::from_file(file)?;
+        depdb.update_blocks(&file_blocks);
+        block_index.insert(file.clone(), file_blocks);
+    }
+
+    let test = TestItem {
+        file: test_file.clone(),
+        function: "test_limit".to_string(),
+        class: None,
+        line: 3,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    // config.py was imported before coverage started, so `MAX = 10` (line 1)
+    // wasn't recorded; only the body of limit() was
+    let mut coverage = HashMap::new();
+    coverage.insert(config_file.clone(), vec![4]);
+    coverage.insert(test_file.clone(), vec![4]);
+    depdb.record_test_coverage(&test, &coverage, true, &block_index);
+    assert!(matches!(depdb.needs_run(&test), TestRunDecision::CanSkip));
+
+    fs::write(&config_file, config_v1.replace("MAX = 10", "MAX = 20"))?;
+    depdb.update_blocks(&FileBlocks::from_file(&config_file)?);
+
+    let decision = depdb.needs_run(&test);
+    assert!(
+        matches!(decision, TestRunDecision::DependencyChanged(_)),
+        "Changing a module constant should re-run tests using the module, got {:?}",
+        decision
+    );
+
+    Ok(())
+}
+
+#[test]
+fn defining_a_function_at_import_does_not_depend_on_it() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let helpers_file = tmp.path().join("helpers.py");
+    let helpers_v1 = &dedent(
+        r#"
+        def helper_a():
+            return 1
+
+        @staticmethod
+        def helper_b():
+            return 2
+    "#,
+    );
+    fs::write(&helpers_file, helpers_v1)?;
+    let helpers_file = helpers_file.canonicalize()?;
+
+    let mut depdb = DependencyDatabase::default();
+    let file_blocks = FileBlocks::from_file(&helpers_file)?;
+    depdb.update_blocks(&file_blocks);
+    let mut block_index = HashMap::new();
+    block_index.insert(helpers_file.clone(), file_blocks);
+
+    let test = TestItem {
+        file: tmp.path().join("test_b.py"),
+        function: "test_b".to_string(),
+        class: None,
+        line: 1,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    // Importing helpers.py runs both `def`s (and the decorator); only helper_b's body ran
+    let mut coverage = HashMap::new();
+    coverage.insert(helpers_file.clone(), vec![1, 4, 5, 6]);
+    depdb.record_test_coverage(&test, &coverage, true, &block_index);
+
+    fs::write(&helpers_file, helpers_v1.replace("return 1", "return 10"))?;
+    depdb.update_blocks(&FileBlocks::from_file(&helpers_file)?);
+    let decision = depdb.needs_run(&test);
+    assert!(
+        matches!(decision, TestRunDecision::CanSkip),
+        "Changing a function the test never called should not re-run it, got {:?}",
+        decision
+    );
+
+    fs::write(&helpers_file, helpers_v1.replace("return 2", "return 20"))?;
+    depdb.update_blocks(&FileBlocks::from_file(&helpers_file)?);
+    let decision = depdb.needs_run(&test);
+    assert!(
+        matches!(decision, TestRunDecision::DependencyChanged(_)),
+        "Changing a function the test called should re-run it, got {:?}",
+        decision
+    );
+
+    Ok(())
+}
+
 // =============================================================================
 // BUG: Line Number Fragility
 // =============================================================================
 
+#[test]
+fn changed_trailing_class_variable_reruns() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_foo.py");
+    let code_v1 = &dedent(
+        r#"
+        class Config:
+            def describe(self):
+                return "config"
+
+            retries = 3
+
+        def test_retries():
+            assert Config.retries == 3
+    "#,
+    );
+    fs::write(&test_file, code_v1)?;
+
+    let mut depdb = DependencyDatabase::default();
+
+    let file_blocks_v1 = FileBlocks::from_file(&test_file)?;
+    depdb.update_blocks(&file_blocks_v1);
+
+    let mut block_index = HashMap::new();
+    block_index.insert(test_file.canonicalize()?, file_blocks_v1);
+
+    let test = TestItem {
+        file: test_file.canonicalize()?,
+        function: "test_retries".to_string(),
+        class: None,
+        line: 7,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    // Class body executes at import: lines 1, 2 and 5, then the test body
+    let mut coverage = HashMap::new();
+    coverage.insert(test_file.canonicalize()?, vec![1, 2, 5, 7, 8]);
+    depdb.record_test_coverage(&test, &coverage, true, &block_index);
+
+    let code_v2 = &dedent(
+        r#"
+        class Config:
+            def describe(self):
+                return "config"
+
+            retries = 5
+
+        def test_retries():
+            assert Config.retries == 3
+    "#,
+    );
+    fs::write(&test_file, code_v2)?;
+
+    let file_blocks_v2 = FileBlocks::from_file(&test_file)?;
+    depdb.update_blocks(&file_blocks_v2);
+
+    let decision = depdb.needs_run(&test);
+    assert!(
+        matches!(decision, TestRunDecision::DependencyChanged(_)),
+        "Test should re-run when a trailing class variable changed, got {:?}",
+        decision
+    );
+
+    Ok(())
+}
+
 #[test]
 fn adding_blank_line_should_not_invalidate_cache() -> Result<()> {
     // CRITICAL BUG: Currently, BlockId includes start_line and end_line.
@@ -224,7 +548,11 @@ fn adding_blank_line_should_not_invalidate_cache() -> Result<()> {
         function: "test_foo".to_string(),
         class: None,
         line: 4,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let mut coverage = HashMap::new();
@@ -259,7 +587,11 @@ fn adding_blank_line_should_not_invalidate_cache() -> Result<()> {
         function: "test_foo".to_string(),
         class: None,
         line: 5, // Line number changed
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let decision = depdb.needs_run(&test_v2);
@@ -298,7 +630,11 @@ fn adding_comment_should_not_invalidate_cache() -> Result<()> {
         function: "test_foo".to_string(),
         class: None,
         line: 2,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let mut coverage = HashMap::new();
@@ -317,7 +653,11 @@ fn adding_comment_should_not_invalidate_cache() -> Result<()> {
         function: "test_foo".to_string(),
         class: None,
         line: 3,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let decision = depdb.needs_run(&test_v2);
@@ -366,7 +706,11 @@ fn reordering_functions_with_same_content_should_not_invalidate() -> Result<()>
         function: "test_uses_a".to_string(),
         class: None,
         line: 8,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let mut coverage = HashMap::new();
@@ -396,7 +740,11 @@ fn reordering_functions_with_same_content_should_not_invalidate() -> Result<()>
         function: "test_uses_a".to_string(),
         class: None,
         line: 8,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let decision = depdb.needs_run(&test_v2);
@@ -418,8 +766,8 @@ fn reordering_functions_with_same_content_should_not_invalidate() -> Result<()>
 
 #[test]
 fn relative_and_absolute_paths_should_match() -> Result<()> {
-    // BUG: Discovery may use relative paths while selection uses absolute paths
-    // This can cause tests to always appear as "new"
+    // Discovery may use relative paths while selection uses absolute paths;
+    // both must resolve to the same dependency key.
 
     let tmp = TempDir::new()?;
     let test_file = tmp.path().join("test_foo.py");
@@ -439,7 +787,11 @@ fn relative_and_absolute_paths_should_match() -> Result<()> {
         function: "test_ok".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let mut coverage = HashMap::new();
@@ -452,18 +804,66 @@ fn relative_and_absolute_paths_should_match() -> Result<()> {
         function: "test_ok".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let decision_abs = depdb.needs_run(&test_abs);
     let decision_rel = depdb.needs_run(&test_rel);
 
-    // Both should give the same result
-    // BUG: Currently they might differ
-    assert_eq!(
-        decision_abs.should_run(),
-        decision_rel.should_run(),
-        "Relative and absolute paths should give same result"
+    assert!(matches!(decision_abs, TestRunDecision::CanSkip));
+    assert!(
+        matches!(decision_rel, TestRunDecision::CanSkip),
+        "Relative and absolute paths should give same result, got {:?}",
+        decision_rel
+    );
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn coverage_reported_through_symlink_maps_to_canonical_blocks() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let real_dir = tmp.path().join("real");
+    fs::create_dir(&real_dir)?;
+    fs::write(real_dir.join("test_foo.py"), "def test_ok(): pass\n")?;
+    let link_dir = tmp.path().join("link");
+    std::os::unix::fs::symlink(&real_dir, &link_dir)?;
+    let linked_file = link_dir.join("test_foo.py");
+
+    let mut depdb = DependencyDatabase::default();
+    let file_blocks = FileBlocks::from_file(&linked_file)?;
+    depdb.update_blocks(&file_blocks);
+
+    let mut block_index = HashMap::new();
+    block_index.insert(linked_file.canonicalize()?, file_blocks);
+
+    let test = TestItem {
+        file: linked_file.clone(),
+        function: "test_ok".to_string(),
+        class: None,
+        line: 1,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    // The interpreter reports the path it imported, not the canonical one
+    let mut coverage = HashMap::new();
+    coverage.insert(linked_file.clone(), vec![1]);
+    depdb.record_test_coverage(&test, &coverage, true, &block_index);
+
+    let decision = depdb.needs_run(&test);
+    assert!(
+        matches!(decision, TestRunDecision::CanSkip),
+        "Symlinked coverage should map to canonical blocks, got {:?}",
+        decision
     );
 
     Ok(())
@@ -495,7 +895,11 @@ fn different_files_same_function_name() -> Result<()> {
         function: "test_common".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let test_b = TestItem {
@@ -503,7 +907,11 @@ fn different_files_same_function_name() -> Result<()> {
         function: "test_common".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     // Record coverage for test_a only
@@ -558,7 +966,11 @@ fn same_method_name_different_classes() -> Result<()> {
         function: "test_common".to_string(),
         class: Some("TestAlpha".to_string()),
         line: 2,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let test_beta = TestItem {
@@ -566,7 +978,11 @@ fn same_method_name_different_classes() -> Result<()> {
         function: "test_common".to_string(),
         class: Some("TestBeta".to_string()),
         line: 6,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     // Record coverage for TestAlpha.test_common only
@@ -595,9 +1011,7 @@ fn same_method_name_different_classes() -> Result<()> {
 
 #[test]
 fn save_and_load_roundtrip() -> Result<()> {
-    // This test requires setting up a temp cache directory
-    // For now, just verify the basic API works
-
+    let tmp = TempDir::new()?;
     let mut depdb = DependencyDatabase::default();
 
     // Add some data
@@ -606,15 +1020,247 @@ fn save_and_load_roundtrip() -> Result<()> {
         function: "test_ok".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let block_index = HashMap::new();
     depdb.record_test_coverage(&test, &HashMap::new(), true, &block_index);
 
-    // Save and load would normally persist to disk
-    // Just verify it doesn't panic
-    depdb.save();
+    depdb.save(tmp.path());
+
+    let loaded = DependencyDatabase::load(tmp.path());
+    assert_eq!(loaded.stats().total_tests, depdb.stats().total_tests);
+
+    Ok(())
+}
+
+#[test]
+fn old_format_database_is_discarded_with_notice() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let path = tmp.path().join("depdb.json");
+    // Pre-versioning layout: no `version` field, tests keyed by bare names
+    fs::write(
+        &path,
+        r#"{"blocks": {"foo": "abc"}, "tests": {"test_foo": {"deps": [], "ok": true}}}"#,
+    )?;
+
+    let (depdb, notice) = DependencyDatabase::load_from(&path);
+
+    let stats = depdb.stats();
+    assert_eq!(stats.total_blocks, 0);
+    assert_eq!(stats.total_tests, 0);
+    let notice = notice.expect("expected a reset notice");
+    assert!(
+        notice.contains("format upgrade"),
+        "Notice should explain the reset: {}",
+        notice
+    );
+
+    Ok(())
+}
+
+#[test]
+fn previous_version_database_is_discarded_with_notice() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let path = tmp.path().join("depdb.json");
+    fs::write(
+        &path,
+        format!(
+            r#"{{"version": {}, "blocks": {{}}, "tests": {{}}}}"#,
+            CURRENT_VERSION - 1
+        ),
+    )?;
+
+    let (_, notice) = DependencyDatabase::load_from(&path);
+    assert!(notice.is_some(), "Expected a reset notice");
+
+    Ok(())
+}
+
+#[test]
+fn current_format_database_loads_without_notice() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let path = tmp.path().join("depdb.json");
+    fs::write(
+        &path,
+        format!(
+            r#"{{"version": {}, "blocks": {{}}, "tests": {{}}}}"#,
+            CURRENT_VERSION
+        ),
+    )?;
+
+    let (_, notice) = DependencyDatabase::load_from(&path);
+    assert!(notice.is_none(), "Unexpected notice: {:?}", notice);
+
+    Ok(())
+}
+
+#[test]
+fn truncated_database_is_reported_and_repaired() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let path = tmp.path().join("depdb.json");
+
+    let mut depdb = DependencyDatabase::default();
+    for i in 0..5 {
+        let test = TestItem {
+            file: PathBuf::from("/tmp/test_foo.py"),
+            function: format!("test_{i}"),
+            class: None,
+            line: i + 1,
+            decorator_line: None,
+            markers: vec![],
+            params: vec![],
+            kind: TestKind::Function,
+            rootdir: None,
+        };
+        depdb.record_test_coverage(&test, &HashMap::new(), true, &HashMap::new());
+    }
+    depdb.save_to(&path)?;
+    assert_eq!(DependencyDatabase::verify(&path), FileHealth::Valid);
+
+    // A crash part-way through the last entry
+    let data = fs::read(&path)?;
+    fs::write(&path, &data[..data.len() - 10])?;
+
+    assert!(
+        matches!(DependencyDatabase::verify(&path), FileHealth::Corrupt(_)),
+        "Truncated database should be reported as corrupt"
+    );
+
+    let kept = DependencyDatabase::repair(&path)?;
+    assert_eq!(kept, 4, "Every complete entry should survive the repair");
+    assert_eq!(DependencyDatabase::verify(&path), FileHealth::Valid);
+    let (repaired, notice) = DependencyDatabase::load_from(&path);
+    assert!(notice.is_none(), "Unexpected notice: {:?}", notice);
+    assert_eq!(repaired.stats().total_tests, 4);
+
+    Ok(())
+}
+
+#[test]
+fn save_replaces_database_atomically() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let path = tmp.path().join("depdb.json");
+    fs::write(&path, "previous")?;
+
+    // A link to the old file only keeps its content if `save_to` renames a
+    // new file into place instead of writing over the old one
+    let old = tmp.path().join("old.json");
+    fs::hard_link(&path, &old)?;
+
+    DependencyDatabase::default().save_to(&path)?;
+
+    assert_eq!(fs::read_to_string(&old)?, "previous");
+    assert_eq!(DependencyDatabase::verify(&path), FileHealth::Valid);
+    let leftovers: Vec<_> = fs::read_dir(tmp.path())?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+        .collect();
+    assert!(leftovers.is_empty(), "Temporary file left behind");
+
+    Ok(())
+}
+
+#[test]
+fn concurrent_saves_keep_both_tests() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let path = tmp.path().join("depdb.json");
+
+    // Both load before either saves, like a watch run next to a manual run
+    let barrier = std::sync::Barrier::new(2);
+    std::thread::scope(|scope| {
+        for name in ["test_watch", "test_manual"] {
+            let (path, barrier) = (&path, &barrier);
+            scope.spawn(move || {
+                let (mut depdb, _) = DependencyDatabase::load_from(path);
+                barrier.wait();
+                let test = TestItem {
+                    file: PathBuf::from("/tmp/test_foo.py"),
+                    function: name.to_string(),
+                    class: None,
+                    line: 1,
+                    decorator_line: None,
+                    markers: vec![],
+                    params: vec![],
+                    kind: TestKind::Function,
+                    rootdir: None,
+                };
+                depdb.record_test_coverage(&test, &HashMap::new(), true, &HashMap::new());
+                depdb.save_to(path).unwrap();
+            });
+        }
+    });
+
+    let content = fs::read_to_string(&path)?;
+    serde_json::from_str::<serde_json::Value>(&content)?;
+    let (depdb, notice) = DependencyDatabase::load_from(&path);
+    assert!(notice.is_none(), "Unexpected notice: {:?}", notice);
+    assert_eq!(depdb.stats().total_tests, 2, "Both saves should survive");
+
+    Ok(())
+}
+
+#[test]
+fn outcome_flip_without_code_change_is_flaky() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_foo.py");
+    let code_v1 = &dedent(
+        r#"
+        def helper():
+            return 1
+
+        def test_uses_helper():
+            assert helper() == 1
+    "#,
+    );
+    fs::write(&test_file, code_v1)?;
+
+    let mut depdb = DependencyDatabase::default();
+    let file_blocks = FileBlocks::from_file(&test_file)?;
+    depdb.update_blocks(&file_blocks);
+    let mut block_index = HashMap::new();
+    block_index.insert(test_file.canonicalize()?, file_blocks);
+
+    let test = TestItem {
+        file: test_file.canonicalize()?,
+        function: "test_uses_helper".to_string(),
+        class: None,
+        line: 5,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+    let mut coverage = HashMap::new();
+    coverage.insert(test_file.canonicalize()?, vec![1, 2, 5, 6]);
+
+    // Pass, then fail with identical checksums
+    depdb.record_test_coverage(&test, &coverage, true, &block_index);
+    assert!(depdb.flaky_tests().is_empty());
+    depdb.record_test_coverage(&test, &coverage, false, &block_index);
+
+    let flaky = depdb.flaky_tests();
+    assert_eq!(flaky.len(), 1);
+    assert!(flaky[0].test.ends_with("test_foo.py::test_uses_helper"));
+    assert_eq!((flaky[0].passes, flaky[0].failures), (1, 1));
+    assert_eq!(flaky[0].history, vec![true, false]);
+
+    // Once the code changes, the new outcome is explained by the edit
+    fs::write(&test_file, code_v1.replace("return 1", "return 2"))?;
+    let file_blocks_v2 = FileBlocks::from_file(&test_file)?;
+    depdb.update_blocks(&file_blocks_v2);
+    block_index.insert(test_file.canonicalize()?, file_blocks_v2);
+    depdb.record_test_coverage(&test, &coverage, true, &block_index);
+
+    assert!(
+        depdb.flaky_tests().is_empty(),
+        "history should restart after a dependency changed"
+    );
 
     Ok(())
 }
@@ -633,7 +1279,11 @@ fn stats_accurate() -> Result<()> {
         function: "test_a".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let test_b = TestItem {
@@ -641,7 +1291,11 @@ fn stats_accurate() -> Result<()> {
         function: "test_b".to_string(),
         class: None,
         line: 2,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     // Record test_a as passed, test_b as failed
@@ -657,6 +1311,182 @@ fn stats_accurate() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn prune_removes_entries_for_deleted_files() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let kept_file = tmp.path().join("test_kept.py");
+    let deleted_file = tmp.path().join("test_deleted.py");
+    fs::write(&kept_file, "def test_kept(): pass\n")?;
+    fs::write(&deleted_file, "def test_gone(): pass\n")?;
+
+    let mut depdb = DependencyDatabase::default();
+    let mut block_index = HashMap::new();
+    for file in [&kept_file, &deleted_file] {
+        let file_blocks = FileBlocks::from_file(&file.canonicalize()?)?;
+        depdb.update_blocks(&file_blocks);
+        block_index.insert(file.canonicalize()?, file_blocks);
+    }
+
+    let kept = TestItem {
+        file: kept_file.canonicalize()?,
+        function: "test_kept".to_string(),
+        class: None,
+        line: 1,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+    let gone = TestItem {
+        file: deleted_file.canonicalize()?,
+        function: "test_gone".to_string(),
+        class: None,
+        line: 1,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    let mut coverage = HashMap::new();
+    coverage.insert(kept_file.canonicalize()?, vec![1]);
+    depdb.record_test_coverage(&kept, &coverage, true, &block_index);
+    depdb.record_test_coverage(&gone, &HashMap::new(), true, &block_index);
+
+    fs::remove_file(&deleted_file)?;
+
+    let stats = depdb.prune();
+    assert_eq!(stats.removed_tests, 1);
+    assert_eq!(stats.removed_blocks, 1);
+
+    assert!(
+        matches!(depdb.needs_run(&gone), TestRunDecision::NeverRun),
+        "Pruned test should be forgotten"
+    );
+    assert!(
+        matches!(depdb.needs_run(&kept), TestRunDecision::CanSkip),
+        "Surviving test should keep its history"
+    );
+    assert_eq!(depdb.stats().total_tests, 1);
+
+    Ok(())
+}
+
+#[test]
+fn export_and_import_relocate_paths_to_new_root() -> Result<()> {
+    let source = "def test_ok(): pass\n";
+    let machine_a = TempDir::new()?;
+    let machine_b = TempDir::new()?;
+    let root_a = machine_a.path().canonicalize()?;
+    let root_b = machine_b.path().canonicalize()?;
+    fs::write(root_a.join("test_foo.py"), source)?;
+    fs::write(root_b.join("test_foo.py"), source)?;
+
+    let item = |root: &PathBuf| TestItem {
+        file: root.join("test_foo.py"),
+        function: "test_ok".to_string(),
+        class: None,
+        line: 1,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    let mut depdb = DependencyDatabase::default();
+    let file_blocks = FileBlocks::from_file(&root_a.join("test_foo.py"))?;
+    depdb.update_blocks(&file_blocks);
+    let mut block_index = HashMap::new();
+    block_index.insert(root_a.join("test_foo.py"), file_blocks);
+    let mut coverage = HashMap::new();
+    coverage.insert(root_a.join("test_foo.py"), vec![1]);
+    depdb.record_test_coverage(&item(&root_a), &coverage, true, &block_index);
+
+    let bundle = root_a.join("cache.json");
+    let exported = depdb.export(&root_a, &bundle)?;
+    assert_eq!(exported.total_tests, 1);
+    assert!(
+        !fs::read_to_string(&bundle)?.contains(&*root_a.to_string_lossy()),
+        "Exported paths should be relative to the root"
+    );
+
+    let imported = DependencyDatabase::import(&root_b, &bundle)?;
+    let decision = imported.needs_run(&item(&root_b));
+    assert!(
+        matches!(decision, TestRunDecision::CanSkip),
+        "Imported history should apply under the new root, got {:?}",
+        decision
+    );
+
+    Ok(())
+}
+
+#[test]
+fn explain_reports_dependencies_and_last_outcome() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_foo.py");
+    fs::write(
+        &test_file,
+        dedent(
+            r#"
+            def helper():
+                return 1
+
+            def test_uses_helper():
+                assert helper() == 1
+        "#,
+        ),
+    )?;
+    let test_file = test_file.canonicalize()?;
+
+    let mut depdb = DependencyDatabase::default();
+    let file_blocks = FileBlocks::from_file(&test_file)?;
+    depdb.update_blocks(&file_blocks);
+    let mut block_index = HashMap::new();
+    block_index.insert(test_file.clone(), file_blocks);
+
+    let test = TestItem {
+        file: test_file.clone(),
+        function: "test_uses_helper".to_string(),
+        class: None,
+        line: 4,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    let mut coverage = HashMap::new();
+    coverage.insert(test_file.clone(), vec![2, 5]);
+    depdb.record_test_coverage(&test, &coverage, false, &block_index);
+
+    let explanation = depdb.explain(&test);
+
+    assert_eq!(explanation.last_run_passed, Some(false));
+    assert!(matches!(
+        explanation.decision,
+        TestRunDecision::FailedLastTime
+    ));
+    let names: Vec<&str> = explanation
+        .dependencies
+        .iter()
+        .map(|d| d.block.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["helper", "test_uses_helper"]);
+    assert!(
+        explanation
+            .dependencies
+            .iter()
+            .all(|d| d.current_checksum.as_ref() == Some(&d.recorded_checksum))
+    );
+
+    Ok(())
+}
+
 // =============================================================================
 // Coverage Mapping Edge Cases
 // =============================================================================
@@ -677,7 +1507,11 @@ fn coverage_for_file_not_in_block_index_ignored() -> Result<()> {
         function: "test_ok".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     // Record coverage for a file that's not in the index
@@ -715,7 +1549,11 @@ fn coverage_for_line_not_in_any_block_handled() -> Result<()> {
         function: "bar".to_string(),
         class: None,
         line: 4,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     // Coverage includes line 2-3 which are blank (not in any block)
@@ -176,6 +176,198 @@ fn discover_single_file_path() -> Result<()> {
     Ok(())
 }
 
+// =============================================================================
+// Exclusion Tests
+// =============================================================================
+
+#[test]
+fn discover_skips_virtualenv_directories() -> Result<()> {
+    let tmp = TempDir::new()?;
+
+    write_file(&tmp.path().join("test_x.py"), "def test_ok(): pass\n")?;
+    write_file(
+        &tmp.path().join(".venv/lib/site/test_x.py"),
+        "def test_vendored(): pass\n",
+    )?;
+    write_file(
+        &tmp.path().join("node_modules/pkg/test_y.py"),
+        "def test_vendored(): pass\n",
+    )?;
+
+    let files = taut::discovery::find_test_files(&[tmp.path().to_path_buf()])?;
+
+    assert_eq!(files, vec![tmp.path().join("test_x.py")]);
+
+    Ok(())
+}
+
+#[test]
+fn discover_honors_exclude_patterns() -> Result<()> {
+    let tmp = TempDir::new()?;
+
+    write_file(
+        &tmp.path().join("tests/test_kept.py"),
+        "def test_ok(): pass\n",
+    )?;
+    write_file(
+        &tmp.path().join("build/test_built.py"),
+        "def test_ok(): pass\n",
+    )?;
+    write_file(
+        &tmp.path().join("tests/fixtures/test_data.py"),
+        "def test_ok(): pass\n",
+    )?;
+
+    let exclude = vec!["build".to_string(), "tests/fixtures".to_string()];
+    let files = taut::discovery::find_test_files_excluding(&[tmp.path().to_path_buf()], &exclude)?;
+
+    assert_eq!(files, vec![tmp.path().join("tests/test_kept.py")]);
+
+    Ok(())
+}
+
+#[test]
+fn discover_honors_gitignore() -> Result<()> {
+    let tmp = TempDir::new()?;
+
+    write_file(
+        &tmp.path().join(".gitignore"),
+        "# generated\ndist/\n*.tmp.py\n",
+    )?;
+    write_file(&tmp.path().join("test_real.py"), "def test_ok(): pass\n")?;
+    write_file(
+        &tmp.path().join("dist/test_copy.py"),
+        "def test_ok(): pass\n",
+    )?;
+    write_file(
+        &tmp.path().join("test_scratch.tmp.py"),
+        "def test_ok(): pass\n",
+    )?;
+
+    let files = taut::discovery::find_test_files(&[tmp.path().to_path_buf()])?;
+
+    assert_eq!(files, vec![tmp.path().join("test_real.py")]);
+
+    Ok(())
+}
+
+#[test]
+fn explicit_file_is_never_excluded() -> Result<()> {
+    let tmp = TempDir::new()?;
+
+    let target = tmp.path().join(".venv/test_explicit.py");
+    write_file(&target, "def test_ok(): pass\n")?;
+
+    let files = taut::discovery::find_test_files(std::slice::from_ref(&target))?;
+
+    assert_eq!(files, vec![target]);
+
+    Ok(())
+}
+
+#[test]
+fn ignored_directory_is_not_collected() -> Result<()> {
+    let tmp = TempDir::new()?;
+
+    write_file(
+        &tmp.path().join("tests/test_fast.py"),
+        "def test_a(): pass\n",
+    )?;
+    write_file(
+        &tmp.path().join("tests/slow/test_big.py"),
+        "def test_b(): pass\n",
+    )?;
+    write_file(
+        &tmp.path().join("tests/slowish/test_c.py"),
+        "def test_c(): pass\n",
+    )?;
+    write_file(
+        &tmp.path().join("tests/test_flaky.py"),
+        "def test_d(): pass\n",
+    )?;
+
+    let ignore = taut::exclude::IgnoreList::new(&[
+        tmp.path().join("tests/slow").display().to_string(),
+        "**/test_flaky.py".to_string(),
+    ]);
+    let mut files = taut::discovery::find_test_files(&[tmp.path().to_path_buf()])?;
+    files.retain(|f| !ignore.is_ignored(f));
+
+    assert_eq!(
+        files,
+        vec![
+            tmp.path().join("tests/slowish/test_c.py"),
+            tmp.path().join("tests/test_fast.py"),
+        ]
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// Custom Pattern Tests
+// =============================================================================
+
+#[test]
+fn discover_with_custom_patterns() -> Result<()> {
+    use taut::discovery::{TestPatterns, extract_tests_with, find_test_files_with};
+
+    let tmp = TempDir::new()?;
+
+    write_file(
+        &tmp.path().join("check_math.py"),
+        &dedent(
+            r#"
+            def check_sum():
+                assert 1 + 1 == 2
+
+            def test_default_name():
+                pass
+
+            class CheckSuite:
+                def check_method(self):
+                    pass
+        "#,
+        ),
+    )?;
+    write_file(
+        &tmp.path().join("test_default.py"),
+        "def check_other(): pass\n",
+    )?;
+
+    let patterns = TestPatterns::new(
+        &["check_*".to_string()],
+        &["check_*".to_string()],
+        &["Check*".to_string()],
+    );
+    let files = find_test_files_with(&[tmp.path().to_path_buf()], &[], &patterns)?;
+    assert_eq!(files, vec![tmp.path().join("check_math.py")]);
+
+    let tests = extract_tests_with(&files, &[], false, &patterns)?.tests;
+    let ids: Vec<_> = tests
+        .iter()
+        .map(|t| (t.class.as_deref(), t.function.as_str()))
+        .collect();
+    assert_eq!(
+        ids,
+        vec![(None, "check_sum"), (Some("CheckSuite"), "check_method")]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn empty_patterns_fall_back_to_defaults() -> Result<()> {
+    let patterns = taut::discovery::TestPatterns::new(&[], &[], &[]);
+
+    assert!(patterns.is_test_file(Path::new("test_alpha.py")));
+    assert!(!patterns.is_test_file(Path::new("check_alpha.py")));
+    assert!(patterns.is_test_name("test_something"));
+    assert!(patterns.is_test_class("TestThing"));
+
+    Ok(())
+}
+
 // =============================================================================
 // Function Extraction Tests
 // =============================================================================
@@ -405,6 +597,107 @@ fn extract_underscore_test_methods() -> Result<()> {
     Ok(())
 }
 
+// =============================================================================
+// Parametrize Tests
+// =============================================================================
+
+#[test]
+fn parametrized_test_expands_to_one_item_per_case() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let file = tmp.path().join("test_params.py");
+
+    write_python_file(
+        &file,
+        &dedent(
+            r#"
+            from taut import parametrize
+
+            @parametrize("n", [1, 2, 3])
+            def test_single(n):
+                pass
+
+            @parametrize("a, b", [(1, "x"), (-2, None)])
+            def test_pairs(a, b):
+                pass
+
+            class TestGroup:
+                @parametrize(("flag",), [(True,), (False,)])
+                def test_flag(self, flag):
+                    pass
+
+            def test_plain():
+                pass
+        "#,
+        ),
+    )?;
+
+    let items = taut::discovery::extract_tests_from_file(&file)?;
+    let names: Vec<_> = items.iter().map(|i| i.name()).collect();
+
+    assert_eq!(
+        names,
+        vec![
+            "test_single[1]",
+            "test_single[2]",
+            "test_single[3]",
+            "test_pairs[1-x]",
+            "test_pairs[-2-None]",
+            "test_flag[True]",
+            "test_flag[False]",
+            "test_plain",
+        ]
+    );
+    assert!(items[3].id().ends_with("test_params.py::test_pairs[1-x]"));
+    assert!(
+        items[5]
+            .id()
+            .ends_with("test_params.py::TestGroup::test_flag[True]")
+    );
+    assert_eq!(
+        items[4].params,
+        vec![
+            ("a".to_string(), serde_json::json!(-2)),
+            ("b".to_string(), serde_json::Value::Null),
+        ]
+    );
+    assert!(items[7].params.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn stacked_parametrize_runs_every_combination() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let file = tmp.path().join("test_stacked.py");
+
+    write_python_file(
+        &file,
+        &dedent(
+            r#"
+            @parametrize("x", [1, 2])
+            @parametrize("y", ["a", "b"])
+            def test_grid(x, y):
+                pass
+        "#,
+        ),
+    )?;
+
+    let items = taut::discovery::extract_tests_from_file(&file)?;
+    let names: Vec<_> = items.iter().map(|i| i.name()).collect();
+
+    assert_eq!(
+        names,
+        vec![
+            "test_grid[1-a]",
+            "test_grid[1-b]",
+            "test_grid[2-a]",
+            "test_grid[2-b]"
+        ]
+    );
+
+    Ok(())
+}
+
 // =============================================================================
 // Line Number Tests
 // =============================================================================
@@ -480,6 +773,90 @@ fn extract_correct_line_numbers_with_decorators() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn extract_decorator_line_alongside_def_line() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let file = tmp.path().join("test_decorator_line.py");
+
+    write_file(
+        &file,
+        &dedent(
+            r#"
+            @decorator
+            def test_decorated():
+                pass
+
+            def test_plain():
+                pass
+        "#,
+        ),
+    )?;
+
+    let items = taut::discovery::extract_tests_from_file(&file)?;
+
+    let decorated = items
+        .iter()
+        .find(|i| i.function == "test_decorated")
+        .unwrap();
+    assert_eq!(decorated.decorator_line, Some(1));
+    assert_eq!(decorated.line, 2);
+
+    let plain = items.iter().find(|i| i.function == "test_plain").unwrap();
+    assert_eq!(plain.decorator_line, None);
+    assert_eq!(plain.line, 5);
+
+    Ok(())
+}
+
+#[test]
+fn extract_doctests_from_docstrings_with_examples() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let file = tmp.path().join("helpers.py");
+
+    write_file(
+        &file,
+        &dedent(
+            r#"
+            """Helpers.
+
+            >>> 1 + 1
+            2
+            """
+
+            def documented():
+                """Returns one.
+
+                >>> documented()
+                1
+                """
+                return 1
+
+            def undocumented():
+                """No examples here."""
+
+            class Box:
+                def size(self):
+                    """
+                    >>> Box().size()
+                    0
+                    """
+                    return 0
+        "#,
+        ),
+    )?;
+
+    let items = taut::discovery::extract_doctests_from_file(&file)?;
+    let names: Vec<_> = items.iter().map(|i| i.function.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["doctest:<module>", "doctest:documented", "doctest:Box.size"]
+    );
+    assert!(items.iter().all(|i| i.is_doctest() && i.class.is_none()));
+    assert_eq!(items[1].line, 7);
+
+    Ok(())
+}
+
 // =============================================================================
 // Filter Tests
 // =============================================================================
@@ -506,7 +883,7 @@ fn filter_by_function_name() -> Result<()> {
     )?;
 
     let files = vec![file];
-    let items = taut::discovery::extract_tests(&files, Some("alpha"))?;
+    let items = taut::discovery::extract_tests(&files, &["alpha".to_string()])?.tests;
     let names: Vec<_> = items.iter().map(|i| i.function.as_str()).collect();
 
     assert!(names.contains(&"test_alpha"));
@@ -541,7 +918,7 @@ fn filter_case_insensitive() -> Result<()> {
     )?;
 
     let files = vec![file];
-    let items = taut::discovery::extract_tests(&files, Some("alpha"))?;
+    let items = taut::discovery::extract_tests(&files, &["alpha".to_string()])?.tests;
 
     assert_eq!(items.len(), 3, "Filter should be case-insensitive");
 
@@ -569,7 +946,7 @@ fn filter_by_class_name() -> Result<()> {
     )?;
 
     let files = vec![file];
-    let items = taut::discovery::extract_tests(&files, Some("Alpha"))?;
+    let items = taut::discovery::extract_tests(&files, &["Alpha".to_string()])?.tests;
 
     assert_eq!(items.len(), 1);
     assert_eq!(items[0].class, Some("TestAlpha".to_string()));
@@ -595,6 +972,29 @@ fn handle_syntax_error_gracefully() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn collection_reports_unparseable_files_alongside_tests() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let broken = tmp.path().join("test_broken.py");
+    let good = tmp.path().join("test_good.py");
+
+    write_file(&broken, "def test_broken(\n")?;
+    write_file(&good, "def test_ok(): pass\n")?;
+
+    let collection = taut::discovery::extract_tests(&[broken.clone(), good], &[])?;
+
+    assert_eq!(collection.tests.len(), 1);
+    assert_eq!(collection.errors.len(), 1);
+    assert_eq!(collection.errors[0].0, broken);
+    assert!(
+        collection.errors[0].1.contains("Parse error"),
+        "{}",
+        collection.errors[0].1
+    );
+
+    Ok(())
+}
+
 #[test]
 fn handle_empty_file() -> Result<()> {
     let tmp = TempDir::new()?;
@@ -660,13 +1060,57 @@ fn handle_nested_classes() -> Result<()> {
 
     let items = taut::discovery::extract_tests_from_file(&file)?;
 
-    // At minimum, TestOuter.test_outer should be found
     let outer = items.iter().find(|i| i.function == "test_outer");
     assert!(outer.is_some(), "Should find test_outer");
     assert_eq!(outer.unwrap().class, Some("TestOuter".to_string()));
 
-    // Nested classes may or may not be supported - document the behavior
-    // For now, we just verify no panic
+    // Nested classes use a dotted class and a `::`-separated ID
+    let inner = items
+        .iter()
+        .find(|i| i.function == "test_inner")
+        .expect("Should find test_inner");
+    assert_eq!(inner.class, Some("TestOuter.TestInner".to_string()));
+    assert_eq!(inner.line, 6);
+    assert!(
+        inner.id().ends_with("::TestOuter::TestInner::test_inner"),
+        "{}",
+        inner.id()
+    );
+    assert!(inner.matches_id(&format!(
+        "{}::TestOuter::TestInner::test_inner",
+        file.display()
+    )));
+
+    Ok(())
+}
+
+#[test]
+fn nested_classes_inherit_markers_and_skip_non_test_classes() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let file = tmp.path().join("test_nested_markers.py");
+
+    write_file(
+        &file,
+        &dedent(
+            r#"
+            @parallel
+            class TestOuter:
+                class TestInner:
+                    def test_inner(self):
+                        pass
+
+                class Helper:
+                    def test_not_collected(self):
+                        pass
+        "#,
+        ),
+    )?;
+
+    let items = taut::discovery::extract_tests_from_file(&file)?;
+
+    assert_eq!(items.len(), 1, "{:?}", items);
+    assert_eq!(items[0].function, "test_inner");
+    assert!(items[0].is_parallel());
 
     Ok(())
 }
@@ -722,7 +1166,7 @@ fn handle_multiple_test_files() -> Result<()> {
     write_file(&tmp.path().join("test_b.py"), "def test_b1(): pass\n")?;
 
     let files = taut::discovery::find_test_files(&[tmp.path().to_path_buf()])?;
-    let items = taut::discovery::extract_tests(&files, None)?;
+    let items = taut::discovery::extract_tests(&files, &[])?.tests;
 
     assert_eq!(items.len(), 3);
 
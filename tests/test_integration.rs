@@ -13,7 +13,7 @@ use std::fs;
 
 use anyhow::Result;
 
-use helpers::{TempProject, dedent, run_taut};
+use helpers::{TempProject, dedent, run_taut, run_taut_in_dir, run_taut_with_env};
 
 // =============================================================================
 // Basic Execution Tests
@@ -167,6 +167,98 @@ fn incremental_run_skips_unchanged_tests() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn incremental_run_reuses_cache_across_path_spellings() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_paths.py",
+        &dedent(
+            r#"
+            def test_one():
+                assert True
+
+            def test_two():
+                assert True
+        "#,
+        ),
+    )?;
+
+    let result1 = run_taut(&project, &["."])?;
+    result1.assert_success();
+
+    // Same file, spelled relative to the project
+    let result2 = run_taut(&project, &["./test_paths.py"])?;
+    result2.assert_success();
+    result2.assert_stdout_contains("2 skipped");
+
+    // Same file, spelled as an absolute path
+    let absolute = project.path().join("test_paths.py");
+    let result3 = run_taut(&project, &[absolute.to_str().unwrap()])?;
+    result3.assert_success();
+    result3.assert_stdout_contains("2 skipped");
+
+    Ok(())
+}
+
+#[test]
+fn incremental_run_reruns_everything_after_config_change() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_files(&[
+        ("pyproject.toml", "[tool.taut]\nexclude = []\n"),
+        ("test_config.py", "def test_one():\n    assert True\n"),
+    ])?;
+
+    run_taut(&project, &["."])?.assert_success();
+    let cached = run_taut(&project, &["."])?;
+    cached.assert_stdout_contains("1 skipped");
+
+    project.write_file("pyproject.toml", "[tool.taut]\nexclude = [\"build\"]\n")?;
+    let changed = run_taut(&project, &["."])?;
+    changed.assert_success();
+    changed.assert_stdout_contains("1 passed");
+    assert!(
+        !changed.stdout.contains("skipped"),
+        "stdout: {}",
+        changed.stdout
+    );
+    assert!(
+        changed.stderr.contains("configuration changed"),
+        "stderr: {}",
+        changed.stderr
+    );
+
+    let again = run_taut(&project, &["."])?;
+    again.assert_stdout_contains("1 skipped");
+
+    Ok(())
+}
+
+#[test]
+fn incremental_run_keeps_cache_when_only_max_workers_changes() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_files(&[
+        ("pyproject.toml", "[tool.taut]\nmax_workers = 2\n"),
+        ("test_config.py", "def test_one():\n    assert True\n"),
+    ])?;
+
+    run_taut(&project, &["."])?.assert_success();
+
+    project.write_file("pyproject.toml", "[tool.taut]\nmax_workers = 4\n")?;
+    let result = run_taut(&project, &["."])?;
+    result.assert_success();
+    result.assert_stdout_contains("1 skipped");
+    assert!(
+        !result.stderr.contains("configuration changed"),
+        "stderr: {}",
+        result.stderr
+    );
+
+    Ok(())
+}
+
 #[test]
 fn incremental_run_reruns_changed_tests() -> Result<()> {
     let mut project = TempProject::new()?;
@@ -268,6 +360,110 @@ fn filter_option_limits_tests() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn repeated_filters_match_any_pattern() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_filter.py",
+        &dedent(
+            r#"
+            def test_alpha(): assert True
+            def test_beta(): assert True
+            def test_gamma(): assert True
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(
+        &project,
+        &[
+            "--no-cache",
+            "-v",
+            "--ordered-output",
+            "-k",
+            "alpha",
+            "-k",
+            "beta",
+            ".",
+        ],
+    )?;
+    result.assert_success();
+    result.assert_stdout_contains("2 passed");
+    assert_eq!(verbose_order(&result.stdout), ["test_alpha", "test_beta"]);
+
+    let result = run_taut(
+        &project,
+        &["--no-cache", "-v", "--ordered-output", "-k", "!beta", "."],
+    )?;
+    result.assert_success();
+    assert_eq!(verbose_order(&result.stdout), ["test_alpha", "test_gamma"]);
+
+    Ok(())
+}
+
+#[test]
+fn exact_filter_matches_whole_names() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_exact.py",
+        &dedent(
+            r#"
+            def test_foo(): assert True
+            def test_foobar(): assert True
+
+            class TestGroup:
+                def test_foo(self): assert True
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["list", "--exact", "-k", "test_foo", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("test_exact.py::test_foo");
+    result.assert_stdout_contains("test_exact.py::TestGroup::test_foo");
+    assert!(
+        !result.stdout.contains("test_foobar"),
+        "stdout: {}",
+        result.stdout
+    );
+
+    let result = run_taut(&project, &["--no-cache", "--exact", "-k", "test_foo", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("2 passed");
+
+    Ok(())
+}
+
+#[test]
+fn regex_filter_selects_by_pattern() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_regex.py",
+        &dedent(
+            r#"
+            def test_foo(): assert True
+            def test_bar(): assert True
+            def test_foobar(): assert True
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["list", "-k", "re:test_(foo|bar)$", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("test_regex.py::test_foo");
+    result.assert_stdout_contains("test_regex.py::test_bar");
+    result.assert_stdout_not_contains("test_foobar");
+
+    let result = run_taut(&project, &["-k", "re:test_(foo", "."])?;
+    result.assert_failure();
+    result.assert_stderr_contains("Invalid filter pattern 're:test_(foo'");
+
+    Ok(())
+}
+
 #[test]
 fn no_cache_runs_all_tests() -> Result<()> {
     let mut project = TempProject::new()?;
@@ -353,174 +549,2621 @@ fn no_parallel_runs_sequentially() -> Result<()> {
     Ok(())
 }
 
-// =============================================================================
-// List Command Tests
-// =============================================================================
+/// Test names in the order verbose output reported them.
+fn verbose_order(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .filter_map(|l| l.split("::").nth(1))
+        .map(|rest| rest.split_whitespace().next().unwrap_or("").to_string())
+        .collect()
+}
 
 #[test]
-fn list_command_shows_discovered_tests() -> Result<()> {
+fn shuffle_with_seed_is_reproducible() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    let source: String = (0..8)
+        .map(|i| format!("def test_{}(): assert True\n", i))
+        .collect();
+    project.write_file("test_shuffle.py", &source)?;
+
+    let args = [
+        "--no-cache",
+        "-v",
+        "--ordered-output",
+        "--seed",
+        "1234",
+        ".",
+    ];
+    let first = run_taut(&project, &args)?;
+    let second = run_taut(&project, &args)?;
+    first.assert_success();
+    first.assert_stdout_contains("--seed 1234");
+
+    let order = verbose_order(&first.stdout);
+    assert_eq!(order.len(), 8, "stdout: {}", first.stdout);
+    assert_eq!(order, verbose_order(&second.stdout));
+
+    let other = run_taut(
+        &project,
+        &["--no-cache", "-v", "--ordered-output", "--seed", "99", "."],
+    )?;
+    assert_ne!(
+        order,
+        verbose_order(&other.stdout),
+        "Different seeds should produce different orders"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn order_slowest_first_uses_durations_from_previous_run() -> Result<()> {
     let mut project = TempProject::new()?;
 
     project.write_file(
-        "test_list.py",
+        "test_speed.py",
         &dedent(
             r#"
-            def test_alpha(): pass
-            def test_beta(): pass
+            import time
 
-            class TestClass:
-                def test_method(self): pass
+            def test_fast():
+                pass
+
+            def test_mid():
+                time.sleep(0.2)
+
+            def test_slow():
+                time.sleep(0.4)
         "#,
         ),
     )?;
 
-    let result = run_taut(&project, &["list", "."])?;
+    // --prime runs everything and records how long each test took
+    let result = run_taut(&project, &["--prime", "--no-parallel", "."])?;
     result.assert_success();
 
-    // Should list all tests
-    assert!(
-        result.stdout.contains("test_alpha"),
-        "Should list test_alpha"
-    );
-    assert!(result.stdout.contains("test_beta"), "Should list test_beta");
-    assert!(
-        result.stdout.contains("test_method"),
-        "Should list test_method"
+    let result = run_taut(
+        &project,
+        &[
+            "--prime",
+            "--no-parallel",
+            "-v",
+            "--order",
+            "slowest-first",
+            ".",
+        ],
+    )?;
+    result.assert_success();
+    assert_eq!(
+        verbose_order(&result.stdout),
+        vec!["test_slow", "test_mid", "test_fast"],
+        "stdout: {}",
+        result.stdout
     );
 
     Ok(())
 }
 
 #[test]
-fn list_command_with_filter() -> Result<()> {
+fn order_failed_first_runs_last_failures_first() -> Result<()> {
     let mut project = TempProject::new()?;
 
     project.write_file(
-        "test_filter.py",
-        "def test_alpha(): pass\ndef test_beta(): pass\n",
+        "test_mixed.py",
+        "def test_a(): assert True\ndef test_b(): assert True\ndef test_c(): assert False\n",
     )?;
 
-    let result = run_taut(&project, &["list", "-k", "alpha", "."])?;
-    result.assert_success();
+    let result = run_taut(&project, &["--no-parallel", "."])?;
+    result.assert_failure();
 
-    assert!(result.stdout.contains("test_alpha"));
-    assert!(!result.stdout.contains("test_beta"));
+    let result = run_taut(
+        &project,
+        &[
+            "--prime",
+            "--no-parallel",
+            "-v",
+            "--order",
+            "failed-first",
+            ".",
+        ],
+    )?;
+    // The failure is reported again in the summary, so only look at the run itself
+    assert_eq!(
+        verbose_order(&result.stdout)[..3],
+        ["test_c", "test_a", "test_b"],
+        "stdout: {}",
+        result.stdout
+    );
 
     Ok(())
 }
 
-// =============================================================================
-// Cache Commands Tests
-// =============================================================================
-
 #[test]
-fn cache_info_shows_stats() -> Result<()> {
+fn last_seed_reuses_the_recorded_seed() -> Result<()> {
     let mut project = TempProject::new()?;
 
-    project.write_file("test_cache.py", "def test_ok(): assert True\n")?;
-
-    // Run tests to populate cache
-    run_taut(&project, &["."])?;
-
-    // Check cache info
-    let result = run_taut(&project, &["cache", "info"])?;
-    result.assert_success();
+    let source: String = (0..8)
+        .map(|i| format!("def test_{}(): assert True\n", i))
+        .collect();
+    project.write_file("test_shuffle.py", &source)?;
 
-    assert!(
-        result.stdout.contains("Cache") || result.stdout.contains("cache"),
-        "Should show cache info"
+    let shuffled = run_taut(
+        &project,
+        &["--no-cache", "-v", "--ordered-output", "--shuffle", "."],
+    )?;
+    shuffled.assert_success();
+    let seed = shuffled
+        .stdout
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Using --seed "))
+        .expect("shuffled run should print its seed")
+        .to_string();
+
+    let replayed = run_taut(
+        &project,
+        &["--no-cache", "-v", "--ordered-output", "--last-seed", "."],
+    )?;
+    replayed.assert_success();
+    replayed.assert_stdout_contains(&format!("--seed {seed}"));
+    assert_eq!(
+        verbose_order(&shuffled.stdout),
+        verbose_order(&replayed.stdout)
     );
 
+    project.write_file("test_shuffle.py", "def test_broken(): assert False\n")?;
+    let failed = run_taut(&project, &["--no-cache", "--last-seed", "."])?;
+    failed.assert_stdout_contains(&format!("Reproduce this order with: taut --seed {seed}"));
+
     Ok(())
 }
 
 #[test]
-fn cache_clear_removes_cache() -> Result<()> {
+fn dry_run_does_not_record_the_seed() -> Result<()> {
     let mut project = TempProject::new()?;
 
-    project.write_file("test_clear.py", "def test_ok(): assert True\n")?;
+    project.write_file(
+        "test_shuffle.py",
+        "def test_a(): pass\ndef test_b(): pass\n",
+    )?;
+    run_taut(&project, &["--no-cache", "--seed", "5", "."])?.assert_success();
+    run_taut(&project, &["--no-cache", "--dry-run", "--seed", "7", "."])?.assert_success();
 
-    // Run tests to populate cache
-    run_taut(&project, &["."])?;
+    let replayed = run_taut(&project, &["--no-cache", "--last-seed", "."])?;
+    replayed.assert_success();
+    replayed.assert_stdout_contains("Using --seed 5");
 
-    // Clear cache
-    let result = run_taut(&project, &["cache", "clear"])?;
-    result.assert_success();
+    Ok(())
+}
 
-    // After clearing, next run should run all tests (not skip)
-    let result2 = run_taut(&project, &["."])?;
-    result2.assert_success();
+#[test]
+fn seed_rejects_a_non_random_order() -> Result<()> {
+    let mut project = TempProject::new()?;
 
-    // Should not skip (cache was cleared)
-    // This might show "passed" without "skipped"
+    project.write_file("test_order.py", "def test_a(): pass\ndef test_b(): pass\n")?;
+    for flags in [&["--seed", "5"][..], &["--last-seed"][..]] {
+        let mut args = vec!["--no-cache", "--order", "name"];
+        args.extend_from_slice(flags);
+        args.push(".");
+        let result = run_taut(&project, &args)?;
+        result.assert_failure();
+        result.assert_stderr_contains("--order must be random");
+    }
 
     Ok(())
 }
 
-// =============================================================================
-// Multiple Paths Tests
-// =============================================================================
-
 #[test]
-fn multiple_paths_combined() -> Result<()> {
+fn order_random_shuffles_with_seed() -> Result<()> {
     let mut project = TempProject::new()?;
 
-    project.mkdir("dir_a")?;
-    project.mkdir("dir_b")?;
-
-    project.write_file("dir_a/test_a.py", "def test_a(): assert True\n")?;
-    project.write_file("dir_b/test_b.py", "def test_b(): assert True\n")?;
-
-    // Run on both directories
-    let result = run_taut(&project, &["dir_a", "dir_b"])?;
-    result.assert_success();
+    let source: String = (0..8)
+        .map(|i| format!("def test_{}(): assert True\n", i))
+        .collect();
+    project.write_file("test_shuffle.py", &source)?;
+
+    let random = run_taut(
+        &project,
+        &[
+            "--no-cache",
+            "-v",
+            "--ordered-output",
+            "--order",
+            "random",
+            "--seed",
+            "1234",
+            ".",
+        ],
+    )?;
+    random.assert_success();
+    random.assert_stdout_contains("--seed 1234");
+
+    let seeded = run_taut(
+        &project,
+        &[
+            "--no-cache",
+            "-v",
+            "--ordered-output",
+            "--seed",
+            "1234",
+            ".",
+        ],
+    )?;
+    assert_eq!(verbose_order(&random.stdout), verbose_order(&seeded.stdout));
 
-    // Should find tests from both
-    assert!(
-        result.stdout.contains("2 passed") || result.count_in_stdout(".") >= 2,
-        "Should run tests from both directories"
-    );
+    let rejected = run_taut(&project, &["--order", "random", "--shuffle", "."])?;
+    rejected.assert_failure();
 
     Ok(())
 }
 
 #[test]
-fn single_file_path() -> Result<()> {
+fn compare_durations_flags_tests_that_got_slower() -> Result<()> {
     let mut project = TempProject::new()?;
 
-    project.write_file("test_single.py", "def test_single(): assert True\n")?;
-    project.write_file("test_other.py", "def test_other(): assert True\n")?;
-
-    // Run on single file
-    let result = run_taut(&project, &["test_single.py"])?;
+    project.write_file(
+        "test_speed.py",
+        "import time\n\ndef test_speed():\n    time.sleep(0.05)\n",
+    )?;
+    let result = run_taut(&project, &["."])?;
     result.assert_success();
 
-    // Should only run the one test
-    assert!(
-        result.stdout.contains("1 passed"),
-        "Should only run one test from specified file"
-    );
+    // The edit makes the test run again, now ten times slower
+    project.write_file(
+        "test_speed.py",
+        "import time\n\ndef test_speed():\n    time.sleep(0.5)\n",
+    )?;
+    let result = run_taut(&project, &["--compare-durations", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("Slower than last run (by more than 50%)");
+    result.assert_stdout_contains("test_speed.py::test_speed");
 
     Ok(())
 }
 
-// =============================================================================
-// Edge Cases
-// =============================================================================
+/// Start and end times recorded by a test in `<name>.times`.
+fn recorded_interval(project: &TempProject, name: &str) -> Result<(f64, f64)> {
+    let times = std::fs::read_to_string(project.path().join(format!("{}.times", name)))?;
+    let mut parts = times.split_whitespace().map(|t| t.parse::<f64>());
+    Ok((parts.next().unwrap()?, parts.next().unwrap()?))
+}
+
+/// Whether the tests that wrote `<a>.times` and `<b>.times` ran at the same time.
+fn overlapped(project: &TempProject, a: &str, b: &str) -> Result<bool> {
+    let (a_start, a_end) = recorded_interval(project, a)?;
+    let (b_start, b_end) = recorded_interval(project, b)?;
+    Ok(a_start < b_end && b_start < a_end)
+}
 
 #[test]
-fn no_tests_found_message() -> Result<()> {
+fn parallel_marked_tests_run_concurrently_under_no_parallel() -> Result<()> {
     let mut project = TempProject::new()?;
 
-    // Create a non-test file
-    project.write_file("not_a_test.py", "def foo(): pass\n")?;
+    project.write_file(
+        "test_overlap.py",
+        &dedent(
+            r#"
+            import os
+            import time
 
-    let result = run_taut(&project, &["."])?;
+            def parallel():
+                return lambda f: f
 
-    // Should indicate no tests found (not crash)
-    assert!(
-        result.stdout.contains("No tests") || result.stdout.contains("0"),
-        "Should indicate no tests: {}",
+            HERE = os.path.dirname(os.path.abspath(__file__))
+
+            def record(name):
+                start = time.time()
+                time.sleep(0.5)
+                with open(os.path.join(HERE, name + ".times"), "w") as f:
+                    f.write(f"{start} {time.time()}")
+
+            @parallel()
+            def test_first():
+                record("first")
+
+            @parallel()
+            def test_second():
+                record("second")
+
+            def test_third():
+                record("third")
+
+            def test_fourth():
+                record("fourth")
+        "#,
+        ),
+    )?;
+
+    // --no-parallel: only the @parallel pair overlaps
+    let result = run_taut(&project, &["--no-cache", "--no-parallel", "-j", "2", "."])?;
+    result.assert_success();
+    assert!(
+        overlapped(&project, "first", "second")?,
+        "@parallel tests should overlap under --no-parallel"
+    );
+    assert!(
+        !overlapped(&project, "third", "fourth")?,
+        "unmarked tests should run one at a time under --no-parallel"
+    );
+
+    // By default every test may run concurrently
+    let result = run_taut(&project, &["--no-cache", "-j", "4", "."])?;
+    result.assert_success();
+    assert!(
+        overlapped(&project, "third", "fourth")?,
+        "unmarked tests should overlap by default"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn parallel_groups_never_overlap_tests_in_the_same_group() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    // Each test takes a lock file exclusively, so overlapping tests would fail
+    let tests: String = ["a", "b", "c"]
+        .iter()
+        .map(|name| {
+            format!(
+                "@parallel()\n@mark(group=\"db\")\ndef test_db_{}():\n    use_db()\n\n",
+                name
+            )
+        })
+        .collect();
+    project.write_file(
+        "test_groups.py",
+        &format!(
+            "{}\n{}",
+            dedent(
+                r#"
+                import os
+                import time
+
+                def parallel():
+                    return lambda f: f
+
+                def mark(**kwargs):
+                    return lambda f: f
+
+                LOCK = os.path.join(os.path.dirname(os.path.abspath(__file__)), "db.lock")
+
+                def use_db():
+                    fd = os.open(LOCK, os.O_CREAT | os.O_EXCL | os.O_WRONLY)
+                    os.close(fd)
+                    time.sleep(0.3)
+                    os.remove(LOCK)
+            "#,
+            ),
+            tests
+        ),
+    )?;
+
+    for isolation in ["process-per-test", "process-per-run"] {
+        let result = run_taut(
+            &project,
+            &[
+                "--no-cache",
+                "-j",
+                "3",
+                "--parallel-groups",
+                "--isolation",
+                isolation,
+                ".",
+            ],
+        )?;
+        result.assert_success();
+        result.assert_stdout_contains("3 passed");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn ordered_output_reports_parallel_results_in_collection_order() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    // Earlier tests sleep longer, so they finish last
+    let source: String = (0..4)
+        .map(|i| {
+            format!(
+                "\n@parallel\ndef test_{}():\n    time.sleep({})\n",
+                i,
+                0.1 * (4 - i) as f64
+            )
+        })
+        .collect();
+    project.write_file(
+        "test_ordered.py",
+        &format!("import time\nfrom taut import parallel\n{}", source),
+    )?;
+
+    let result = run_taut(
+        &project,
+        &["--no-cache", "-v", "-j", "4", "--ordered-output", "."],
+    )?;
+    result.assert_success();
+    assert_eq!(
+        verbose_order(&result.stdout),
+        vec!["test_0", "test_1", "test_2", "test_3"],
+        "stdout: {}",
+        result.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn max_collect_caps_number_of_tests() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    let source: String = (0..5)
+        .map(|i| format!("def test_{}(): assert True\n", i))
+        .collect();
+    project.write_file("test_cap.py", &source)?;
+
+    let result = run_taut(&project, &["--no-cache", "--max-collect", "2", "."])?;
+    result.assert_success();
+
+    result.assert_stdout_contains("2 passed");
+    result.assert_stdout_contains("ran 2 of 5 selected tests");
+
+    Ok(())
+}
+
+#[test]
+fn repeat_runs_each_test_multiple_times() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_repeat.py",
+        &dedent(
+            r#"
+            def test_one():
+                assert True
+
+            def test_two():
+                assert True
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["--repeat", "3", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("6 passed");
+
+    // Repeated runs don't populate the cache, so a normal run still executes everything
+    let result = run_taut(&project, &["."])?;
+    result.assert_success();
+    result.assert_stdout_contains("2 passed");
+
+    Ok(())
+}
+
+#[test]
+fn group_failures_by_category_tallies_failures() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_mixed.py",
+        &dedent(
+            r#"
+            def test_assert_one():
+                assert 1 == 2
+
+            def test_assert_two():
+                assert False
+
+            def test_key_error():
+                {}["missing"]
+
+            def test_value_error_one():
+                raise ValueError("bad")
+
+            def test_value_error_two():
+                int("nope")
+
+            def test_value_error_three():
+                raise ValueError("worse")
+
+            def test_passes():
+                assert True
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["--group-failures-by", "category", "."])?;
+    result.assert_failure();
+    result.assert_stdout_contains("Failures by category:");
+
+    let group = |label: &str| {
+        result
+            .stdout_lines_matching(|line| line.trim_start().ends_with(label))
+            .into_iter()
+            .filter_map(|line| line.split_whitespace().next()?.parse::<usize>().ok())
+            .next()
+    };
+    assert_eq!(group("ValueError"), Some(3));
+    assert_eq!(group("assertion"), Some(2));
+    assert_eq!(group("KeyError"), Some(1));
+
+    Ok(())
+}
+
+#[test]
+fn tap_output_emits_plan_and_results() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_tap.py",
+        &dedent(
+            r#"
+            def skip(reason=None):
+                return lambda f: f
+
+            def test_pass():
+                assert True
+
+            def test_fail():
+                assert 1 == 2
+
+            @skip(reason="not today")
+            def test_skipped():
+                pass
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["--output", "tap", "."])?;
+    result.assert_failure();
+    result.assert_stdout_contains("1..3");
+    result.assert_stdout_contains("# SKIP not today");
+    result.assert_stdout_not_contains("passed");
+
+    let failures = result.stdout_lines_matching(|line| line.starts_with("not ok "));
+    assert_eq!(failures.len(), 1, "stdout:\n{}", result.stdout);
+    assert!(failures[0].ends_with("test_fail"));
+
+    let oks = result.stdout_lines_matching(|line| line.starts_with("ok "));
+    assert_eq!(oks.len(), 2, "stdout:\n{}", result.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn summary_breaks_down_skips_by_kind() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_skips.py",
+        &dedent(
+            r#"
+            def skip(reason=None):
+                return lambda f: f
+
+            def test_pass():
+                assert True
+
+            @skip(reason="not today")
+            def test_skipped():
+                pass
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["."])?;
+    result.assert_success();
+    result.assert_stdout_contains("1 passed, 1 skipped (1 @skip)");
+
+    // The passing test is now cached, and reported separately from the @skip one
+    let result = run_taut(&project, &["."])?;
+    result.assert_success();
+    result.assert_stdout_contains("2 skipped (1 @skip, 1 unchanged)");
+
+    let result = run_taut(&project, &["--deselect", "test_skips.py::test_pass", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("2 skipped (1 @skip, 1 deselected)");
+
+    Ok(())
+}
+
+#[test]
+fn prime_populates_cache_and_exits_zero() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_prime.py",
+        &dedent(
+            r#"
+            def test_pass():
+                assert True
+
+            def test_fail():
+                assert False
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["--prime", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("1 failed");
+
+    let info = run_taut(&project, &["cache", "info"])?;
+    info.assert_success();
+    info.assert_stdout_contains("2 tests tracked");
+    info.assert_stdout_contains("1 passed, 1 failed");
+
+    // A real run afterwards benefits from the primed cache
+    let result = run_taut(&project, &["."])?;
+    result.assert_failure();
+    result.assert_stdout_contains("1 skipped");
+
+    Ok(())
+}
+
+#[test]
+fn github_actions_env_emits_error_annotations() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_annotate.py",
+        &dedent(
+            r#"
+            def test_pass():
+                assert True
+
+            def test_fail():
+                assert False, "first line\nsecond line"
+        "#,
+        ),
+    )?;
+    let content = project.read_file("test_annotate.py")?;
+    let line = content
+        .lines()
+        .position(|l| l.starts_with("def test_fail"))
+        .expect("test_fail should be defined")
+        + 1;
+
+    let result = run_taut_with_env(&project, &["."], &[("GITHUB_ACTIONS", "true")])?;
+    result.assert_failure();
+
+    let annotations = result.stdout_lines_matching(|l| l.starts_with("::error "));
+    assert_eq!(annotations.len(), 1, "stdout:\n{}", result.stdout);
+    let annotation = annotations[0];
+    assert!(annotation.contains("test_annotate.py"), "{}", annotation);
+    assert!(
+        annotation.contains(&format!(",line={},", line)),
+        "{}",
+        annotation
+    );
+    assert!(
+        annotation.contains("::first line%0Asecond line"),
+        "{}",
+        annotation
+    );
+
+    // Annotations would corrupt a TAP stream, so they go to stderr instead
+    let tap = run_taut_with_env(
+        &project,
+        &["--output", "tap", "."],
+        &[("GITHUB_ACTIONS", "true")],
+    )?;
+    tap.assert_failure();
+    tap.assert_stdout_not_contains("::error ");
+    tap.assert_stderr_contains("::error file=");
+
+    Ok(())
+}
+
+#[test]
+fn no_capture_streams_test_output() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_print.py",
+        &dedent(
+            r#"
+            def test_prints():
+                print("visible from the test")
+                assert True
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["--no-cache", "."])?;
+    result.assert_success();
+    result.assert_stdout_not_contains("visible from the test");
+
+    let result = run_taut(&project, &["--no-cache", "-s", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("visible from the test");
+
+    Ok(())
+}
+
+#[test]
+fn test_id_argument_runs_only_that_test() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_ids.py",
+        &dedent(
+            r#"
+            class TestMath:
+                def test_add(self):
+                    assert 1 + 1 == 2
+
+                def test_sub(self):
+                    assert 2 - 1 == 1
+
+                def test_mul(self):
+                    assert 2 * 2 == 4
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["-v", "test_ids.py::TestMath::test_sub"])?;
+    result.assert_success();
+    result.assert_stdout_contains("1 passed");
+    result.assert_stdout_contains("test_sub");
+    result.assert_stdout_not_contains("test_add");
+    result.assert_stdout_not_contains("test_mul");
+
+    // Unknown tests are reported rather than silently ignored
+    let result = run_taut(&project, &["test_ids.py::TestMath::test_div"])?;
+    result.assert_failure();
+    result.assert_stderr_contains("Test not found");
+
+    Ok(())
+}
+
+#[test]
+fn from_file_runs_listed_tests_and_warns_on_unknown() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_listed.py",
+        &dedent(
+            r#"
+            def test_one():
+                assert True
+
+            def test_two():
+                assert True
+
+            def test_unlisted():
+                assert True
+
+            class TestGroup:
+                def test_method(self):
+                    assert True
+        "#,
+        ),
+    )?;
+    project.write_file(
+        "failed.txt",
+        &dedent(
+            r#"
+            # collected from shard 3
+            test_listed.py::test_one
+
+            test_listed.py::TestGroup::test_method
+            test_listed.py::test_missing
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["-v", "--from-file", "failed.txt"])?;
+    result.assert_success();
+    result.assert_stdout_contains("2 passed");
+    result.assert_stdout_contains("test_one");
+    result.assert_stdout_contains("test_method");
+    result.assert_stdout_not_contains("test_unlisted");
+    result.assert_stderr_contains("Warning: Test not found: test_listed.py::test_missing");
+
+    Ok(())
+}
+
+#[test]
+fn custom_name_patterns_from_pyproject() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "pyproject.toml",
+        &dedent(
+            r#"
+            [tool.taut]
+            file_patterns = ["check_*.py"]
+            function_patterns = ["check_*"]
+        "#,
+        ),
+    )?;
+    project.write_file(
+        "check_math.py",
+        &dedent(
+            r#"
+            def check_sum():
+                assert 1 + 1 == 2
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["-v", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("check_sum");
+    result.assert_stdout_contains("1 passed");
+
+    Ok(())
+}
+
+#[test]
+fn coverage_report_lcov_lists_every_executed_file() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "mathlib.py",
+        &dedent(
+            r#"
+            def double(x):
+                return x * 2
+        "#,
+        ),
+    )?;
+    project.write_file(
+        "test_mathlib.py",
+        &dedent(
+            r#"
+            from mathlib import double
+
+            def test_double():
+                assert double(2) == 4
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(
+        &project,
+        &["--coverage-report", "lcov", "coverage.info", "."],
+    )?;
+    result.assert_success();
+
+    let lcov = project.read_file("coverage.info")?;
+    let sources: Vec<&str> = lcov.lines().filter(|l| l.starts_with("SF:")).collect();
+    assert_eq!(sources.len(), 2, "unexpected SF records:\n{}", lcov);
+    assert!(
+        sources
+            .iter()
+            .any(|l| l.ends_with("mathlib.py") && !l.ends_with("test_mathlib.py"))
+    );
+    assert!(sources.iter().any(|l| l.ends_with("test_mathlib.py")));
+    assert!(lcov.contains("DA:"));
+    assert!(lcov.trim_end().ends_with("end_of_record"));
+
+    Ok(())
+}
+
+#[test]
+fn coverage_report_honors_include_and_exclude() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    // Paths under site-packages are dropped by default, like an installed package
+    project.mkdir("site-packages/mylib")?;
+    project.write_file("site-packages/mylib/core.py", "def one():\n    return 1\n")?;
+    project.write_file(
+        "test_lib.py",
+        &dedent(
+            r#"
+            import sys, os
+            sys.path.insert(0, os.path.join(os.path.dirname(__file__), "site-packages"))
+            from mylib.core import one
+
+            def test_one():
+                assert one() == 1
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(
+        &project,
+        &[
+            "--coverage-report",
+            "lcov",
+            "coverage.info",
+            "--coverage-include",
+            "site-packages/mylib",
+            "--coverage-exclude",
+            "*/test_*.py",
+            ".",
+        ],
+    )?;
+    result.assert_success();
+
+    let lcov = project.read_file("coverage.info")?;
+    let sources: Vec<&str> = lcov.lines().filter(|l| l.starts_with("SF:")).collect();
+    assert_eq!(sources.len(), 1, "unexpected SF records:\n{}", lcov);
+    assert!(sources[0].ends_with("site-packages/mylib/core.py"));
+
+    Ok(())
+}
+
+#[test]
+fn cov_fail_under_fails_when_module_is_mostly_untested() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "billing.py",
+        &dedent(
+            r#"
+            def total(items):
+                return sum(items)
+
+            def invoice(customer, items):
+                lines = []
+                for item in items:
+                    lines.append(f"{customer}: {item}")
+                if not lines:
+                    lines.append("empty")
+                header = "INVOICE"
+                footer = "THANK YOU"
+                return "\n".join([header, *lines, footer])
+
+            def refund(amount):
+                if amount <= 0:
+                    raise ValueError("amount must be positive")
+                fee = amount * 0.05
+                return amount - fee
+        "#,
+        ),
+    )?;
+    project.write_file(
+        "test_billing.py",
+        &dedent(
+            r#"
+            from billing import total
+
+            def test_total():
+                assert total([1, 2]) == 3
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["--cov-fail-under", "90", "."])?;
+    result.assert_failure();
+    result.assert_stdout_contains("1 passed");
+    result.assert_stdout_contains("Coverage: ");
+    result.assert_stdout_contains("below --cov-fail-under 90%");
+
+    Ok(())
+}
+
+#[test]
+fn cov_fail_under_passes_when_threshold_is_met() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "mathlib.py",
+        &dedent(
+            r#"
+            def double(x):
+                return x * 2
+        "#,
+        ),
+    )?;
+    project.write_file(
+        "test_mathlib.py",
+        &dedent(
+            r#"
+            from mathlib import double
+
+            def test_double():
+                assert double(2) == 4
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["--cov-fail-under", "90", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("Coverage: 100.00%");
+    result.assert_stdout_not_contains("below --cov-fail-under");
+
+    Ok(())
+}
+
+#[test]
+fn parametrized_cases_run_with_their_arguments() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_params.py",
+        &dedent(
+            r#"
+            def parametrize(argnames, argvalues):
+                return lambda f: f
+
+            @parametrize("n, expected", [(1, 2), (2, 4), (3, 7)])
+            def test_double(n, expected):
+                assert n * 2 == expected
+
+            class TestWords:
+                @parametrize("word", ["taut", "rust"])
+                def test_length(self, word):
+                    assert len(word) == 4
+        "#,
+        ),
+    )?;
+
+    for isolation in ["process-per-test", "process-per-run"] {
+        let result = run_taut(
+            &project,
+            &["-v", "--no-cache", "--isolation", isolation, "."],
+        )?;
+        result.assert_failure();
+        result.assert_stdout_contains("4 passed, 1 failed");
+        result.assert_stdout_contains("test_double[3-7]");
+        result.assert_stdout_contains("assert 6 == 7");
+        result.assert_stdout_contains("TestWords::test_length[rust]");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn deselect_removes_exact_test_ids() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_three.py",
+        &dedent(
+            r#"
+            def test_a():
+                assert True
+
+            def test_b():
+                assert False, "known bad"
+
+            class TestGroup:
+                def test_c(self):
+                    assert True
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(
+        &project,
+        &[
+            "--no-cache",
+            "--deselect",
+            "test_three.py::test_b",
+            "--deselect",
+            "test_three.py::test_missing",
+            ".",
+        ],
+    )?;
+    result.assert_success();
+    result.assert_stdout_contains("2 passed");
+    result.assert_stdout_not_contains("failed");
+    result
+        .assert_stderr_contains("Warning: --deselect test_three.py::test_missing matched no tests");
+
+    Ok(())
+}
+
+#[test]
+fn quiet_prints_only_the_summary_line() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_quiet.py",
+        &dedent(
+            r#"
+            def test_one():
+                assert True
+
+            def test_two():
+                assert True
+
+            def test_three():
+                assert 1 == 2
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["-q", "--no-cache", "."])?;
+    result.assert_failure();
+    let lines: Vec<&str> = result
+        .stdout
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .collect();
+    assert_eq!(
+        lines.len(),
+        1,
+        "expected only the summary line:\n{}",
+        result.stdout
+    );
+    assert!(lines[0].starts_with("2 passed, 1 failed"), "{}", lines[0]);
+    result.assert_stdout_not_contains("..");
+    result.assert_stdout_not_contains("taut");
+
+    Ok(())
+}
+
+#[test]
+fn extra_verbose_prints_full_traceback_inline() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_deep.py",
+        &dedent(
+            r#"
+            def level(n):
+                if n == 0:
+                    assert False, "bottom of the stack"
+                level(n - 1)
+
+            def test_deep():
+                level(8)
+
+            def test_after():
+                assert True
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["-vv", "--no-cache", "--no-parallel", "."])?;
+    result.assert_failure();
+    let stdout = &result.stdout;
+    let traceback_end = stdout
+        .find("AssertionError: bottom of the stack")
+        .expect("full traceback printed");
+    let summary = stdout.find("1 passed, 1 failed").expect("summary printed");
+    assert!(traceback_end < summary);
+    assert!(stdout[..summary].contains("test_after"));
+
+    // A single -v only shows the start of the traceback
+    let result = run_taut(&project, &["-v", "--no-cache", "."])?;
+    result.assert_stdout_not_contains("AssertionError: bottom of the stack");
+
+    Ok(())
+}
+
+#[test]
+fn summary_counts_errored_tests_separately() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_files(&[
+        ("test_ok.py", "def test_pass():\n    assert True\n"),
+        ("test_fail.py", "def test_fail():\n    assert 1 == 2\n"),
+        (
+            "test_broken.py",
+            "import definitely_not_a_module\n\ndef test_never_runs():\n    pass\n",
+        ),
+    ])?;
+
+    let result = run_taut(&project, &["--no-cache", "."])?;
+    result.assert_failure();
+    result.assert_stdout_contains("1 passed, 1 failed, 1 errored");
+
+    Ok(())
+}
+
+#[test]
+fn failure_report_shows_captured_output() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_capture.py",
+        &dedent(
+            r#"
+            import sys
+
+            def test_diagnostic():
+                print("connecting to db at localhost:5432")
+                print("retrying after timeout", file=sys.stderr)
+                assert False
+
+            def test_noisy():
+                for i in range(200):
+                    print(f"line {i}")
+                assert False
+
+            def test_quiet_pass():
+                print("all good here")
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["--no-cache", "."])?;
+    result.assert_failure();
+    result.assert_stdout_contains("Captured stdout:");
+    result.assert_stdout_contains("connecting to db at localhost:5432");
+    result.assert_stdout_contains("Captured stderr:");
+    result.assert_stdout_contains("retrying after timeout");
+    // Long output keeps only its tail
+    result.assert_stdout_contains("150 earlier lines not shown");
+    result.assert_stdout_contains("line 199");
+    result.assert_stdout_not_contains("line 149\n");
+    // Passing tests' output only shows with --show-capture all
+    result.assert_stdout_not_contains("all good here");
+
+    let all = run_taut(&project, &["--no-cache", "--show-capture", "all", "."])?;
+    all.assert_stdout_contains("all good here");
+
+    let none = run_taut(&project, &["--no-cache", "--show-capture", "no", "."])?;
+    none.assert_stdout_not_contains("connecting to db");
+
+    Ok(())
+}
+
+#[test]
+fn failure_summary_is_grouped_by_file() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_alpha.py",
+        &dedent(
+            r#"
+            def test_alpha_fails():
+                assert 1 == 2
+        "#,
+        ),
+    )?;
+    project.write_file(
+        "test_beta.py",
+        &dedent(
+            r#"
+            def test_beta_ok():
+                assert True
+
+            def test_beta_first():
+                assert "a" == "b"
+
+            def test_beta_second():
+                assert [] == [1]
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["--no-cache", "."])?;
+    result.assert_failure();
+    result.assert_stdout_contains("1 passed, 3 failed");
+
+    let stdout = &result.stdout;
+    let beta = stdout.find("test_beta.py (2 failed)").expect("beta header");
+    let alpha = stdout
+        .find("test_alpha.py (1 failed)")
+        .expect("alpha header");
+    // Files with the most failures come first, each followed by its own tests
+    assert!(beta < alpha);
+    let beta_section = &stdout[beta..alpha];
+    assert!(beta_section.contains("test_beta_first"));
+    assert!(beta_section.contains("test_beta_second"));
+    assert!(stdout[alpha..].contains("test_alpha_fails"));
+
+    Ok(())
+}
+
+#[test]
+fn tb_option_controls_summary_traceback() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_tb.py",
+        &dedent(
+            r#"
+            def inner_helper():
+                assert 1 == 2
+
+            def outer_helper():
+                inner_helper()
+
+            def test_nested():
+                outer_helper()
+        "#,
+        ),
+    )?;
+
+    // Default is short: only the innermost frame
+    let result = run_taut(&project, &["--no-cache", "."])?;
+    result.assert_failure();
+    result.assert_stdout_contains("in inner_helper");
+    result.assert_stdout_not_contains("in outer_helper");
+
+    let result = run_taut(&project, &["--no-cache", "--tb", "long", "."])?;
+    result.assert_failure();
+    result.assert_stdout_contains("in outer_helper");
+    result.assert_stdout_contains("in inner_helper");
+    result.assert_stdout_contains("in test_nested");
+
+    let result = run_taut(&project, &["--no-cache", "--tb", "no", "."])?;
+    result.assert_failure();
+    result.assert_stdout_contains("assert 1 == 2");
+    result.assert_stdout_not_contains("File \"");
+    result.assert_stdout_not_contains("in inner_helper");
+
+    let result = run_taut(&project, &["--no-cache", "--tb", "full", "."])?;
+    result.assert_failure();
+    result.assert_stderr_contains("invalid value 'full' for '--tb <TB>'");
+
+    Ok(())
+}
+
+#[test]
+fn doctests_flag_runs_docstring_examples() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "mathutils.py",
+        &dedent(
+            r#"
+            def add(a, b):
+                """
+                >>> add(1, 2)
+                3
+                """
+                return a + b
+
+            def sub(a, b):
+                """
+                >>> sub(3, 1)
+                5
+                """
+                return a - b
+        "#,
+        ),
+    )?;
+    project.write_file(
+        "test_math.py",
+        &dedent(
+            r#"
+            def test_plain():
+                assert True
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["--no-cache", "--doctests", "."])?;
+    result.assert_failure();
+    result.assert_stdout_contains("2 passed");
+    result.assert_stdout_contains("1 failed");
+    result.assert_stdout_contains("doctest:sub");
+    result.assert_stdout_contains("Expected:");
+
+    // Without the flag only regular tests are collected
+    let result = run_taut(&project, &["--no-cache", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("1 passed");
+
+    Ok(())
+}
+
+#[test]
+fn stepwise_stops_at_failure_and_resumes_from_it() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    let with_b = |body: &str| {
+        dedent(&format!(
+            r#"
+            def test_a():
+                assert True
+
+            def test_b():
+                {}
+
+            def test_c():
+                assert True
+        "#,
+            body
+        ))
+    };
+    project.write_file("test_stepwise.py", &with_b("assert False, \"broken\""))?;
+
+    // First run stops at the failing test and never reaches test_c
+    let result = run_taut(&project, &["--no-cache", "--stepwise", "-v", "."])?;
+    result.assert_failure();
+    result.assert_stdout_contains("1 passed");
+    result.assert_stdout_contains("1 failed");
+    result.assert_stdout_not_contains("test_c");
+    result.assert_stdout_contains("Stepwise: stopped at");
+
+    // After the fix, the run resumes at test_b and continues past it
+    project.write_file("test_stepwise.py", &with_b("assert True"))?;
+    let result = run_taut(&project, &["--no-cache", "--stepwise", "-v", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("Stepwise: skipping 1 tests before");
+    result.assert_stdout_contains("2 passed");
+    result.assert_stdout_not_contains("test_a");
+
+    // A clean pass clears the stored position
+    let result = run_taut(&project, &["--no-cache", "--stepwise", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("3 passed");
+
+    Ok(())
+}
+
+#[test]
+fn collection_errors_are_reported_and_fail_the_run() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file("test_good.py", "def test_ok():\n    assert True\n")?;
+    project.write_file("test_broken.py", "def test_broken(\n")?;
+
+    let result = run_taut(&project, &["--no-cache", "."])?;
+    result.assert_failure();
+    result.assert_stdout_contains("1 passed");
+    result.assert_stdout_contains("Collection errors:");
+    result.assert_stdout_contains("test_broken.py");
+
+    let result = run_taut(
+        &project,
+        &["--no-cache", "--continue-on-collection-errors", "."],
+    )?;
+    result.assert_success();
+    result.assert_stdout_contains("Collection errors:");
+
+    Ok(())
+}
+
+#[test]
+fn misspelled_markers_warn_or_fail_with_strict_markers() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_typo.py",
+        &dedent(
+            r#"
+            def skp(f):
+                return f
+
+            @skp
+            def test_meant_to_skip():
+                assert True
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["--no-cache", "."])?;
+    result.assert_success();
+    result.assert_stderr_contains("unknown decorator @skp, did you mean @skip?");
+    result.assert_stdout_contains("1 passed");
+
+    let result = run_taut(&project, &["--no-cache", "--strict-markers", "."])?;
+    result.assert_failure();
+    result.assert_stdout_contains("Collection errors:");
+    result.assert_stdout_contains("did you mean @skip?");
+
+    Ok(())
+}
+
+#[test]
+fn before_all_and_after_all_hooks_wrap_the_run() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_files(&[
+        (
+            "pyproject.toml",
+            &dedent(
+                r#"
+                [tool.taut]
+                before_all = "echo ready > service.txt && echo started service"
+                after_all = "rm service.txt && echo stopped service"
+            "#,
+            ),
+        ),
+        (
+            "test_service.py",
+            &dedent(
+                r#"
+                from pathlib import Path
+
+                def test_service_is_up():
+                    here = Path(__file__).parent
+                    assert (here / "service.txt").read_text().strip() == "ready"
+            "#,
+            ),
+        ),
+    ])?;
+
+    let result = run_taut(&project, &["--no-cache", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("1 passed");
+    result.assert_stderr_contains("started service");
+    result.assert_stderr_contains("stopped service");
+    assert!(!project.path().join("service.txt").exists());
+
+    project.write_file(
+        "pyproject.toml",
+        "[tool.taut]\nbefore_all = \"echo cannot start >&2; exit 1\"\n",
+    )?;
+    let result = run_taut(&project, &["--no-cache", "."])?;
+    result.assert_failure();
+    result.assert_stderr_contains("cannot start");
+    result.assert_stdout_not_contains("passed");
+
+    Ok(())
+}
+
+#[test]
+fn dry_run_lists_selection_without_running() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_side_effect.py",
+        &dedent(
+            r#"
+            from pathlib import Path
+
+            def skip(reason):
+                return lambda f: f
+
+            def test_touches_file():
+                (Path(__file__).parent / "ran.txt").write_text("ran")
+
+            @skip("not today")
+            def test_skipped():
+                pass
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["--dry-run", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("Would run (1):");
+    result.assert_stdout_contains("test_side_effect.py::test_touches_file");
+    result.assert_stdout_contains("Would skip (1):");
+    result.assert_stdout_contains("test_side_effect.py::test_skipped (not today)");
+    assert!(!project.path().join("ran.txt").exists());
+
+    // Once recorded, the unchanged test would be skipped by the cache
+    run_taut(&project, &["."])?.assert_success();
+    std::fs::remove_file(project.path().join("ran.txt"))?;
+    let result = run_taut(&project, &["--dry-run", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("Would run (0):");
+    result.assert_stdout_contains("test_side_effect.py::test_touches_file (unchanged)");
+    assert!(!project.path().join("ran.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn profile_lists_every_phase() -> Result<()> {
+    let mut project = TempProject::new()?;
+    project.write_file("test_profile.py", "def test_one():\n    assert True\n")?;
+
+    let result = run_taut(&project, &["--profile", "."])?;
+    result.assert_success();
+    assert!(
+        result.stderr.contains("Profile:"),
+        "stderr: {}",
+        result.stderr
+    );
+    for phase in [
+        "find_test_files",
+        "extract_tests",
+        "index_files",
+        "select_tests",
+        "run_tests",
+        "total",
+    ] {
+        let line = result
+            .stderr
+            .lines()
+            .find(|line| line.trim_start().starts_with(phase))
+            .unwrap_or_else(|| panic!("no {phase} line in stderr: {}", result.stderr));
+        let value = line.split_whitespace().nth(1).unwrap_or_default();
+        assert!(
+            value
+                .strip_suffix("ms")
+                .is_some_and(|ms| ms.parse::<f64>().is_ok()),
+            "{phase} should be in milliseconds: {line}"
+        );
+    }
+
+    let plain = run_taut(&project, &["."])?;
+    assert!(!plain.stderr.contains("Profile:"));
+
+    Ok(())
+}
+
+#[test]
+fn collect_timeout_reports_slow_file_and_continues() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    let huge: String = (0..50_000)
+        .map(|i| format!("def test_{i}():\n    assert {i} == {i}\n"))
+        .collect();
+    project.write_files(&[
+        ("test_huge.py", huge.as_str()),
+        ("test_small.py", "def test_small():\n    assert True\n"),
+    ])?;
+
+    let started = std::time::Instant::now();
+    let result = run_taut(&project, &["list", "--collect-timeout", "0.05", "."])?;
+    assert!(
+        started.elapsed() < std::time::Duration::from_secs(30),
+        "Discovery should not wait for the slow file"
+    );
+
+    result.assert_stdout_contains("test_small.py::test_small");
+    result.assert_stdout_not_contains("test_huge.py::");
+    result.assert_stderr_contains("Timed out parsing");
+    result.assert_stderr_contains("test_huge.py");
+
+    Ok(())
+}
+
+#[test]
+fn bare_run_collects_from_configured_testpaths() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_files(&[
+        ("pyproject.toml", "[tool.taut]\ntestpaths = [\"tests\"]\n"),
+        ("tests/test_app.py", "def test_app(): assert True\n"),
+        (
+            "vendor/lib/test_vendored.py",
+            "def test_vendored(): assert False\n",
+        ),
+    ])?;
+
+    let result = run_taut(&project, &["--no-cache", "-v"])?;
+    result.assert_success();
+    result.assert_stdout_contains("test_app");
+    result.assert_stdout_not_contains("test_vendored");
+
+    let listed = run_taut(&project, &["list"])?;
+    listed.assert_stdout_contains("tests/test_app.py::test_app");
+    listed.assert_stdout_not_contains("test_vendored");
+
+    // Explicit paths still win
+    let result = run_taut(&project, &["--no-cache", "vendor"])?;
+    result.assert_failure();
+
+    Ok(())
+}
+
+#[test]
+fn flaky_reports_tests_that_flip_without_code_changes() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    // Fails the first time it runs, passes afterwards
+    project.write_file(
+        "test_flaky.py",
+        &dedent(
+            r#"
+            from pathlib import Path
+
+            MARKER = Path(__file__).with_name("ran_before")
+
+            def test_sometimes():
+                first = not MARKER.exists()
+                MARKER.touch()
+                assert not first
+
+            def test_stable():
+                assert True
+        "#,
+        ),
+    )?;
+
+    let empty = run_taut(&project, &["flaky"])?;
+    empty.assert_success();
+    empty.assert_stdout_contains("No flaky tests recorded.");
+
+    run_taut(&project, &["."])?.assert_failure();
+    run_taut(&project, &["."])?.assert_success();
+
+    let result = run_taut(&project, &["flaky"])?;
+    result.assert_success();
+    result.assert_stdout_contains("test_flaky.py::test_sometimes");
+    result.assert_stdout_contains("1 passed, 1 failed  [F.]");
+    result.assert_stdout_not_contains("test_stable");
+    result.assert_stdout_contains("1 flaky test(s)");
+
+    Ok(())
+}
+
+#[test]
+fn quiet_skips_hides_skipped_tests_but_counts_them() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    let source: String = (0..30)
+        .map(|i| format!("def test_{}(): assert True\n", i))
+        .collect();
+    project.write_file("test_many.py", &source)?;
+    run_taut(&project, &["."])?.assert_success();
+
+    let verbose = run_taut(&project, &["-v", "."])?;
+    verbose.assert_stdout_contains("(unchanged)");
+
+    let result = run_taut(&project, &["-v", "--quiet-skips", "."])?;
+    result.assert_success();
+    result.assert_stdout_not_contains("(unchanged)");
+    result.assert_stdout_contains("30 skipped");
+
+    let result = run_taut(&project, &["--quiet-skips", "."])?;
+    result.assert_success();
+    result.assert_stdout_not_contains("sss");
+    result.assert_stdout_contains("30 skipped");
+
+    Ok(())
+}
+
+#[test]
+fn summary_only_prints_a_single_line() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_mixed.py",
+        &dedent(
+            r#"
+            def skip(reason=None):
+                return lambda f: f
+
+            def test_pass():
+                print("noise")
+
+            def test_fail():
+                assert 1 == 2
+
+            @skip(reason="not today")
+            def test_skipped():
+                pass
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["--summary-only", "--shuffle", "."])?;
+    result.assert_failure();
+    let lines: Vec<&str> = result
+        .stdout
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .collect();
+    assert_eq!(lines.len(), 1, "stdout: {}", result.stdout);
+    assert!(
+        lines[0].contains("1 passed, 1 failed, 1 skipped"),
+        "stdout: {}",
+        result.stdout
+    );
+    result.assert_stderr_contains("Using --seed");
+
+    Ok(())
+}
+
+#[test]
+fn summary_only_keeps_annotations_off_stdout() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_mixed.py",
+        "def test_pass():\n    pass\n\ndef test_fail():\n    assert 1 == 2\n",
+    )?;
+
+    let result = run_taut_with_env(
+        &project,
+        &["--summary-only", "--github-annotations", "."],
+        &[("GITHUB_ACTIONS", "true")],
+    )?;
+    result.assert_failure();
+    let lines: Vec<&str> = result
+        .stdout
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .collect();
+    assert_eq!(lines.len(), 1, "stdout: {}", result.stdout);
+    assert!(
+        lines[0].contains("1 passed, 1 failed"),
+        "stdout: {}",
+        result.stdout
+    );
+    result.assert_stderr_contains("::error file=");
+
+    let result = run_taut(&project, &["--summary-only", "--explain", "."])?;
+    result.assert_failure();
+    result.assert_stderr_contains("cannot be used with");
+
+    Ok(())
+}
+
+#[test]
+fn bail_on_worker_crash_exits_with_code_2() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_crash.py",
+        &dedent(
+            r#"
+            import os
+
+            def test_crash():
+                os._exit(1)
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(
+        &project,
+        &[
+            "--no-cache",
+            "--isolation",
+            "process-per-run",
+            "--bail-on-worker-crash",
+            ".",
+        ],
+    )?;
+    assert_eq!(result.exit_code, 2, "stderr: {}", result.stderr);
+    // The ID is rootdir-relative even without a config file, not `./test_crash.py`
+    result.assert_stderr_contains(
+        "Worker crashed while running test_crash.py::test_crash (--bail-on-worker-crash)",
+    );
+
+    Ok(())
+}
+
+#[test]
+fn deadline_stops_starting_tests_and_exits_with_code_3() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    let tests: String = (0..6)
+        .map(|i| format!("def test_slow_{}():\n    time.sleep(0.5)\n\n", i))
+        .collect();
+    project.write_file("test_slow.py", &format!("import time\n\n{}", tests))?;
+
+    let result = run_taut(
+        &project,
+        &["--no-cache", "--no-parallel", "--deadline", "1.2", "."],
+    )?;
+    assert_eq!(result.exit_code, 3, "stdout: {}", result.stdout);
+    result.assert_stdout_contains("Deadline of 1.2s reached");
+
+    // Partial completion: some tests ran, the rest are counted as skipped
+    result.assert_stdout_contains(" passed");
+    result.assert_stdout_not_contains("0 passed");
+    result.assert_stdout_contains("deadline)");
+
+    Ok(())
+}
+
+#[test]
+fn skip_slow_and_only_slow_select_by_slow_marker() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_speed.py",
+        &dedent(
+            r#"
+            def mark(**kwargs):
+                return lambda f: f
+
+            @mark(slow=True)
+            def test_slow():
+                pass
+
+            def test_fast():
+                pass
+
+            @mark(slow=False)
+            def test_not_slow():
+                pass
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(
+        &project,
+        &["--no-cache", "-v", "--ordered-output", "--skip-slow", "."],
+    )?;
+    result.assert_success();
+    assert_eq!(
+        verbose_order(&result.stdout),
+        vec!["test_fast", "test_not_slow"]
+    );
+
+    let result = run_taut(
+        &project,
+        &["--no-cache", "-v", "--ordered-output", "--only-slow", "."],
+    )?;
+    result.assert_success();
+    assert_eq!(verbose_order(&result.stdout), vec!["test_slow"]);
+
+    let result = run_taut(&project, &["--skip-slow", "--only-slow", "."])?;
+    result.assert_failure();
+    result.assert_stderr_contains("cannot be used with");
+
+    Ok(())
+}
+
+// =============================================================================
+// List Command Tests
+// =============================================================================
+
+#[test]
+fn list_command_shows_discovered_tests() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_list.py",
+        &dedent(
+            r#"
+            def test_alpha(): pass
+            def test_beta(): pass
+
+            class TestClass:
+                def test_method(self): pass
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["list", "."])?;
+    result.assert_success();
+
+    // Should list all tests
+    assert!(
+        result.stdout.contains("test_alpha"),
+        "Should list test_alpha"
+    );
+    assert!(result.stdout.contains("test_beta"), "Should list test_beta");
+    assert!(
+        result.stdout.contains("test_method"),
+        "Should list test_method"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_ids_are_relative_to_rootdir() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file("pyproject.toml", "[project]\nname = \"demo\"\n")?;
+    project.write_file(
+        "tests/test_math.py",
+        &dedent(
+            r#"
+            def test_add():
+                assert 1 + 1 == 2
+        "#,
+        ),
+    )?;
+
+    // Listing from a subdirectory still reports IDs relative to the rootdir
+    let tests_dir = project.path().join("tests");
+    let result = run_taut_in_dir(&tests_dir, &["list", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("tests/test_math.py::test_add");
+    result.assert_stdout_not_contains("./test_math.py");
+
+    // ...and those IDs can be run from anywhere in the project
+    let result = run_taut_in_dir(&tests_dir, &["tests/test_math.py::test_add"])?;
+    result.assert_success();
+    result.assert_stdout_contains("1 passed");
+
+    Ok(())
+}
+
+#[test]
+fn test_ids_without_config_are_relative_to_the_current_directory() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file("tests/test_math.py", "def test_add(): pass\n")?;
+
+    for path in ["./tests/test_math.py", "tests/test_math.py", "."] {
+        let result = run_taut(&project, &["list", path])?;
+        result.assert_success();
+        result.assert_stdout_contains("tests/test_math.py::test_add");
+        result.assert_stdout_not_contains("./tests/test_math.py");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn list_command_with_filter() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_filter.py",
+        "def test_alpha(): pass\ndef test_beta(): pass\n",
+    )?;
+
+    let result = run_taut(&project, &["list", "-k", "alpha", "."])?;
+    result.assert_success();
+
+    assert!(result.stdout.contains("test_alpha"));
+    assert!(!result.stdout.contains("test_beta"));
+
+    Ok(())
+}
+
+#[test]
+fn list_command_json_output() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_json.py",
+        &dedent(
+            r#"
+            def skip(reason=None):
+                return lambda f: f
+
+            def test_plain():
+                pass
+
+            @skip("flaky")
+            def test_decorated():
+                pass
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["list", "--json", "."])?;
+    result.assert_success();
+
+    let items: serde_json::Value = serde_json::from_str(&result.stdout)?;
+    let items = items.as_array().expect("expected a JSON array");
+    assert_eq!(items.len(), 2);
+
+    let decorated = items
+        .iter()
+        .find(|item| item["function"] == "test_decorated")
+        .expect("test_decorated should be listed");
+    assert!(decorated["line"].as_u64().unwrap_or(0) > 0);
+    assert!(
+        decorated["file"]
+            .as_str()
+            .unwrap()
+            .ends_with("test_json.py")
+    );
+    assert_eq!(decorated["markers"][0]["name"], "skip");
+    assert_eq!(decorated["markers"][0]["args"]["reason"], "flaky");
+
+    Ok(())
+}
+
+#[test]
+fn list_json_includes_skip_reasons() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_reasons.py",
+        &dedent(
+            r#"
+            def skip(reason=None):
+                return lambda f: f
+
+            def test_cached():
+                pass
+
+            @skip("flaky")
+            def test_marked():
+                pass
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["."])?;
+    result.assert_success();
+    project.write_file("test_new.py", "def test_new(): pass\n")?;
+
+    let result = run_taut(&project, &["list", "--json", "."])?;
+    result.assert_success();
+    let items: serde_json::Value = serde_json::from_str(&result.stdout)?;
+    let reason = |function: &str| {
+        items
+            .as_array()
+            .expect("expected a JSON array")
+            .iter()
+            .find(|item| item["function"] == function)
+            .map(|item| item["skip_reason"].clone())
+            .unwrap_or_else(|| panic!("{} should be listed", function))
+    };
+
+    assert_eq!(reason("test_cached"), "unchanged");
+    assert_eq!(reason("test_marked"), "flaky");
+    assert!(reason("test_new").is_null());
+
+    Ok(())
+}
+
+#[test]
+fn list_markers_counts_marker_shapes_across_files() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_one.py",
+        &dedent(
+            r#"
+            from taut import mark, parallel, skip
+
+            @skip("flaky")
+            def test_skipped():
+                pass
+
+            @mark(slow=True)
+            @parallel()
+            def test_slow_parallel():
+                pass
+
+            def test_plain():
+                pass
+        "#,
+        ),
+    )?;
+    project.write_file(
+        "test_two.py",
+        &dedent(
+            r#"
+            from taut import mark, parallel
+
+            @mark(slow=True)
+            def test_slow():
+                pass
+
+            @parallel()
+            def test_parallel():
+                pass
+        "#,
+        ),
+    )?;
+
+    let result = run_taut(&project, &["list", "--markers", "."])?;
+    result.assert_success();
+    let rows: Vec<Vec<&str>> = result
+        .stdout
+        .lines()
+        .map(|line| {
+            line.split("  ")
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            vec!["@mark(slow=bool)", "2"],
+            vec!["@parallel()", "2"],
+            vec!["@skip(reason)", "1"],
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn list_tree_groups_tests_by_directory_file_and_class() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "tests/api/test_users.py",
+        &dedent(
+            r#"
+            class TestUsers:
+                def test_create(self): pass
+                def test_delete(self): pass
+
+            def test_helper(): pass
+        "#,
+        ),
+    )?;
+    project.write_file("tests/test_root.py", "def test_root(): pass\n")?;
+
+    let result = run_taut(&project, &["list", "--tree", "."])?;
+    result.assert_success();
+
+    let tree: Vec<&str> = result
+        .stdout
+        .lines()
+        .take_while(|l| !l.is_empty())
+        .collect();
+    assert_eq!(
+        tree,
+        vec![
+            "tests/",
+            "  api/",
+            "    test_users.py",
+            "      TestUsers",
+            "        test_create",
+            "        test_delete",
+            "      test_helper",
+            "  test_root.py",
+            "    test_root",
+        ]
+    );
+    result.assert_stdout_contains("4 tests");
+
+    // The -k filter prunes the tree
+    let result = run_taut(&project, &["list", "--tree", "-k", "delete", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("        test_delete");
+    result.assert_stdout_not_contains("test_create");
+    result.assert_stdout_not_contains("test_root");
+    assert_eq!(result.count_in_stdout("test_delete"), 1);
+
+    Ok(())
+}
+
+// =============================================================================
+// Cache Commands Tests
+// =============================================================================
+
+#[test]
+fn cache_info_shows_stats() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file("test_cache.py", "def test_ok(): assert True\n")?;
+
+    // Run tests to populate cache
+    run_taut(&project, &["."])?;
+
+    // Check cache info
+    let result = run_taut(&project, &["cache", "info"])?;
+    result.assert_success();
+
+    assert!(
+        result.stdout.contains("Cache") || result.stdout.contains("cache"),
+        "Should show cache info"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cache_clear_removes_cache() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file("test_clear.py", "def test_ok(): assert True\n")?;
+
+    // Run tests to populate cache
+    run_taut(&project, &["."])?;
+
+    // Clear cache
+    let result = run_taut(&project, &["cache", "clear"])?;
+    result.assert_success();
+
+    // After clearing, next run should run all tests (not skip)
+    let result2 = run_taut(&project, &["."])?;
+    result2.assert_success();
+
+    // Should not skip (cache was cleared)
+    // This might show "passed" without "skipped"
+
+    Ok(())
+}
+
+#[test]
+fn cache_export_and_import_roundtrip_keeps_skips() -> Result<()> {
+    let mut project = TempProject::new()?;
+    project.write_file("test_shared.py", "def test_ok(): assert True\n")?;
+    let bundle = project.path().join("taut-cache.json");
+    let bundle = bundle.to_str().unwrap();
+
+    run_taut(&project, &["."])?.assert_success();
+    run_taut(&project, &["cache", "export", bundle])?.assert_success();
+    run_taut(&project, &["cache", "clear"])?.assert_success();
+
+    let result = run_taut(&project, &["cache", "import", bundle])?;
+    result.assert_success();
+    result.assert_stdout_contains("Imported 1 tests");
+
+    let result = run_taut(&project, &["."])?;
+    result.assert_success();
+    result.assert_stdout_contains("1 skipped");
+
+    Ok(())
+}
+
+#[test]
+fn version_verbose_reports_python_and_cache_dir() -> Result<()> {
+    let project = TempProject::new()?;
+    let cache_dir = project.path().join("custom-cache");
+    let cache_arg = cache_dir.to_string_lossy().into_owned();
+
+    let result = run_taut(&project, &["version"])?;
+    result.assert_success();
+    result.assert_stdout_contains(&format!("taut {}", env!("CARGO_PKG_VERSION")));
+    result.assert_stdout_not_contains("Python:");
+
+    let result = run_taut(
+        &project,
+        &["version", "--verbose", "--cache-dir", &cache_arg],
+    )?;
+    result.assert_success();
+    result.assert_stdout_contains("Python: 3.");
+    result.assert_stdout_contains("Coverage tracer: sys.");
+    result.assert_stdout_contains(&format!("Cache directory: {}", cache_arg));
+    result.assert_stdout_contains("Workers: ");
+
+    Ok(())
+}
+
+#[test]
+fn cache_dir_flag_and_env_choose_cache_location() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file("test_cached.py", "def test_one(): assert True\n")?;
+    let cache_dir = project.path().join("custom-cache");
+    let cache_arg = cache_dir.to_string_lossy().into_owned();
+
+    let result = run_taut(&project, &["--cache-dir", &cache_arg, "."])?;
+    result.assert_success();
+    assert!(
+        cache_dir.join("depdb.json").is_file(),
+        "depdb.json should be written to --cache-dir"
+    );
+
+    // The same cache is found again through the environment variable
+    let result = run_taut_with_env(&project, &["."], &[("TAUT_CACHE_DIR", &cache_arg)])?;
+    result.assert_success();
+    result.assert_stdout_contains("1 skipped");
+
+    let info = run_taut(&project, &["cache", "info", "--cache-dir", &cache_arg])?;
+    info.assert_stdout_contains(&format!("Cache location: {}", cache_arg));
+
+    Ok(())
+}
+
+#[test]
+fn cache_per_branch_keeps_separate_depdbs() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file("pyproject.toml", "[tool.taut]\ncache_per_branch = true\n")?;
+    project.write_file("test_cached.py", "def test_one(): assert True\n")?;
+    let cache_dir = project.path().join("custom-cache");
+    let cache_arg = cache_dir.to_string_lossy().into_owned();
+
+    let args = ["--cache-dir", cache_arg.as_str(), "."];
+    let result = run_taut_with_env(&project, &args, &[("TAUT_CACHE_BRANCH", "main")])?;
+    result.assert_success();
+    assert!(cache_dir.join("main").join("depdb.json").is_file());
+
+    // A new branch starts from an empty cache and runs everything again
+    let result = run_taut_with_env(&project, &args, &[("TAUT_CACHE_BRANCH", "feature/login")])?;
+    result.assert_success();
+    result.assert_stdout_contains("1 passed");
+    assert!(cache_dir.join("feature_login").join("depdb.json").is_file());
+
+    let result = run_taut_with_env(&project, &args, &[("TAUT_CACHE_BRANCH", "main")])?;
+    result.assert_success();
+    result.assert_stdout_contains("1 skipped");
+
+    Ok(())
+}
+
+#[test]
+fn changed_since_selects_dependents_of_changed_code() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    let helpers_v1 = dedent(
+        r#"
+        def helper_a():
+            return 1
+
+        def helper_b():
+            return 2
+    "#,
+    );
+    project.write_files(&[
+        ("helpers.py", &helpers_v1),
+        (
+            "test_a.py",
+            "from helpers import helper_a\n\ndef test_a():\n    assert helper_a() > 0\n",
+        ),
+        (
+            "test_b.py",
+            "from helpers import helper_b\n\ndef test_b():\n    assert helper_b() > 0\n",
+        ),
+    ])?;
+    project.git(&["init", "-q"])?;
+    project.git(&["add", "helpers.py", "test_a.py", "test_b.py"])?;
+    project.git(&["commit", "-q", "-m", "v1"])?;
+    run_taut(&project, &["."])?.assert_success();
+
+    // Change helper_a and let a normal run record it
+    project.write_file("helpers.py", &helpers_v1.replace("return 1", "return 10"))?;
+    project.git(&["commit", "-q", "-am", "v2"])?;
+    run_taut(&project, &["."])?.assert_success();
+
+    // Against the previous commit, only helper_a's dependents (and new tests) run
+    project.write_file("test_new.py", "def test_new():\n    pass\n")?;
+    let result = run_taut(&project, &["-v", "--changed-since", "HEAD~1", "."])?;
+    result.assert_success();
+    result.assert_stdout_contains("test_a.py::test_a");
+    result.assert_stdout_contains("test_new.py::test_new");
+    result.assert_stdout_contains("2 passed");
+    result.assert_stdout_contains("1 skipped");
+
+    let result = run_taut(&project, &["--changed-since", "no-such-ref", "."])?;
+    result.assert_failure();
+    result.assert_stderr_contains("--changed-since no-such-ref");
+
+    Ok(())
+}
+
+#[test]
+fn bisect_finds_the_commit_that_broke_a_test() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_files(&[
+        ("calc.py", "def add(a, b):\n    return a + b\n"),
+        (
+            "test_calc.py",
+            "from calc import add\n\ndef test_add():\n    assert add(2, 2) == 4\n",
+        ),
+    ])?;
+    project.git(&["init", "-q"])?;
+    project.git(&["add", "calc.py", "test_calc.py"])?;
+    project.git(&["commit", "-q", "-m", "add calc"])?;
+    let good = project.git(&["rev-parse", "HEAD"])?;
+
+    project.write_file("README.md", "calc\n")?;
+    project.git(&["add", "README.md"])?;
+    project.git(&["commit", "-q", "-m", "readme"])?;
+
+    project.write_file("calc.py", "def add(a, b):\n    return a - b\n")?;
+    project.git(&["commit", "-q", "-am", "break add"])?;
+    let broken = project.git(&["rev-parse", "HEAD"])?;
+
+    for i in 0..3 {
+        project.write_file("README.md", &format!("calc {i}\n"))?;
+        project.git(&["commit", "-q", "-am", "docs"])?;
+    }
+    let branch = project.git(&["rev-parse", "--abbrev-ref", "HEAD"])?;
+
+    let result = run_taut(
+        &project,
+        &["bisect", "test_calc.py::test_add", "--good", &good],
+    )?;
+    result.assert_success();
+    result.assert_stdout_contains(&format!("First bad commit: {broken}"));
+
+    // The bisect session is cleaned up and the original branch checked out
+    assert_eq!(project.git(&["rev-parse", "--abbrev-ref", "HEAD"])?, branch);
+    assert!(
+        !project.path().join(".git/BISECT_LOG").exists(),
+        "bisect session was left running"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn worker_collects_coverage_with_either_tracer() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_files(&[
+        ("helpers.py", "def helper():\n    return 1\n"),
+        (
+            "test_cov.py",
+            &dedent(
+                r#"
+                from helpers import helper
+
+                def test_a():
+                    assert helper() == 1
+
+                def test_b():
+                    assert helper() == 1
+            "#,
+            ),
+        ),
+    ])?;
+
+    // "" keeps the tracer probed at startup; settrace forces the fallback
+    for tracer in ["", "settrace"] {
+        let cache_dir = project.path().join(format!("cache-{}", tracer));
+        let cache_arg = cache_dir.to_string_lossy().into_owned();
+        let env = [("TAUT_COVERAGE_TRACER", tracer)];
+
+        let args = [
+            "--isolation",
+            "process-per-run",
+            "--cache-dir",
+            &cache_arg,
+            ".",
+        ];
+        run_taut_with_env(&project, &args, &env)?.assert_success();
+
+        // Both tests ran in the same worker, so the second reused its strategy
+        for test in ["test_cov.py::test_a", "test_cov.py::test_b"] {
+            let explain = run_taut(&project, &["explain", test, "--cache-dir", &cache_arg])?;
+            explain.assert_success();
+            explain.assert_stdout_contains("\"name\": \"helper\"");
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn explain_flag_names_the_changed_dependency() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file(
+        "test_helper.py",
+        "def helper():\n    return 1\n\ndef test_helper():\n    assert helper() == 1\n",
+    )?;
+    let result = run_taut(&project, &["."])?;
+    result.assert_success();
+
+    project.write_file(
+        "test_helper.py",
+        "def helper():\n    return 2\n\ndef test_helper():\n    assert helper() == 1\n",
+    )?;
+    let result = run_taut(&project, &["--explain", "."])?;
+    result.assert_stdout_contains(
+        "test_helper.py::test_helper: re-running because helper() in test_helper.py changed",
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// Multiple Paths Tests
+// =============================================================================
+
+#[test]
+fn multiple_paths_combined() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.mkdir("dir_a")?;
+    project.mkdir("dir_b")?;
+
+    project.write_file("dir_a/test_a.py", "def test_a(): assert True\n")?;
+    project.write_file("dir_b/test_b.py", "def test_b(): assert True\n")?;
+
+    // Run on both directories
+    let result = run_taut(&project, &["dir_a", "dir_b"])?;
+    result.assert_success();
+
+    // Should find tests from both
+    assert!(
+        result.stdout.contains("2 passed") || result.count_in_stdout(".") >= 2,
+        "Should run tests from both directories"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn single_file_path() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    project.write_file("test_single.py", "def test_single(): assert True\n")?;
+    project.write_file("test_other.py", "def test_other(): assert True\n")?;
+
+    // Run on single file
+    let result = run_taut(&project, &["test_single.py"])?;
+    result.assert_success();
+
+    // Should only run the one test
+    assert!(
+        result.stdout.contains("1 passed"),
+        "Should only run one test from specified file"
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// Edge Cases
+// =============================================================================
+
+#[test]
+fn no_tests_found_message() -> Result<()> {
+    let mut project = TempProject::new()?;
+
+    // Create a non-test file
+    project.write_file("not_a_test.py", "def foo(): pass\n")?;
+
+    let result = run_taut(&project, &["."])?;
+
+    // Should indicate no tests found (not crash)
+    assert!(
+        result.stdout.contains("No tests") || result.stdout.contains("0"),
+        "Should indicate no tests: {}",
         result.stdout
     );
 
@@ -542,6 +3185,26 @@ fn empty_directory() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn exitcode_on_no_tests_applies_to_run_and_list() -> Result<()> {
+    let project = TempProject::new()?;
+
+    let result = run_taut(&project, &["."])?;
+    assert_eq!(result.exit_code, 0);
+
+    let result = run_taut(&project, &["--exitcode-on-no-tests", "5", "."])?;
+    assert_eq!(result.exit_code, 5, "stdout: {}", result.stdout);
+    result.assert_stdout_contains("No tests");
+
+    let result = run_taut(&project, &["list", "--exitcode-on-no-tests", "5", "."])?;
+    assert_eq!(result.exit_code, 5, "stdout: {}", result.stdout);
+
+    let result = run_taut(&project, &["watch", "--exitcode-on-no-tests", "5", "."])?;
+    assert_eq!(result.exit_code, 5, "stdout: {}", result.stdout);
+
+    Ok(())
+}
+
 #[test]
 fn test_with_unicode_output() -> Result<()> {
     let mut project = TempProject::new()?;
@@ -0,0 +1,110 @@
+//! Tests for test execution ordering (`--order`).
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use taut::cli::{OrderBy, order_tests};
+use taut::discovery::{TestItem, TestKind};
+
+fn item(function: &str) -> TestItem {
+    TestItem {
+        file: PathBuf::from("test_order.py"),
+        function: function.to_string(),
+        class: None,
+        line: 1,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    }
+}
+
+fn names(tests: &[TestItem]) -> Vec<&str> {
+    tests.iter().map(|t| t.function.as_str()).collect()
+}
+
+/// test_b has no recorded duration; test_a and test_d failed last time.
+fn ordered(order: OrderBy) -> Vec<TestItem> {
+    let durations: HashMap<&str, Duration> = [
+        ("test_c", Duration::from_millis(50)),
+        ("test_a", Duration::from_millis(500)),
+        ("test_d", Duration::from_millis(5)),
+    ]
+    .into_iter()
+    .collect();
+
+    let mut tests = vec![
+        item("test_c"),
+        item("test_a"),
+        item("test_b"),
+        item("test_d"),
+    ];
+    order_tests(
+        &mut tests,
+        order,
+        |t| durations.get(t.function.as_str()).copied(),
+        |t| ["test_a", "test_d"].contains(&t.function.as_str()),
+    );
+    tests
+}
+
+#[test]
+fn discovery_order_is_unchanged() {
+    assert_eq!(
+        names(&ordered(OrderBy::Discovery)),
+        vec!["test_c", "test_a", "test_b", "test_d"]
+    );
+}
+
+#[test]
+fn duration_desc_runs_slowest_first_and_unknown_first() {
+    assert_eq!(
+        names(&ordered(OrderBy::DurationDesc)),
+        vec!["test_b", "test_a", "test_c", "test_d"]
+    );
+}
+
+#[test]
+fn duration_asc_runs_fastest_first_and_unknown_last() {
+    assert_eq!(
+        names(&ordered(OrderBy::DurationAsc)),
+        vec!["test_d", "test_c", "test_a", "test_b"]
+    );
+}
+
+#[test]
+fn name_order_is_alphabetical() {
+    assert_eq!(
+        names(&ordered(OrderBy::Name)),
+        vec!["test_a", "test_b", "test_c", "test_d"]
+    );
+}
+
+#[test]
+fn order_by_rejects_unknown_values() {
+    assert_eq!(
+        OrderBy::from_str("duration-desc", false),
+        Ok(OrderBy::DurationDesc)
+    );
+    assert!(OrderBy::from_str("bogus", false).is_err());
+}
+
+#[test]
+fn failed_first_keeps_discovery_order_otherwise() {
+    assert_eq!(
+        names(&ordered(OrderBy::FailedFirst)),
+        vec!["test_a", "test_d", "test_c", "test_b"]
+    );
+}
+
+#[test]
+fn order_flag_values_parse() {
+    let parse = |value| OrderBy::from_str(value, false);
+    assert_eq!(parse("file"), Ok(OrderBy::Discovery));
+    assert_eq!(parse("slowest-first"), Ok(OrderBy::DurationDesc));
+    assert_eq!(parse("failed-first"), Ok(OrderBy::FailedFirst));
+    assert_eq!(parse("random"), Ok(OrderBy::Random));
+}
@@ -0,0 +1,68 @@
+//! Tests for watch-mode event debouncing.
+
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use notify::event::{EventKind, ModifyKind};
+use taut::cli::debounce_changes;
+
+fn modified(path: &str) -> notify::Event {
+    notify::Event::new(EventKind::Modify(ModifyKind::Any)).add_path(PathBuf::from(path))
+}
+
+#[test]
+fn burst_of_events_is_coalesced() {
+    let (tx, rx) = mpsc::channel();
+
+    // An editor saving several files (and touching the same one twice)
+    tx.send(modified("src/models.py")).unwrap();
+    tx.send(modified("src/models.py")).unwrap();
+    tx.send(modified("README.md")).unwrap();
+
+    let late = tx.clone();
+    let sender = thread::spawn(move || {
+        // Arrives within the window after the previous event, so it joins the batch
+        thread::sleep(Duration::from_millis(30));
+        late.send(modified("tests/test_models.py")).unwrap();
+    });
+
+    let first = modified("src/views.py");
+    let changed = debounce_changes(&rx, first, Duration::from_millis(200));
+    sender.join().unwrap();
+
+    assert_eq!(
+        changed,
+        vec![
+            PathBuf::from("src/views.py"),
+            PathBuf::from("src/models.py"),
+            PathBuf::from("tests/test_models.py"),
+        ]
+    );
+}
+
+#[test]
+fn events_after_the_window_start_a_new_batch() {
+    let (tx, rx) = mpsc::channel();
+
+    let sender = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        tx.send(modified("later.py")).unwrap();
+    });
+
+    let changed = debounce_changes(&rx, modified("first.py"), Duration::from_millis(20));
+    assert_eq!(changed, vec![PathBuf::from("first.py")]);
+
+    sender.join().unwrap();
+    let next = rx.recv().unwrap();
+    assert_eq!(next.paths, vec![PathBuf::from("later.py")]);
+}
+
+#[test]
+fn non_python_changes_are_ignored() {
+    let (_tx, rx) = mpsc::channel();
+
+    let changed = debounce_changes(&rx, modified("notes.txt"), Duration::from_millis(10));
+    assert!(changed.is_empty());
+}
@@ -13,14 +13,18 @@ mod helpers;
 
 use std::fs;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use tempfile::TempDir;
 
 use helpers::dedent;
-use taut::discovery::TestItem;
-use taut::runner::{IsolationMode, run_tests};
+use taut::discovery::{TestItem, TestKind};
+use taut::runner::{
+    AsyncMode, ErrorCategory, FixtureFailure, FixturePhase, ImportMode, IsolationMode, RunOptions,
+    SkipKind, WarningsMode, run_tests, run_tests_with_options,
+};
+use taut::worker_pool::WorkerCrashed;
 
 fn write_file(path: &std::path::Path, content: &str) -> Result<()> {
     if let Some(parent) = path.parent() {
@@ -45,7 +49,11 @@ fn runs_passing_test() -> Result<()> {
         function: "test_ok".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -78,7 +86,11 @@ fn runs_failing_assertion() -> Result<()> {
         function: "test_fail".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -114,7 +126,11 @@ fn runs_failing_exception() -> Result<()> {
         function: "test_raises".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -157,7 +173,11 @@ fn captures_stdout() -> Result<()> {
         function: "test_prints".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -203,7 +223,11 @@ fn captures_stderr() -> Result<()> {
         function: "test_stderr".to_string(),
         class: None,
         line: 2,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -254,7 +278,11 @@ fn runs_async_test() -> Result<()> {
         function: "test_async".to_string(),
         class: None,
         line: 3,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -301,7 +329,11 @@ fn async_test_can_use_await() -> Result<()> {
         function: "test_await".to_string(),
         class: None,
         line: 7,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -318,6 +350,78 @@ fn async_test_can_use_await() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn shared_async_mode_keeps_one_loop_for_fixture_and_test() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_loop_bound.py");
+    write_file(
+        &test_file,
+        &dedent(
+            r#"
+            import asyncio
+
+            # Bound to whichever loop ran setup_function
+            _ready = None
+
+            async def setup_function():
+                global _ready
+                loop = asyncio.get_running_loop()
+                _ready = loop.create_future()
+                loop.call_later(0.05, _ready.set_result, "ready")
+
+            async def test_uses_loop_bound_future():
+                assert await _ready == "ready"
+        "#,
+        ),
+    )?;
+
+    let item = TestItem {
+        file: test_file,
+        function: "test_uses_loop_bound_future".to_string(),
+        class: None,
+        line: 13,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    for isolation in [IsolationMode::ProcessPerTest, IsolationMode::ProcessPerRun] {
+        let run = |async_mode| {
+            let options = RunOptions {
+                parallel: false,
+                isolation,
+                async_mode,
+                ..RunOptions::default()
+            };
+            run_tests_with_options(std::slice::from_ref(&item), &options, |_| {})
+        };
+
+        let shared = run(AsyncMode::Shared)?;
+        assert!(
+            shared.results[0].passed,
+            "{:?}: {:?}",
+            isolation, shared.results[0].error
+        );
+
+        // asyncio.run gives the test a new loop, and the future's loop is gone
+        let per_test = run(AsyncMode::PerTest)?;
+        let error = per_test.results[0]
+            .error
+            .as_ref()
+            .expect("future from another loop should fail");
+        assert!(
+            error.message.contains("different loop"),
+            "{:?}: {}",
+            isolation,
+            error.message
+        );
+    }
+
+    Ok(())
+}
+
 // =============================================================================
 // Class-based Tests
 // =============================================================================
@@ -342,7 +446,11 @@ fn runs_class_method_test() -> Result<()> {
         function: "test_add".to_string(),
         class: Some("TestMath".to_string()),
         line: 2,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -359,6 +467,195 @@ fn runs_class_method_test() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn runs_unittest_test_case_through_unittest() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_legacy.py");
+    write_file(
+        &test_file,
+        &dedent(
+            r#"
+            import unittest
+
+            class TestLegacy(unittest.TestCase):
+                def setUp(self):
+                    self.value = 2
+                    self.addCleanup(print, "cleaned up")
+
+                def test_equal(self):
+                    self.assertEqual(self.value, 3)
+
+                def test_skipped(self):
+                    self.skipTest("needs a database")
+
+                def test_ok(self):
+                    self.assertTrue(self.value)
+        "#,
+        ),
+    )?;
+
+    let item = |function: &str| TestItem {
+        file: test_file.clone(),
+        function: function.to_string(),
+        class: Some("TestLegacy".to_string()),
+        line: 1,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+    let items = [item("test_equal"), item("test_skipped"), item("test_ok")];
+
+    for isolation in [IsolationMode::ProcessPerTest, IsolationMode::ProcessPerRun] {
+        let results = run_tests(&items, false, None, false, isolation, |_| {})?;
+
+        let failed = &results.results[0];
+        assert!(!failed.passed && !failed.skipped && !failed.errored);
+        let error = failed
+            .error
+            .as_ref()
+            .expect("assertEqual failure has an error");
+        assert!(
+            error.message.contains("2 != 3"),
+            "unexpected message: {}",
+            error.message
+        );
+        assert_eq!(error.category, Some(ErrorCategory::Assertion));
+
+        let skipped = &results.results[1];
+        assert!(skipped.skipped, "skipTest() should skip, got {skipped:?}");
+        assert!(skipped.error.is_none());
+        assert_eq!(skipped.skip_kind, Some(SkipKind::Runtime));
+        assert_eq!(skipped.skip_reason.as_deref(), Some("needs a database"));
+
+        let passed = &results.results[2];
+        assert!(passed.passed);
+        assert!(
+            passed
+                .stdout
+                .as_deref()
+                .is_some_and(|out| out.contains("cleaned up")),
+            "addCleanup() callbacks should run"
+        );
+
+        assert!(!results.all_passed());
+        assert_eq!(results.skipped_count(), 1);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn warnings_are_reported_or_fail_the_test_in_error_mode() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_warn.py");
+    write_file(
+        &test_file,
+        &dedent(
+            r#"
+            import warnings
+
+            def test_deprecated():
+                warnings.warn("old_api() is deprecated", DeprecationWarning)
+        "#,
+        ),
+    )?;
+
+    let item = TestItem {
+        file: test_file,
+        function: "test_deprecated".to_string(),
+        class: None,
+        line: 4,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    for isolation in [IsolationMode::ProcessPerTest, IsolationMode::ProcessPerRun] {
+        let run = |warnings| {
+            let options = RunOptions {
+                parallel: false,
+                isolation,
+                warnings,
+                ..RunOptions::default()
+            };
+            run_tests_with_options(std::slice::from_ref(&item), &options, |_| {})
+        };
+
+        let reported = run(WarningsMode::Default)?;
+        let result = &reported.results[0];
+        assert!(result.passed, "{:?}: {:?}", isolation, result.error);
+        assert_eq!(result.warnings.len(), 1, "{:?}", result.warnings);
+        assert!(result.warnings[0].contains("DeprecationWarning: old_api() is deprecated"));
+
+        let failed = run(WarningsMode::Error)?;
+        let result = &failed.results[0];
+        assert!(
+            !result.passed,
+            "{:?}: error mode should fail the test",
+            isolation
+        );
+        let error = result.error.as_ref().unwrap();
+        assert_eq!(error.message, "DeprecationWarning: old_api() is deprecated");
+
+        let ignored = run(WarningsMode::Ignore)?;
+        assert!(ignored.results[0].passed);
+        assert!(ignored.results[0].warnings.is_empty());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn runs_nested_class_method_test() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_nested_class.py");
+    write_file(
+        &test_file,
+        &dedent(
+            r#"
+            class TestOuter:
+                class TestInner:
+                    def test_inner(self):
+                        assert type(self).__qualname__ == "TestOuter.TestInner"
+        "#,
+        ),
+    )?;
+
+    let item = TestItem {
+        file: test_file,
+        function: "test_inner".to_string(),
+        class: Some("TestOuter.TestInner".to_string()),
+        line: 3,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    for isolation in [IsolationMode::ProcessPerTest, IsolationMode::ProcessPerRun] {
+        let results = run_tests(
+            std::slice::from_ref(&item),
+            false,
+            None,
+            false,
+            isolation,
+            |_| {},
+        )?;
+        assert!(
+            results.results[0].passed,
+            "{:?}: {:?}",
+            isolation, results.results[0].error
+        );
+    }
+
+    Ok(())
+}
+
 #[test]
 fn runs_setup_and_teardown() -> Result<()> {
     let tmp = TempDir::new()?;
@@ -385,7 +682,11 @@ fn runs_setup_and_teardown() -> Result<()> {
         function: "test_uses_setup".to_string(),
         class: Some("TestWithSetup".to_string()),
         line: 8,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -407,99 +708,828 @@ fn runs_setup_and_teardown() -> Result<()> {
 }
 
 #[test]
-fn setup_failure_fails_test() -> Result<()> {
+fn setup_failure_fails_test() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_setup_fail.py");
+    write_file(
+        &test_file,
+        &dedent(
+            r#"
+            class TestSetupFails:
+                def setUp(self):
+                    raise RuntimeError("setup failed")
+
+                def test_never_runs(self):
+                    assert True
+        "#,
+        ),
+    )?;
+
+    let item = TestItem {
+        file: test_file,
+        function: "test_never_runs".to_string(),
+        class: Some("TestSetupFails".to_string()),
+        line: 5,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    let results = run_tests(
+        &[item],
+        false,
+        None,
+        false,
+        IsolationMode::ProcessPerTest,
+        |_| {},
+    )?;
+
+    assert!(!results.results[0].passed);
+    let error = results.results[0].error.as_ref().unwrap();
+    assert!(
+        error.message.contains("setup failed") || error.message.contains("RuntimeError"),
+        "Error should mention setup failure: {}",
+        error.message
+    );
+
+    Ok(())
+}
+
+#[test]
+fn teardown_runs_after_failure() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_teardown.py");
+    let marker_file = tmp.path().join("teardown_ran.txt");
+
+    write_file(
+        &test_file,
+        &format!(
+            r#"
+class TestTeardownAfterFailure:
+    def setUp(self):
+        pass
+
+    def tearDown(self):
+        with open({:?}, 'w') as f:
+            f.write('teardown ran')
+
+    def test_fails(self):
+        assert False
+"#,
+            marker_file
+        ),
+    )?;
+
+    let item = TestItem {
+        file: test_file,
+        function: "test_fails".to_string(),
+        class: Some("TestTeardownAfterFailure".to_string()),
+        line: 10,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    let results = run_tests(
+        &[item],
+        false,
+        None,
+        false,
+        IsolationMode::ProcessPerTest,
+        |_| {},
+    )?;
+
+    assert!(!results.results[0].passed);
+
+    // Check that tearDown ran despite test failure
+    assert!(
+        marker_file.exists(),
+        "tearDown should run even when test fails"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn setup_fixture_error_names_fixture_and_phase() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_fixture_error.py");
+    write_file(
+        &test_file,
+        &dedent(
+            r#"
+            class TestBrokenFixture:
+                def setUp(self):
+                    raise RuntimeError("database unavailable")
+
+                def test_never_runs(self):
+                    assert True
+        "#,
+        ),
+    )?;
+
+    let item = TestItem {
+        file: test_file,
+        function: "test_never_runs".to_string(),
+        class: Some("TestBrokenFixture".to_string()),
+        line: 5,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    let results = run_tests(
+        &[item],
+        false,
+        None,
+        false,
+        IsolationMode::ProcessPerTest,
+        |_| {},
+    )?;
+
+    assert!(!results.results[0].passed);
+    let error = results.results[0].error.as_ref().unwrap();
+    assert_eq!(
+        error.fixture,
+        Some(FixtureFailure {
+            name: "setUp".to_string(),
+            phase: FixturePhase::Setup,
+        })
+    );
+    assert!(
+        error.message.contains("setUp") && error.message.contains("database unavailable"),
+        "Error should name the fixture and the cause: {}",
+        error.message
+    );
+
+    Ok(())
+}
+
+#[test]
+fn hanging_setup_fixture_times_out() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_fixture_hang.py");
+    write_file(
+        &test_file,
+        &dedent(
+            r#"
+            import time
+
+            class TestHangingFixture:
+                def setUp(self):
+                    time.sleep(30)
+
+                def test_never_runs(self):
+                    assert True
+        "#,
+        ),
+    )?;
+
+    let item = TestItem {
+        file: test_file,
+        function: "test_never_runs".to_string(),
+        class: Some("TestHangingFixture".to_string()),
+        line: 7,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    let options = RunOptions {
+        parallel: false,
+        fixture_setup_timeout: Some(Duration::from_millis(200)),
+        ..RunOptions::default()
+    };
+    let results = run_tests_with_options(&[item], &options, |_| {})?;
+
+    assert!(!results.results[0].passed);
+    assert!(
+        results.results[0].duration < Duration::from_secs(10),
+        "Timeout should abort the fixture early, took {:?}",
+        results.results[0].duration
+    );
+    let error = results.results[0].error.as_ref().unwrap();
+    assert_eq!(
+        error.fixture.as_ref().map(|f| f.phase),
+        Some(FixturePhase::Setup)
+    );
+    assert!(
+        error.message.contains("setUp") && error.message.contains("timed out"),
+        "Error should report the fixture timeout: {}",
+        error.message
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// Error Categories
+// =============================================================================
+
+fn run_single(file: &std::path::Path, function: &str) -> Result<taut::runner::TestResult> {
+    let item = TestItem {
+        file: file.to_path_buf(),
+        function: function.to_string(),
+        class: None,
+        line: 1,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+    let results = run_tests(
+        &[item],
+        false,
+        None,
+        false,
+        IsolationMode::ProcessPerTest,
+        |_| {},
+    )?;
+    Ok(results.results.into_iter().next().unwrap())
+}
+
+#[test]
+fn assertion_error_categorized_as_assertion() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_cat.py");
+    write_file(&test_file, "def test_assert(): assert 1 == 2\n")?;
+
+    let result = run_single(&test_file, "test_assert")?;
+    let error = result.error.expect("test should fail");
+    assert_eq!(error.category, Some(ErrorCategory::Assertion));
+
+    Ok(())
+}
+
+#[test]
+fn value_error_categorized_with_type_name() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_cat.py");
+    write_file(&test_file, "def test_value(): raise ValueError('bad')\n")?;
+
+    let result = run_single(&test_file, "test_value")?;
+    let error = result.error.expect("test should fail");
+    assert_eq!(
+        error.category,
+        Some(ErrorCategory::Exception {
+            type_name: "ValueError".to_string()
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn import_error_categorized_as_import() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_cat.py");
+    write_file(
+        &test_file,
+        "import definitely_not_a_module\n\ndef test_ok(): assert True\n",
+    )?;
+
+    let result = run_single(&test_file, "test_ok")?;
+    let error = result.error.expect("test should fail");
+    assert_eq!(error.category, Some(ErrorCategory::Import));
+
+    Ok(())
+}
+
+#[test]
+fn import_error_marks_result_errored_but_assertion_does_not() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let broken = tmp.path().join("test_broken.py");
+    let failing = tmp.path().join("test_failing.py");
+    write_file(
+        &broken,
+        "import definitely_not_a_module\n\ndef test_ok(): assert True\n",
+    )?;
+    write_file(&failing, "def test_assert(): assert 1 == 2\n")?;
+
+    let result = run_single(&broken, "test_ok")?;
+    assert!(!result.passed);
+    assert!(result.errored, "import error should count as errored");
+
+    let result = run_single(&failing, "test_assert")?;
+    assert!(!result.passed);
+    assert!(!result.errored, "assertion failure should count as failed");
+
+    let result = run_single_in_worker(&broken, "test_ok")?;
+    assert!(result.errored);
+    let result = run_single_in_worker(&failing, "test_assert")?;
+    assert!(!result.errored);
+
+    Ok(())
+}
+
+// =============================================================================
+// Assertion Rewriting
+// =============================================================================
+
+#[test]
+fn rewritten_assert_reports_operand_values() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_rewrite.py");
+    write_file(
+        &test_file,
+        "def value():\n    return 3\n\ndef test_eq():\n    assert value() == 4\n",
+    )?;
+
+    let result = run_single(&test_file, "test_eq")?;
+    let error = result.error.expect("test should fail");
+    assert!(
+        error.message.contains("assert 3 == 4"),
+        "message should show operand values: {}",
+        error.message
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// Assertion Helpers
+// =============================================================================
+
+#[test]
+fn taut_raises_is_importable_in_worker() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_helpers.py");
+    write_file(
+        &test_file,
+        &dedent(
+            r#"
+            import taut
+
+            def test_raises():
+                with taut.raises(ValueError) as info:
+                    int("nope")
+                assert "nope" in str(info.value)
+
+            def test_not_raised():
+                with taut.raises(ValueError):
+                    pass
+        "#,
+        ),
+    )?;
+
+    let result = run_single_in_worker(&test_file, "test_raises")?;
+    assert!(result.passed, "{:?}", result.error);
+
+    let result = run_single_in_worker(&test_file, "test_not_raised")?;
+    let error = result.error.expect("test should fail");
+    assert_eq!(error.message, "DID NOT RAISE ValueError");
+
+    Ok(())
+}
+
+#[test]
+fn taut_skip_called_in_a_test_skips_it() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_runtime_skip.py");
+    write_file(
+        &test_file,
+        &dedent(
+            r#"
+            import taut
+
+            def test_offline():
+                taut.skip("no network")
+                assert False, "unreachable"
+
+            @taut.skip("decided statically")
+            def test_marked():
+                pass
+        "#,
+        ),
+    )?;
+
+    for result in [
+        run_single_in_worker(&test_file, "test_offline")?,
+        run_single(&test_file, "test_offline")?,
+    ] {
+        assert!(result.skipped && !result.passed, "{:?}", result.error);
+        assert_eq!(result.skip_kind, Some(SkipKind::Runtime));
+        assert_eq!(result.skip_reason.as_deref(), Some("no network"));
+    }
+
+    // As a decorator it is only a marker, read before the test runs
+    let result = run_single_in_worker(&test_file, "test_marked")?;
+    assert!(result.passed, "{:?}", result.error);
+
+    Ok(())
+}
+
+// =============================================================================
+// Module Fixtures (setup_function / teardown_function)
+// =============================================================================
+
+#[test]
+fn setup_function_value_is_visible_to_test() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_xunit.py");
+    write_file(
+        &test_file,
+        &dedent(
+            r#"
+            state = {}
+
+            def setup_function(func):
+                state["name"] = func.__name__
+
+            def test_sees_setup():
+                assert state["name"] == "test_sees_setup"
+        "#,
+        ),
+    )?;
+
+    let result = run_single(&test_file, "test_sees_setup")?;
+    assert!(result.passed, "error: {:?}", result.error);
+
+    Ok(())
+}
+
+#[test]
+fn teardown_function_runs_after_failing_assertion() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_xunit.py");
+    let marker = tmp.path().join("teardown.log");
+    write_file(
+        &test_file,
+        &dedent(&format!(
+            r#"
+            def setup_function():
+                pass
+
+            def teardown_function():
+                with open({marker:?}, "w") as f:
+                    f.write("done")
+
+            def test_fails():
+                assert 1 == 2
+        "#,
+            marker = marker.display().to_string()
+        )),
+    )?;
+
+    let result = run_single(&test_file, "test_fails")?;
+    assert!(!result.passed);
+    let error = result.error.expect("test should fail");
+    assert_eq!(error.category, Some(ErrorCategory::Assertion));
+    assert_eq!(fs::read_to_string(&marker)?, "done");
+
+    Ok(())
+}
+
+#[test]
+fn failing_teardown_is_attached_to_the_test_failure() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_teardown_too.py");
+    write_file(
+        &test_file,
+        &dedent(
+            r#"
+            import unittest
+
+            def teardown_function():
+                raise RuntimeError("module teardown broke")
+
+            def test_fails():
+                assert 1 == 2
+
+            class TestLegacy(unittest.TestCase):
+                @classmethod
+                def tearDownClass(cls):
+                    raise RuntimeError("class teardown broke")
+
+                def test_equal(self):
+                    self.assertEqual(2, 3)
+        "#,
+        ),
+    )?;
+
+    let result = run_single(&test_file, "test_fails")?;
+    let error = result.error.expect("test should fail");
+    assert_eq!(error.category, Some(ErrorCategory::Assertion));
+    assert!(error.fixture.is_none(), "{error:?}");
+    assert!(
+        error.message.contains("teardown_function")
+            && error.message.contains("module teardown broke"),
+        "{}",
+        error.message
+    );
+
+    let item = TestItem {
+        file: test_file.clone(),
+        function: "test_equal".to_string(),
+        class: Some("TestLegacy".to_string()),
+        line: 1,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+    let results = run_tests(
+        &[item],
+        false,
+        None,
+        false,
+        IsolationMode::ProcessPerTest,
+        |_| {},
+    )?;
+    let error = results.results[0].error.as_ref().expect("test should fail");
+    assert_eq!(error.category, Some(ErrorCategory::Assertion));
+    assert!(
+        error.message.contains("2 != 3") && error.message.contains("class teardown broke"),
+        "{}",
+        error.message
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// Worker Crash Reporting
+// =============================================================================
+
+fn run_single_in_worker(
+    file: &std::path::Path,
+    function: &str,
+) -> Result<taut::runner::TestResult> {
+    let item = TestItem {
+        file: file.to_path_buf(),
+        function: function.to_string(),
+        class: None,
+        line: 1,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+    let results = run_tests(
+        &[item],
+        false,
+        None,
+        false,
+        IsolationMode::ProcessPerRun,
+        |_| {},
+    )?;
+    Ok(results.results.into_iter().next().unwrap())
+}
+
+#[test]
+fn import_time_error_traceback_reaches_result() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_import_boom.py");
+    write_file(
+        &test_file,
+        "raise RuntimeError('boom at import')\n\ndef test_never(): pass\n",
+    )?;
+
+    let result = run_single_in_worker(&test_file, "test_never")?;
+    let error = result.error.expect("test should fail");
+    assert!(
+        error.message.contains("boom at import"),
+        "{}",
+        error.message
+    );
+    let traceback = error.traceback.expect("traceback should be attached");
+    assert!(traceback.contains("RuntimeError"), "{}", traceback);
+
+    Ok(())
+}
+
+#[test]
+fn exception_type_reported_separately_from_message() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_exc_type.py");
+    write_file(&test_file, "def test_value(): raise ValueError('boom')\n")?;
+
+    let result = run_single_in_worker(&test_file, "test_value")?;
+    let error = result.error.expect("test should fail");
+    assert_eq!(error.exception_type.as_deref(), Some("ValueError"));
+    assert_eq!(error.message, "ValueError: boom");
+
+    // The one-shot runner reports it too
+    let result = run_single(&test_file, "test_value")?;
+    let error = result.error.expect("test should fail");
+    assert_eq!(error.exception_type.as_deref(), Some("ValueError"));
+
+    Ok(())
+}
+
+#[test]
+fn raw_stdout_writes_do_not_corrupt_protocol() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_raw_stdout.py");
+    write_file(
+        &test_file,
+        &dedent(
+            r#"
+            import os
+
+            def test_raw():
+                # Bytes that would look like a huge frame length, then invalid UTF-8
+                os.write(1, b"\xff\xff\xff\x7f\x00\xfe{not msgpack}\n")
+                print("after")
+        "#,
+        ),
+    )?;
+
+    let result = run_single_in_worker(&test_file, "test_raw")?;
+    assert!(result.passed, "{:?}", result.error);
+    assert_eq!(result.stdout.as_deref(), Some("after\n"));
+
+    Ok(())
+}
+
+#[test]
+fn pool_recovers_from_externally_killed_worker() -> Result<()> {
+    use taut::markers::{Marker, MarkerArgs};
+
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_killed.py");
+    write_file(
+        &test_file,
+        &dedent(
+            r#"
+            import os, signal, threading, time
+
+            def test_arm_killer():
+                # Kill this worker from outside the test, after it has reported back
+                threading.Timer(0.2, os.kill, (os.getpid(), signal.SIGKILL)).start()
+
+            def test_slow():
+                time.sleep(1)
+
+            def test_after():
+                assert True
+        "#,
+        ),
+    )?;
+
+    let parallel = Marker {
+        name: "parallel".to_string(),
+        args: MarkerArgs::default(),
+    };
+    let items: Vec<_> = ["test_arm_killer", "test_slow", "test_after"]
+        .iter()
+        .map(|function| TestItem {
+            file: test_file.clone(),
+            function: function.to_string(),
+            class: None,
+            line: 1,
+            decorator_line: None,
+            markers: vec![parallel.clone()],
+            params: vec![],
+            kind: TestKind::Function,
+            rootdir: None,
+        })
+        .collect();
+
+    let options = RunOptions {
+        workers: Some(1),
+        isolation: IsolationMode::ProcessPerRun,
+        ..RunOptions::default()
+    };
+    let results = run_tests_with_options(&items, &options, |_| {})?;
+
+    assert_eq!(results.results.len(), 3);
+    for result in &results.results {
+        assert!(
+            result.passed,
+            "{}: {:?}",
+            result.item.function, result.error
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bail_on_worker_crash_aborts_the_run() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let test_file = tmp.path().join("test_bail.py");
+    write_file(
+        &test_file,
+        "import os\n\ndef test_exits():\n    os._exit(1)\n\ndef test_after():\n    assert True\n",
+    )?;
+
+    let items: Vec<_> = ["test_exits", "test_after"]
+        .iter()
+        .map(|function| TestItem {
+            file: test_file.clone(),
+            function: function.to_string(),
+            class: None,
+            line: 1,
+            decorator_line: None,
+            markers: vec![],
+            params: vec![],
+            kind: TestKind::Function,
+            rootdir: None,
+        })
+        .collect();
+
+    let options = RunOptions {
+        isolation: IsolationMode::ProcessPerRun,
+        bail_on_worker_crash: true,
+        ..RunOptions::default()
+    };
+    let err = match run_tests_with_options(&items, &options, |_| {}) {
+        Ok(results) => panic!(
+            "expected the run to abort, got {} results",
+            results.results.len()
+        ),
+        Err(err) => err,
+    };
+    let crash = err
+        .downcast_ref::<WorkerCrashed>()
+        .expect("error should be a worker crash");
+    assert!(crash.test_id.ends_with("::test_exits"), "{}", crash.test_id);
+
+    Ok(())
+}
+
+#[test]
+fn deadline_kill_stops_a_running_worker_test() -> Result<()> {
     let tmp = TempDir::new()?;
-    let test_file = tmp.path().join("test_setup_fail.py");
+    let test_file = tmp.path().join("test_deadline.py");
     write_file(
         &test_file,
-        &dedent(
-            r#"
-            class TestSetupFails:
-                def setUp(self):
-                    raise RuntimeError("setup failed")
-
-                def test_never_runs(self):
-                    assert True
-        "#,
-        ),
+        "import time\n\ndef test_hangs():\n    time.sleep(60)\n\ndef test_after():\n    assert True\n",
     )?;
 
-    let item = TestItem {
-        file: test_file,
-        function: "test_never_runs".to_string(),
-        class: Some("TestSetupFails".to_string()),
-        line: 5,
-        markers: vec![],
+    let items: Vec<_> = ["test_hangs", "test_after"]
+        .iter()
+        .map(|function| TestItem {
+            file: test_file.clone(),
+            function: function.to_string(),
+            class: None,
+            line: 1,
+            decorator_line: None,
+            markers: vec![],
+            params: vec![],
+            kind: TestKind::Function,
+            rootdir: None,
+        })
+        .collect();
+
+    let options = RunOptions {
+        isolation: IsolationMode::ProcessPerRun,
+        deadline: Some(Instant::now() + Duration::from_secs(2)),
+        kill_at_deadline: true,
+        ..RunOptions::default()
     };
-
-    let results = run_tests(
-        &[item],
-        false,
-        None,
-        false,
-        IsolationMode::ProcessPerTest,
-        |_| {},
-    )?;
-
-    assert!(!results.results[0].passed);
-    let error = results.results[0].error.as_ref().unwrap();
-    assert!(
-        error.message.contains("setup failed") || error.message.contains("RuntimeError"),
-        "Error should mention setup failure: {}",
-        error.message
-    );
+    let results = run_tests_with_options(&items, &options, |_| {})?;
+
+    assert!(results.total_duration < Duration::from_secs(30));
+    for result in &results.results {
+        assert_eq!(
+            result.skip_kind,
+            Some(SkipKind::Deadline),
+            "{}",
+            result.item.function
+        );
+    }
 
     Ok(())
 }
 
 #[test]
-fn teardown_runs_after_failure() -> Result<()> {
+fn worker_death_attaches_stderr_tail() -> Result<()> {
     let tmp = TempDir::new()?;
-    let test_file = tmp.path().join("test_teardown.py");
-    let marker_file = tmp.path().join("teardown_ran.txt");
-
+    let test_file = tmp.path().join("test_hard_exit.py");
     write_file(
         &test_file,
-        &format!(
+        &dedent(
             r#"
-class TestTeardownAfterFailure:
-    def setUp(self):
-        pass
-
-    def tearDown(self):
-        with open({:?}, 'w') as f:
-            f.write('teardown ran')
+            import os
 
-    def test_fails(self):
-        assert False
-"#,
-            marker_file
+            def test_exits():
+                os.write(2, b"fatal: extension module crashed\n")
+                os._exit(3)
+        "#,
         ),
     )?;
 
-    let item = TestItem {
-        file: test_file,
-        function: "test_fails".to_string(),
-        class: Some("TestTeardownAfterFailure".to_string()),
-        line: 10,
-        markers: vec![],
-    };
-
-    let results = run_tests(
-        &[item],
-        false,
-        None,
-        false,
-        IsolationMode::ProcessPerTest,
-        |_| {},
-    )?;
-
-    assert!(!results.results[0].passed);
-
-    // Check that tearDown ran despite test failure
+    let result = run_single_in_worker(&test_file, "test_exits")?;
+    let error = result.error.expect("test should fail");
+    assert_eq!(error.category, Some(ErrorCategory::Crash));
+    let traceback = error.traceback.expect("stderr tail should be attached");
     assert!(
-        marker_file.exists(),
-        "tearDown should run even when test fails"
+        traceback.contains("fatal: extension module crashed"),
+        "{}",
+        traceback
     );
 
     Ok(())
@@ -537,7 +1567,11 @@ fn imports_from_same_directory() -> Result<()> {
         function: "test_import".to_string(),
         class: None,
         line: 3,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -588,7 +1622,11 @@ fn imports_from_subdirectory() -> Result<()> {
         function: "test_add".to_string(),
         class: None,
         line: 3,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -609,12 +1647,201 @@ fn imports_from_subdirectory() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn relative_import_resolves_inside_package() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let pkg = tmp.path().join("pkg");
+
+    write_file(&pkg.join("__init__.py"), "")?;
+    write_file(&pkg.join("helper.py"), "VALUE = 42\n")?;
+    write_file(
+        &pkg.join("test_relative.py"),
+        &dedent(
+            r#"
+            from . import helper
+
+            def test_relative():
+                assert helper.VALUE == 42
+                assert __name__ == "pkg.test_relative"
+        "#,
+        ),
+    )?;
+
+    let item = TestItem {
+        file: pkg.join("test_relative.py"),
+        function: "test_relative".to_string(),
+        class: None,
+        line: 3,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    for isolation in [IsolationMode::ProcessPerTest, IsolationMode::ProcessPerRun] {
+        let results = run_tests(
+            std::slice::from_ref(&item),
+            false,
+            None,
+            false,
+            isolation,
+            |_| {},
+        )?;
+        assert!(
+            results.results[0].passed,
+            "{:?}: {:?}",
+            isolation, results.results[0].error
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn conftest_fixtures_are_injected_by_parameter_name() -> Result<()> {
+    let tmp = TempDir::new()?;
+
+    write_file(
+        &tmp.path().join("conftest.py"),
+        &dedent(
+            r#"
+            from taut import fixture
+
+            @fixture
+            def db():
+                return {"users": ["alice"]}
+
+            @fixture()
+            def user(db):
+                return db["users"][0]
+
+            @fixture
+            def broken():
+                raise RuntimeError("no connection")
+        "#,
+        ),
+    )?;
+    write_file(
+        &tmp.path().join("tests/test_db.py"),
+        &dedent(
+            r#"
+            def test_user(user, db):
+                assert user == "alice"
+                assert db == {"users": ["alice"]}
+
+            def test_broken(broken):
+                pass
+        "#,
+        ),
+    )?;
+
+    let item = |function: &str, line| TestItem {
+        file: tmp.path().join("tests/test_db.py"),
+        function: function.to_string(),
+        class: None,
+        line,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: Some(tmp.path().to_path_buf()),
+    };
+    let items = [item("test_user", 2), item("test_broken", 6)];
+
+    for isolation in [IsolationMode::ProcessPerTest, IsolationMode::ProcessPerRun] {
+        let results = run_tests(&items, false, None, false, isolation, |_| {})?;
+
+        assert!(
+            results.results[0].passed,
+            "{:?}: {:?}",
+            isolation, results.results[0].error
+        );
+
+        let broken = &results.results[1];
+        assert!(!broken.passed && broken.errored, "{isolation:?}");
+        let error = broken.error.as_ref().unwrap();
+        assert_eq!(
+            error.fixture,
+            Some(FixtureFailure {
+                name: "broken".to_string(),
+                phase: FixturePhase::Setup,
+            })
+        );
+        assert!(error.message.contains("no connection"), "{}", error.message);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn rootdir_import_mode_allows_imports_from_project_root() -> Result<()> {
+    let tmp = TempDir::new()?;
+
+    write_file(&tmp.path().join("app/__init__.py"), "")?;
+    write_file(&tmp.path().join("app/helpers.py"), "VALUE = 42\n")?;
+    write_file(
+        &tmp.path().join("tests/test_app.py"),
+        &dedent(
+            r#"
+            from app.helpers import VALUE
+
+            def test_value():
+                assert VALUE == 42
+        "#,
+        ),
+    )?;
+
+    let item = TestItem {
+        file: tmp.path().join("tests/test_app.py"),
+        function: "test_value".to_string(),
+        class: None,
+        line: 3,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: Some(tmp.path().canonicalize()?),
+    };
+
+    for isolation in [IsolationMode::ProcessPerTest, IsolationMode::ProcessPerRun] {
+        let run = |import_mode| {
+            let options = RunOptions {
+                parallel: false,
+                isolation,
+                import_mode,
+                ..RunOptions::default()
+            };
+            run_tests_with_options(std::slice::from_ref(&item), &options, |_| {})
+        };
+
+        let rootdir = run(ImportMode::Rootdir)?;
+        assert!(
+            rootdir.results[0].passed,
+            "{:?}: {:?}",
+            isolation, rootdir.results[0].error
+        );
+
+        // Only tests/ is on sys.path, so the app package isn't found
+        let prepend = run(ImportMode::Prepend)?;
+        let error = prepend.results[0]
+            .error
+            .as_ref()
+            .expect("app shouldn't be importable");
+        assert_eq!(
+            error.category,
+            Some(ErrorCategory::Import),
+            "{:?}",
+            isolation
+        );
+    }
+
+    Ok(())
+}
+
 #[test]
 fn relative_import_fails_gracefully() -> Result<()> {
-    // BUG: Relative imports crash the worker because the module
-    // doesn't have __package__ set.
-    //
-    // This test verifies the error is captured gracefully.
+    // Without an __init__.py there's no package for the import to be relative to
 
     let tmp = TempDir::new()?;
 
@@ -636,7 +1863,11 @@ fn relative_import_fails_gracefully() -> Result<()> {
         function: "test_relative".to_string(),
         class: None,
         line: 3,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -658,6 +1889,7 @@ fn relative_import_fails_gracefully() -> Result<()> {
         "Should fail with import error: {}",
         error.message
     );
+    assert!(error.message.contains("__init__.py"), "{}", error.message);
 
     Ok(())
 }
@@ -683,7 +1915,11 @@ fn import_error_captured() -> Result<()> {
         function: "test_never_runs".to_string(),
         class: None,
         line: 3,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -742,7 +1978,11 @@ fn module_state_isolated_between_tests_process_per_test() -> Result<()> {
         function: "test_first".to_string(),
         class: None,
         line: 3,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let item2 = TestItem {
@@ -750,7 +1990,11 @@ fn module_state_isolated_between_tests_process_per_test() -> Result<()> {
         function: "test_second".to_string(),
         class: None,
         line: 6,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     // Run with process-per-test - each should get fresh state
@@ -778,11 +2022,9 @@ fn module_state_isolated_between_tests_process_per_test() -> Result<()> {
 }
 
 #[test]
-fn module_state_may_leak_in_process_per_run() -> Result<()> {
-    // BUG: In process-per-run mode, modules are cached in sys.modules
-    // Global state leaks between tests.
-    //
-    // This test documents the issue.
+fn module_state_is_reset_in_process_per_run() -> Result<()> {
+    // Workers drop project modules from sys.modules after each test, so
+    // module-level state doesn't leak into the next test.
 
     let tmp = TempDir::new()?;
 
@@ -801,7 +2043,7 @@ fn module_state_may_leak_in_process_per_run() -> Result<()> {
                 assert increment() == 1
 
             def test_second():
-                # In process-per-run, this would be 2 if state leaks
+                # Would be 2 if state leaked from test_first
                 assert increment() == 1
         "#,
         ),
@@ -812,7 +2054,11 @@ fn module_state_may_leak_in_process_per_run() -> Result<()> {
         function: "test_first".to_string(),
         class: None,
         line: 3,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let item2 = TestItem {
@@ -820,10 +2066,14 @@ fn module_state_may_leak_in_process_per_run() -> Result<()> {
         function: "test_second".to_string(),
         class: None,
         line: 6,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
-    // Run with process-per-run - state MAY leak
+    // Run both tests in the same warm worker
     let results = run_tests(
         &[item1, item2],
         false, // sequential so order is deterministic
@@ -836,10 +2086,204 @@ fn module_state_may_leak_in_process_per_run() -> Result<()> {
     // First should always pass
     assert!(results.results[0].passed);
 
-    // Second might fail if state leaked (documenting the bug)
-    if !results.results[1].passed {
-        eprintln!("BUG: Module state leaked between tests in process-per-run mode");
+    // Second sees a freshly imported module
+    assert!(
+        results.results[1].passed,
+        "module state leaked: {:?}",
+        results.results[1].error
+    );
+
+    Ok(())
+}
+
+#[test]
+fn workers_are_recycled_after_max_tests() -> Result<()> {
+    let tmp = TempDir::new()?;
+
+    // Interpreter-global counter: only a fresh process resets it
+    write_file(
+        &tmp.path().join("state.py"),
+        &dedent(
+            r#"
+            import sys
+
+            def bump():
+                sys.taut_test_counter = getattr(sys, "taut_test_counter", 0) + 1
+                return sys.taut_test_counter
+        "#,
+        ),
+    )?;
+
+    let log = tmp.path().join("counts.log");
+    let mut source = String::from("import state\n");
+    for i in 0..5 {
+        source.push_str(&format!(
+            "\ndef test_{i}():\n    with open({log:?}, 'a') as f:\n        f.write(f'{{state.bump()}}\\n')\n",
+            log = log.display().to_string()
+        ));
+    }
+    let test_file = tmp.path().join("test_recycle.py");
+    write_file(&test_file, &source)?;
+
+    let items: Vec<_> = (0..5)
+        .map(|i| TestItem {
+            file: test_file.clone(),
+            function: format!("test_{i}"),
+            class: None,
+            line: 1,
+            decorator_line: None,
+            markers: vec![],
+            params: vec![],
+            kind: TestKind::Function,
+            rootdir: None,
+        })
+        .collect();
+
+    let options = RunOptions {
+        parallel: false,
+        isolation: IsolationMode::ProcessPerRun,
+        worker_max_tests: Some(2),
+        ..RunOptions::default()
+    };
+    let results = run_tests_with_options(&items, &options, |_| {})?;
+
+    assert_eq!(results.results.len(), 5);
+    for (i, result) in results.results.iter().enumerate() {
+        assert_eq!(result.item.function, format!("test_{i}"));
+        assert!(result.passed, "test_{i} failed: {:?}", result.error);
+    }
+
+    // The counter restarts every two tests, when the worker is replaced
+    let counts: Vec<String> = fs::read_to_string(&log)?
+        .lines()
+        .map(String::from)
+        .collect();
+    assert_eq!(counts, vec!["1", "2", "1", "2", "1"]);
+
+    Ok(())
+}
+
+#[test]
+fn worker_pool_size_follows_workers_option() -> Result<()> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use taut::markers::{Marker, MarkerArgs};
+
+    let tmp = TempDir::new()?;
+    let pids = tmp.path().join("pids");
+    fs::create_dir_all(&pids)?;
+
+    // Each test leaves a marker file named after the worker PID that ran it
+    let mut source = String::from("import os, time\n");
+    for i in 0..6 {
+        source.push_str(&format!(
+            "\ndef test_{i}():\n    open(os.path.join({dir:?}, str(os.getpid())), 'a').close()\n    time.sleep(0.2)\n",
+            dir = pids.display().to_string()
+        ));
+    }
+    let test_file = tmp.path().join("test_pool.py");
+    write_file(&test_file, &source)?;
+
+    let parallel = Marker {
+        name: "parallel".to_string(),
+        args: MarkerArgs::default(),
+    };
+    let items: Vec<_> = (0..6)
+        .map(|i| TestItem {
+            file: test_file.clone(),
+            function: format!("test_{i}"),
+            class: None,
+            line: 1,
+            decorator_line: None,
+            markers: vec![parallel.clone()],
+            params: vec![],
+            kind: TestKind::Function,
+            rootdir: None,
+        })
+        .collect();
+
+    // --workers wins over -j
+    let options = RunOptions {
+        jobs: Some(4),
+        workers: Some(2),
+        isolation: IsolationMode::ProcessPerRun,
+        ..RunOptions::default()
+    };
+    let completed = AtomicUsize::new(0);
+    let results = run_tests_with_options(&items, &options, |_| {
+        completed.fetch_add(1, Ordering::SeqCst);
+    })?;
+
+    assert_eq!(completed.load(Ordering::SeqCst), 6);
+    assert!(results.results.iter().all(|r| r.passed));
+    assert_eq!(fs::read_dir(&pids)?.count(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn file_affinity_keeps_each_file_on_one_worker() -> Result<()> {
+    use taut::markers::{Marker, MarkerArgs};
+
+    let tmp = TempDir::new()?;
+    let parallel = Marker {
+        name: "parallel".to_string(),
+        args: MarkerArgs::default(),
+    };
+
+    let mut items = Vec::new();
+    for name in ["test_a.py", "test_b.py"] {
+        let mut source = String::from("import os, time\n");
+        for i in 0..3 {
+            source.push_str(&format!(
+                "\ndef test_{i}():\n    print(os.getpid())\n    time.sleep(0.2)\n"
+            ));
+        }
+        let test_file = tmp.path().join(name);
+        write_file(&test_file, &source)?;
+        items.push(
+            (0..3)
+                .map(|i| TestItem {
+                    file: test_file.clone(),
+                    function: format!("test_{i}"),
+                    class: None,
+                    line: 1,
+                    decorator_line: None,
+                    markers: vec![parallel.clone()],
+                    params: vec![],
+                    kind: TestKind::Function,
+                    rootdir: None,
+                })
+                .collect::<Vec<_>>(),
+        );
     }
+    // Interleave the files, so taking tests in order would mix them across workers
+    let items: Vec<_> = items[0]
+        .iter()
+        .zip(&items[1])
+        .flat_map(|(a, b)| [a.clone(), b.clone()])
+        .collect();
+
+    let options = RunOptions {
+        workers: Some(2),
+        isolation: IsolationMode::ProcessPerRun,
+        file_affinity: true,
+        ..RunOptions::default()
+    };
+    let results = run_tests_with_options(&items, &options, |_| {})?;
+    assert!(results.results.iter().all(|r| r.passed));
+
+    let pids_of = |file: &str| -> Vec<String> {
+        results
+            .results
+            .iter()
+            .filter(|r| r.item.file.ends_with(file))
+            .map(|r| r.stdout.clone().unwrap_or_default().trim().to_string())
+            .collect()
+    };
+    let (a, b) = (pids_of("test_a.py"), pids_of("test_b.py"));
+    assert!(a.iter().all(|pid| pid == &a[0]), "test_a.py ran on {:?}", a);
+    assert!(b.iter().all(|pid| pid == &b[0]), "test_b.py ran on {:?}", b);
+    assert_ne!(a[0], b[0]);
 
     Ok(())
 }
@@ -871,7 +2315,11 @@ fn coverage_collected_for_test_file() -> Result<()> {
         function: "test_with_helper".to_string(),
         class: None,
         line: 5,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -936,7 +2384,11 @@ fn coverage_collected_for_imported_file() -> Result<()> {
         function: "test_add".to_string(),
         class: None,
         line: 3,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -965,6 +2417,80 @@ fn coverage_collected_for_imported_file() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn coverage_exclude_drops_matching_files() -> Result<()> {
+    let tmp = TempDir::new()?;
+
+    write_file(
+        &tmp.path().join("generated/foo.py"),
+        &dedent(
+            r#"
+            def answer():
+                return 42
+        "#,
+        ),
+    )?;
+
+    write_file(
+        &tmp.path().join("test_codegen.py"),
+        &dedent(
+            r#"
+            from generated.foo import answer
+
+            def test_answer():
+                assert answer() == 42
+        "#,
+        ),
+    )?;
+
+    let item = TestItem {
+        file: tmp.path().join("test_codegen.py"),
+        function: "test_answer".to_string(),
+        class: None,
+        line: 3,
+        decorator_line: None,
+        markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
+    };
+
+    for isolation in [IsolationMode::ProcessPerTest, IsolationMode::ProcessPerRun] {
+        let options = RunOptions {
+            parallel: false,
+            collect_coverage: true,
+            isolation,
+            coverage_exclude: vec!["generated".to_string()],
+            ..RunOptions::default()
+        };
+        let results = run_tests_with_options(std::slice::from_ref(&item), &options, |_| {})?;
+
+        let result = &results.results[0];
+        assert!(result.passed, "{:?}: {:?}", isolation, result.error);
+        let coverage = result.coverage.as_ref().unwrap();
+        let covered = |name: &str| {
+            coverage
+                .files
+                .keys()
+                .any(|path| path.to_string_lossy().contains(name))
+        };
+        assert!(
+            covered("test_codegen.py"),
+            "{:?}: {:?}",
+            isolation,
+            coverage
+        );
+        assert!(
+            !covered("generated/foo.py"),
+            "{:?}: excluded file was covered: {:?}",
+            isolation,
+            coverage
+        );
+    }
+
+    Ok(())
+}
+
 #[test]
 fn coverage_excludes_stdlib() -> Result<()> {
     let tmp = TempDir::new()?;
@@ -988,7 +2514,11 @@ fn coverage_excludes_stdlib() -> Result<()> {
         function: "test_uses_stdlib".to_string(),
         class: None,
         line: 5,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -1054,7 +2584,11 @@ fn coverage_works_in_async_test() -> Result<()> {
         function: "test_async_coverage".to_string(),
         class: None,
         line: 5,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -1108,7 +2642,11 @@ fn syntax_error_in_test_file_captured() -> Result<()> {
         function: "test_broken".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -1145,7 +2683,11 @@ fn test_function_not_found_captured() -> Result<()> {
         function: "test_does_not_exist".to_string(),
         class: None,
         line: 1,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(
@@ -1194,7 +2736,11 @@ fn test_duration_tracked() -> Result<()> {
         function: "test_takes_time".to_string(),
         class: None,
         line: 3,
+        decorator_line: None,
         markers: vec![],
+        params: vec![],
+        kind: TestKind::Function,
+        rootdir: None,
     };
 
     let results = run_tests(